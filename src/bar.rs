@@ -0,0 +1,322 @@
+//! `mprisence bar`: the now-playing line for status bars, streamed on
+//! stdout whenever it changes. Players are discovered, deduplicated and
+//! rendered the same way as for Discord, using the `[bar]` templates.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use log::{debug, trace, warn};
+use mpris::PlaybackStatus;
+use serde_json::json;
+use smol_str::SmolStr;
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    config::{schema::FilterAction, ConfigChange, ConfigManager},
+    error::MprisenceError,
+    metadata::{MediaMetadata, MetadataSource, ResolvedTrack},
+    mpd,
+    player::{discovery, file_resolver::FileResolutions, PlayerIdentifier},
+    template::{BarTexts, TemplateManager},
+};
+
+/// Floor for `[bar] interval`, so a typo cannot spin on D-Bus.
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BarFormat {
+    /// JSON for a waybar `custom` module with `return-type = "json"`
+    Waybar,
+    /// Plain text for a polybar `custom/script` module with `tail = true`
+    Polybar,
+    /// Plain text for an i3blocks block with `interval=persist`
+    I3blocks,
+}
+
+/// The player shown in the bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarItem {
+    /// Discovery key, used to keep showing the same player on ties.
+    pub key: SmolStr,
+    pub player_bus_name: SmolStr,
+    pub playback_status: PlaybackStatus,
+    pub texts: BarTexts,
+}
+
+impl BarFormat {
+    /// One output line for `item`; `None` renders the empty bar.
+    pub fn format_line(self, item: Option<&BarItem>) -> String {
+        match self {
+            BarFormat::Waybar => {
+                let value = match item {
+                    Some(item) => json!({
+                        "text": escape_markup(&item.texts.text),
+                        "tooltip": escape_markup(&item.texts.tooltip),
+                        "alt": item.player_bus_name.as_str(),
+                        "class": status_class(item.playback_status),
+                    }),
+                    None => json!({
+                        "text": "",
+                        "tooltip": "",
+                        "class": status_class(PlaybackStatus::Stopped),
+                    }),
+                };
+                value.to_string()
+            }
+            BarFormat::Polybar | BarFormat::I3blocks => item
+                .map(|item| single_line(&item.texts.text))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn status_class(status: PlaybackStatus) -> &'static str {
+    match status {
+        PlaybackStatus::Playing => "playing",
+        PlaybackStatus::Paused => "paused",
+        PlaybackStatus::Stopped => "stopped",
+    }
+}
+
+/// Waybar parses module text as Pango markup.
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Line-based bars read one update per line.
+fn single_line(text: &str) -> String {
+    text.split(['\n', '\r'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Order in which players are shown: playing before paused. Stopped
+/// players are not shown at all.
+fn status_rank(status: PlaybackStatus) -> Option<u8> {
+    match status {
+        PlaybackStatus::Playing => Some(0),
+        PlaybackStatus::Paused => Some(1),
+        PlaybackStatus::Stopped => None,
+    }
+}
+
+/// Pick the key to show among `(key, status)` pairs. On equal status the
+/// player already shown (`current`) wins, so two playing players do not
+/// flip the bar back and forth.
+fn choose(players: &[(SmolStr, PlaybackStatus)], current: Option<&SmolStr>) -> Option<SmolStr> {
+    players
+        .iter()
+        .filter_map(|(key, status)| status_rank(*status).map(|rank| (rank, key)))
+        .min_by(|(rank_a, key_a), (rank_b, key_b)| {
+            rank_a
+                .cmp(rank_b)
+                .then_with(|| (Some(*key_b) == current).cmp(&(Some(*key_a) == current)))
+                .then_with(|| key_a.cmp(key_b))
+        })
+        .map(|(_, key)| key.clone())
+}
+
+/// Scan players and render the one to show. Blocks on D-Bus, so it runs
+/// on a blocking thread.
+fn sample(
+    config: &ConfigManager,
+    templates: &TemplateManager,
+    resolutions: &FileResolutions,
+    current: Option<&SmolStr>,
+) -> Result<Option<BarItem>, MprisenceError> {
+    let winners = discovery::find_players(config, &HashMap::new())?;
+    resolutions.retain(|key| winners.contains_key(key));
+
+    let statuses: Vec<(SmolStr, PlaybackStatus)> = winners
+        .iter()
+        .filter_map(|(key, player)| Some((key.clone(), player.get_playback_status().ok()?)))
+        .collect();
+    let Some(key) = choose(&statuses, current) else {
+        return Ok(None);
+    };
+    let player = &winners[&key];
+    let playback_status = statuses
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, status)| *status)
+        .unwrap_or(PlaybackStatus::Playing);
+
+    let metadata = match player.get_metadata() {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            debug!("Failed to read metadata for {}: {}", key, e);
+            return Ok(None);
+        }
+    };
+    let id = PlayerIdentifier::from(player);
+    let resolved_url = resolutions.resolve_url_blocking(&key, config, player, &metadata);
+    let source = MetadataSource::from_mpris_mapped(metadata, resolved_url, &config.path_map());
+    let ResolvedTrack {
+        metadata: mut media_metadata,
+        url,
+        mut player_config,
+    } = MediaMetadata::resolve(&source, config, &id.identity, &id.player_bus_name);
+    let filter = media_metadata.apply_filter(config, url.as_deref(), &mut player_config);
+    if filter.is_some_and(|rule| rule.action == FilterAction::Hide) {
+        debug!(
+            "Hiding {} from the bar - track matched a [[filter]] rule",
+            key
+        );
+        return Ok(None);
+    }
+
+    let texts =
+        templates.render_bar_texts(player, playback_status, media_metadata, &player_config)?;
+    Ok(Some(BarItem {
+        key,
+        player_bus_name: id.player_bus_name,
+        playback_status,
+        texts,
+    }))
+}
+
+fn scan_ticker(config: &ConfigManager) -> tokio::time::Interval {
+    let period = Duration::from_millis(config.bar_config().interval).max(MIN_INTERVAL);
+    let mut ticker = tokio::time::interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker
+}
+
+/// Print a line for the current player, then another every time it
+/// changes. Runs until stdout is closed, i.e. the bar went away.
+pub async fn run(config: Arc<ConfigManager>, format: BarFormat) -> Result<(), MprisenceError> {
+    // The bar runs on its own, so it follows MPD itself rather than
+    // through the daemon.
+    mpd::spawn(config.clone());
+    let mut templates = Arc::new(TemplateManager::new(&config)?);
+    let mut changes = config.subscribe();
+    let mut ticker = scan_ticker(&config);
+    let resolutions = Arc::new(FileResolutions::default());
+    let mut current: Option<SmolStr> = None;
+    let mut last_line: Option<String> = None;
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let sampler_config = config.clone();
+                let sampler_templates = templates.clone();
+                let sampler_resolutions = resolutions.clone();
+                let sampler_current = current.clone();
+                let item = tokio::task::spawn_blocking(move || {
+                    sample(
+                        &sampler_config,
+                        &sampler_templates,
+                        &sampler_resolutions,
+                        sampler_current.as_ref(),
+                    )
+                })
+                .await;
+                let item = match item {
+                    Ok(Ok(item)) => item,
+                    Ok(Err(e)) => {
+                        warn!("Failed to read players: {}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("Bar sampling task failed: {}", e);
+                        continue;
+                    }
+                };
+                current = item.as_ref().map(|item| item.key.clone());
+                let line = format.format_line(item.as_ref());
+                if last_line.as_deref() != Some(line.as_str()) {
+                    trace!("Bar line changed: {}", line);
+                    let mut stdout = io::stdout();
+                    if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+                        debug!("stdout closed, stopping");
+                        return Ok(());
+                    }
+                    last_line = Some(line);
+                }
+            },
+            Ok(change) = changes.recv() => {
+                if matches!(change, ConfigChange::Reloaded) {
+                    match TemplateManager::new(&config) {
+                        Ok(manager) => {
+                            templates = Arc::new(manager);
+                            // Re-render even if the player state is unchanged.
+                            last_line = None;
+                        }
+                        Err(e) => warn!("Keeping previous bar templates: {}", e),
+                    }
+                    ticker = scan_ticker(&config);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn item(text: &str) -> BarItem {
+        BarItem {
+            key: SmolStr::new("spotify"),
+            player_bus_name: SmolStr::new("spotify"),
+            playback_status: PlaybackStatus::Paused,
+            texts: BarTexts {
+                text: text.to_string(),
+                tooltip: "Tom & Jerry\n<b>".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn formats_waybar_json_with_escaped_markup() {
+        let line = BarFormat::Waybar.format_line(Some(&item("⏸️ Simon & Garfunkel")));
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["text"], "⏸️ Simon &amp; Garfunkel");
+        assert_eq!(value["tooltip"], "Tom &amp; Jerry\n&lt;b&gt;");
+        assert_eq!(value["class"], "paused");
+        assert_eq!(value["alt"], "spotify");
+
+        let empty: Value = serde_json::from_str(&BarFormat::Waybar.format_line(None)).unwrap();
+        assert_eq!(empty["text"], "");
+        assert_eq!(empty["class"], "stopped");
+    }
+
+    #[test]
+    fn text_formats_print_a_single_line() {
+        assert_eq!(
+            BarFormat::Polybar.format_line(Some(&item("Title\n Artist "))),
+            "Title Artist"
+        );
+        assert_eq!(BarFormat::I3blocks.format_line(None), "");
+    }
+
+    #[test]
+    fn prefers_playing_then_the_player_already_shown() {
+        let players = vec![
+            (SmolStr::new("a"), PlaybackStatus::Paused),
+            (SmolStr::new("b"), PlaybackStatus::Playing),
+            (SmolStr::new("c"), PlaybackStatus::Playing),
+            (SmolStr::new("d"), PlaybackStatus::Stopped),
+        ];
+        assert_eq!(choose(&players, None).as_deref(), Some("b"));
+        assert_eq!(
+            choose(&players, Some(&SmolStr::new("c"))).as_deref(),
+            Some("c")
+        );
+        assert_eq!(
+            choose(&players[..1], Some(&SmolStr::new("b"))).as_deref(),
+            Some("a")
+        );
+        assert_eq!(choose(&players[3..], None), None);
+    }
+}
@@ -1,8 +1,11 @@
 use crate::{
+    bar::{self, BarFormat},
     config::{
         get_config,
-        schema::{ActivityType, PlayerConfig, StatusDisplayType, WebPlayerConfig},
+        schema::{ActivityType, PathRewrite, PlayerConfig, StatusDisplayType, WebPlayerConfig},
+        ConfigManager,
     },
+    control::{self, protocol::DaemonStatus, ControlError},
     error::Error,
     player::{
         canonical_player_bus_name, is_playerctld_no_active_error, select_winner_idx,
@@ -46,6 +49,26 @@ pub enum Command {
         #[command(subcommand)]
         command: Option<VersionCommand>,
     },
+    /// Control the running daemon over its local socket
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+    /// Inspect and manage the cover art cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Set up and inspect scrobbling
+    Scrobble {
+        #[command(subcommand)]
+        command: ScrobbleCommand,
+    },
+    /// Stream the now-playing line for a status bar
+    Bar {
+        #[arg(long, value_enum, default_value_t = BarFormat::Waybar)]
+        format: BarFormat,
+    },
 }
 
 #[derive(Subcommand)]
@@ -84,6 +107,69 @@ pub enum PlayersCommand {
     },
 }
 
+#[derive(Subcommand)]
+pub enum CtlCommand {
+    /// Show tracked players and their current activity
+    Status {
+        /// Print the raw JSON reply
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-render and re-push one player, or all players
+    Refresh { player: Option<String> },
+    /// Clear one player's activity until its next update
+    Clear { player: String },
+    /// Hide all activities without stopping the daemon
+    Pause {
+        /// Resume automatically after this long (e.g. 30m, 1h30m)
+        #[arg(long = "for", value_name = "DURATION")]
+        duration: Option<String>,
+    },
+    /// Resume publishing activities
+    Resume,
+    /// Pause if running, resume if paused
+    Toggle,
+    /// Reload the configuration file
+    Reload,
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// List cached covers
+    List,
+    /// Show one entry; a unique key prefix is enough
+    Show { key: String },
+    /// Remove entries, or everything when no filter is given
+    Purge {
+        /// Only entries from this provider
+        #[arg(long)]
+        provider: Option<String>,
+        /// Only entries stored longer ago than this (e.g. 12h, 7d)
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<String>,
+        /// Only expired or unreadable entries and orphaned image data
+        #[arg(long, conflicts_with_all = ["provider", "older_than"])]
+        expired: bool,
+    },
+    /// Show entry count and disk usage
+    Stats,
+    /// Check that cached URLs still resolve
+    Verify {
+        /// Remove entries whose URL is dead
+        #[arg(long)]
+        drop: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScrobbleCommand {
+    /// Authorize with Last.fm (or the service at `api_url`) and print the
+    /// session key
+    Auth,
+    /// Show scrobbling services and queued scrobbles
+    Status,
+}
+
 #[derive(Subcommand)]
 pub enum VersionCommand {
     Validate { version: String },
@@ -91,7 +177,14 @@ pub enum VersionCommand {
 
 impl Command {
     pub fn requires_config(&self) -> bool {
-        matches!(self, Command::Players { .. } | Command::Config { .. })
+        matches!(
+            self,
+            Command::Players { .. }
+                | Command::Config { .. }
+                | Command::Cache { .. }
+                | Command::Scrobble { .. }
+                | Command::Bar { .. }
+        )
     }
 
     pub async fn execute(self) -> Result<(), Error> {
@@ -120,6 +213,7 @@ impl Command {
                         return Ok(());
                     }
 
+                    let path_map = config.path_map();
                     let mut entries = Vec::with_capacity(players.len());
                     for mut player in players {
                         player.set_dbus_timeout_ms(5000);
@@ -160,6 +254,7 @@ impl Command {
                                 title.as_deref(),
                             );
                         let web_player_match = config.matched_web_player_for_url(url.as_deref());
+                        let path_rewrite = url.as_deref().and_then(|url| path_map.rewrite(url));
 
                         entries.push(PlayerDisplay {
                             id,
@@ -171,6 +266,7 @@ impl Command {
                             album,
                             length,
                             url,
+                            path_rewrite,
                             web_player_match,
                             config: player_config,
                             allowed,
@@ -281,6 +377,8 @@ impl Command {
                     }
 
                     if detailed {
+                        let arbitration = daemon_arbitration(&config).await;
+
                         println!("\nDetails");
                         println!("{}", divider);
 
@@ -318,6 +416,9 @@ impl Command {
                             if let Some(url) = &entry.url {
                                 println!("  URL      : {}", url);
                             }
+                            if let Some(rewrite) = &entry.path_rewrite {
+                                println!("  Path Map : {}", rewrite);
+                            }
                             if let Some((key, wp)) = &entry.web_player_match {
                                 println!("  Web Player: {}", format_web_player_match(key, wp));
                             }
@@ -325,6 +426,15 @@ impl Command {
                                 "  Presence : {}",
                                 format_presence(&entry.config, entry.allowed, entry.is_duplicate)
                             );
+                            match &arbitration {
+                                Some(Ok(standings)) => {
+                                    if let Some(standing) = standings.get(&entry.player_bus_name) {
+                                        println!("  Arbitration: {}", standing);
+                                    }
+                                }
+                                Some(Err(e)) => println!("  Arbitration: unknown ({})", e),
+                                None => {}
+                            }
                             println!("  ID       : {}", entry.id);
                             println!("  Bus Name : {}", entry.player_bus_name);
 
@@ -388,6 +498,31 @@ impl Command {
                     print_key_value("state", template_config.state.as_ref());
                     print_key_value("large_text", template_config.large_text.as_ref());
                     print_key_value("small_text", template_config.small_text.as_ref());
+                    for button in template_config.buttons.iter() {
+                        print_key_value("button", format!("{} -> {}", button.label, button.url));
+                    }
+
+                    let filter_rules = config.filter_rules();
+                    if !filter_rules.is_empty() {
+                        println!("\nFilters");
+                        for (index, rule) in filter_rules.iter().enumerate() {
+                            let conditions = rule
+                                .conditions()
+                                .map(|(field, pattern)| {
+                                    format!("{} = \"{}\"", field.key(), pattern)
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            print_key_value(
+                                &format!("#{}", index),
+                                format!(
+                                    "{} ({})",
+                                    format!("{:?}", rule.action).to_lowercase(),
+                                    conditions
+                                ),
+                            );
+                        }
+                    }
 
                     let mut player_configs: Vec<(String, PlayerConfig)> =
                         config.player_configs().into_iter().collect();
@@ -425,6 +560,12 @@ impl Command {
                                     4,
                                 );
                             }
+                            if let Some(resolver) = &cfg.file_resolver {
+                                print_nested_key_value("file_resolver", resolver, 4);
+                            }
+                            for (field, source) in cfg.template.iter().flat_map(|t| t.entries()) {
+                                print_nested_key_value(&format!("template.{field}"), source, 4);
+                            }
 
                             if index + 1 < player_configs.len() {
                                 println!();
@@ -488,6 +629,9 @@ impl Command {
                                     4,
                                 );
                             }
+                            for (field, source) in cfg.template.iter().flat_map(|t| t.entries()) {
+                                print_nested_key_value(&format!("template.{field}"), source, 4);
+                            }
 
                             if index + 1 < web_player_configs.len() {
                                 println!();
@@ -522,11 +666,320 @@ impl Command {
                     println!("mprisence {}", env!("CARGO_PKG_VERSION"));
                 }
             },
+            Command::Ctl { command } => execute_ctl(command).await?,
+            Command::Cache { command } => execute_cache(command).await?,
+            Command::Scrobble { command } => execute_scrobble(command).await?,
+            Command::Bar { format } => bar::run(get_config(), format).await?,
         }
         Ok(())
     }
 }
 
+/// `[arbitration]` standings by bus name, asked from the running daemon:
+/// only it knows which player started playing last. `None` when
+/// arbitration is off.
+async fn daemon_arbitration(
+    config: &ConfigManager,
+) -> Option<Result<HashMap<String, String>, ControlError>> {
+    if !config.arbitration_config().enabled {
+        return None;
+    }
+    let status = async {
+        let reply = control::call("status", serde_json::Value::Null).await?;
+        let status: DaemonStatus = serde_json::from_value(reply)?;
+        Ok::<_, ControlError>(status)
+    };
+    Some(status.await.map(|status| {
+        status
+            .presences
+            .into_iter()
+            .filter_map(|presence| Some((presence.bus_name, presence.arbitration?)))
+            .collect()
+    }))
+}
+
+async fn execute_ctl(command: CtlCommand) -> Result<(), Error> {
+    use serde_json::{json, Value};
+
+    match command {
+        CtlCommand::Status { json } => {
+            let reply = control::call("status", Value::Null).await?;
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&reply).unwrap_or_else(|_| reply.to_string())
+                );
+                return Ok(());
+            }
+            let status: DaemonStatus =
+                serde_json::from_value(reply).map_err(crate::control::ControlError::from)?;
+
+            println!("Daemon");
+            println!("{}", "─".repeat(DIVIDER_WIDTH));
+            print_key_value("paused", format_bool(status.paused));
+            if let Some(until) = status.paused_until {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|since_epoch| since_epoch.as_secs())
+                    .unwrap_or(0);
+                print_key_value(
+                    "resumes in",
+                    crate::utils::format_duration(until.saturating_sub(now)),
+                );
+            }
+            print_key_value("players", status.presences.len());
+
+            for presence in &status.presences {
+                println!();
+                println!(
+                    "{} {}",
+                    player_config_icon(!presence.activity_set),
+                    presence.key
+                );
+                print_nested_key_value("identity", &presence.identity, 4);
+                print_nested_key_value("bus name", &presence.bus_name, 4);
+                print_nested_key_value(
+                    "status",
+                    presence.playback_status.as_deref().unwrap_or("—"),
+                    4,
+                );
+                print_nested_key_value("activity", format_bool(presence.activity_set), 4);
+                if let Some(arbitration) = &presence.arbitration {
+                    print_nested_key_value("arbitration", arbitration, 4);
+                }
+                if let Some(texts) = &presence.texts {
+                    print_nested_key_value("details", &texts.details, 4);
+                    print_nested_key_value("state", &texts.state, 4);
+                    print_nested_key_value("large_text", &texts.large_text, 4);
+                    print_nested_key_value("small_text", &texts.small_text, 4);
+                    for button in &texts.buttons {
+                        print_nested_key_value(
+                            "button",
+                            format!("{} -> {}", button.label, button.url),
+                            4,
+                        );
+                    }
+                }
+            }
+        }
+        CtlCommand::Refresh { player } => {
+            let params = player.map_or(Value::Null, |player| json!({ "player": player }));
+            let reply = control::call("refresh", params).await?;
+            let refreshed = reply["refreshed"]
+                .as_array()
+                .map(|keys| keys.len())
+                .unwrap_or(0);
+            println!("Refreshed {} player(s)", refreshed);
+        }
+        CtlCommand::Clear { player } => {
+            let reply = control::call("clear", json!({ "player": player })).await?;
+            println!(
+                "Cleared activity for {}",
+                reply["cleared"].as_str().unwrap_or(&player)
+            );
+        }
+        CtlCommand::Pause { duration } => {
+            let duration = duration
+                .as_deref()
+                .map(control::hidden::parse_duration)
+                .transpose()?;
+            let params = duration.map_or(
+                Value::Null,
+                |duration| json!({ "duration_secs": duration.as_secs() }),
+            );
+            control::call("pause", params).await?;
+            match duration {
+                Some(duration) => println!(
+                    "Presence paused for {}",
+                    crate::utils::format_duration(duration.as_secs())
+                ),
+                None => println!("Presence paused"),
+            }
+        }
+        CtlCommand::Resume => {
+            control::call("resume", Value::Null).await?;
+            println!("Presence resumed");
+        }
+        CtlCommand::Toggle => {
+            let reply = control::call("toggle", Value::Null).await?;
+            if reply["paused"].as_bool().unwrap_or(false) {
+                println!("Presence paused");
+            } else {
+                println!("Presence resumed");
+            }
+        }
+        CtlCommand::Reload => {
+            control::call("reload", Value::Null).await?;
+            println!("Configuration reloaded");
+        }
+    }
+    Ok(())
+}
+
+async fn execute_cache(command: CacheCommand) -> Result<(), Error> {
+    use crate::cover::{self, CoverManager};
+    use futures::stream::{self, StreamExt};
+    use std::time::SystemTime;
+
+    let cache = cover::open_cache(&get_config().cover_config().cache)?;
+    let now = SystemTime::now();
+
+    match command {
+        CacheCommand::List => {
+            let entries = cache.entries()?;
+            if entries.is_empty() {
+                println!("Cover cache is empty");
+                return Ok(());
+            }
+            println!("Cached covers ({})", entries.len());
+            println!("{}", create_divider());
+            for (key, entry) in &entries {
+                println!(
+                    "{} {}  {: <12} {: <12} {}",
+                    if entry.expires_at > now { "✔" } else { "✖" },
+                    truncate_value(key, CACHE_KEY_WIDTH),
+                    entry.provider,
+                    format_expiry(entry.expires_at, now),
+                    entry.url
+                );
+            }
+        }
+        CacheCommand::Show { key } => {
+            let Some((key, entry)) = cache.find(&key)? else {
+                println!("No cache entry matches '{}'", key);
+                return Ok(());
+            };
+            println!("{}", key);
+            println!("{}", create_divider());
+            print_key_value("provider", &entry.provider);
+            print_key_value("url", &entry.url);
+            print_key_value("stored", format_age(entry.stored_at, now));
+            print_key_value("validated", format_age(entry.last_validated, now));
+            print_key_value("cache expires", format_expiry(entry.expires_at, now));
+            if let Some(url_expires_at) = entry.url_expires_at {
+                print_key_value("url expires", format_expiry(url_expires_at, now));
+            }
+            match entry.data_file.as_deref() {
+                Some(name) => print_key_value(
+                    "image",
+                    format!("{} ({})", name, format_bytes(cache.data_len(&entry))),
+                ),
+                None => print_key_value("image", "—"),
+            }
+        }
+        CacheCommand::Purge {
+            provider,
+            older_than,
+            expired,
+        } => {
+            let removed = if expired {
+                cache.clean()?
+            } else {
+                let older_than = older_than
+                    .as_deref()
+                    .map(crate::control::hidden::parse_duration)
+                    .transpose()?;
+                cache.purge(|_, entry| {
+                    provider
+                        .as_deref()
+                        .is_none_or(|provider| entry.provider.eq_ignore_ascii_case(provider))
+                        && older_than.is_none_or(|age| {
+                            now.duration_since(entry.stored_at)
+                                .is_ok_and(|stored| stored >= age)
+                        })
+                })?
+            };
+            println!("Removed {} cache entr{}", removed, plural_y(removed));
+        }
+        CacheCommand::Stats => {
+            let usage = cache.usage();
+            let entries = cache.entries()?;
+            let expired = entries
+                .iter()
+                .filter(|(_, entry)| entry.expires_at <= now)
+                .count();
+            let mut by_provider: Vec<(String, usize)> = entries
+                .iter()
+                .fold(HashMap::new(), |mut counts, (_, entry)| {
+                    *counts.entry(entry.provider.clone()).or_insert(0) += 1;
+                    counts
+                })
+                .into_iter()
+                .collect();
+            by_provider.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+            println!("Cover cache");
+            println!("{}", create_divider());
+            print_key_value("directory", cache.cache_dir().display());
+            print_key_value(
+                "entries",
+                format!(
+                    "{} / {} ({} expired)",
+                    usage.entries,
+                    format_limit(cache.max_entries() as u64, |n| n.to_string()),
+                    expired
+                ),
+            );
+            print_key_value(
+                "size",
+                format!(
+                    "{} / {}",
+                    format_bytes(usage.bytes),
+                    format_limit(cache.max_bytes(), format_bytes)
+                ),
+            );
+            for (provider, count) in by_provider {
+                print_nested_key_value(&provider, count, 4);
+            }
+        }
+        CacheCommand::Verify { drop } => {
+            let entries: Vec<_> = cache
+                .entries()?
+                .into_iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .collect();
+            if entries.is_empty() {
+                println!("No live cache entries to verify");
+                return Ok(());
+            }
+            let results: Vec<(String, String, bool)> = stream::iter(entries)
+                .map(|(key, entry)| async move {
+                    let alive = CoverManager::validate_cover_url(&entry.url).await;
+                    (key, entry.url, alive)
+                })
+                .buffer_unordered(VERIFY_CONCURRENCY)
+                .collect()
+                .await;
+
+            let mut dead = 0;
+            for (key, url, alive) in &results {
+                if !alive {
+                    dead += 1;
+                    if drop {
+                        cache.remove_by_key(key)?;
+                    }
+                }
+                println!(
+                    "{} {}  {}",
+                    if *alive { "✔" } else { "✖" },
+                    truncate_value(key, CACHE_KEY_WIDTH),
+                    url
+                );
+            }
+            println!("{}", create_divider());
+            println!(
+                "{} alive, {} dead{}",
+                results.len() - dead,
+                dead,
+                if drop && dead > 0 { " (removed)" } else { "" }
+            );
+        }
+    }
+    Ok(())
+}
+
+const CACHE_KEY_WIDTH: usize = 12;
+const VERIFY_CONCURRENCY: usize = 8;
 const NAME_COLUMN_WIDTH: usize = 32;
 const TITLE_COLUMN_WIDTH: usize = 36;
 const DIVIDER_WIDTH: usize = 56;
@@ -545,6 +998,8 @@ struct PlayerDisplay {
     /// detailed view so the user can see what the runtime sees when
     /// resolving web_player overrides.
     url: Option<String>,
+    /// `[[path_map]]` rule the runtime would apply to `url`.
+    path_rewrite: Option<PathRewrite>,
     /// (key, resolved config) of the `[web_player.*]` entry that the runtime
     /// would project onto this player. None when no web_player matches.
     web_player_match: Option<(String, WebPlayerConfig)>,
@@ -556,6 +1011,76 @@ struct PlayerDisplay {
     is_duplicate: bool,
 }
 
+async fn execute_scrobble(command: ScrobbleCommand) -> Result<(), Error> {
+    use crate::scrobble::{lastfm::LastfmClient, queue::ScrobbleQueue};
+
+    let config = get_config();
+    let scrobble = config.scrobble_config();
+    match command {
+        ScrobbleCommand::Auth => {
+            let lastfm = LastfmClient::from_config(&scrobble.lastfm)?;
+            let token = lastfm.request_token().await?;
+            println!("Allow mprisence to scrobble by opening:");
+            println!(
+                "  {}",
+                lastfm.approval_url(&scrobble.lastfm.auth_url, &token)
+            );
+            println!("then press Enter.");
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+
+            let (name, key) = lastfm.fetch_session(&token).await?;
+            println!(
+                "Authorized as {}. Add this under [scrobble.lastfm] in {}:",
+                name,
+                config.config_path().display()
+            );
+            println!("  session_key = \"{}\"", key);
+        }
+        ScrobbleCommand::Status => {
+            println!("Scrobbling");
+            println!("{}", create_divider());
+            let lastfm = &scrobble.lastfm;
+            let listenbrainz = &scrobble.listenbrainz;
+            let services = [
+                (
+                    "lastfm",
+                    lastfm.enabled,
+                    lastfm.session_key.is_some(),
+                    &lastfm.api_url,
+                ),
+                (
+                    "listenbrainz",
+                    listenbrainz.enabled,
+                    listenbrainz.token.is_some(),
+                    &listenbrainz.api_url,
+                ),
+            ];
+            for (name, enabled, authorized, api_url) in services {
+                print_key_value(
+                    name,
+                    if !enabled {
+                        "disabled".to_string()
+                    } else if !authorized {
+                        "enabled, not authorized".to_string()
+                    } else {
+                        format!("enabled ({})", api_url)
+                    },
+                );
+                if let Some(path) = ScrobbleQueue::default_path(name) {
+                    let queue = ScrobbleQueue::load(path, scrobble.max_queue);
+                    print_nested_key_value(
+                        "queued",
+                        format!("{} ({})", queue.len(), queue.path().display()),
+                        2,
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn create_divider() -> String {
     "─".repeat(DIVIDER_WIDTH)
 }
@@ -698,6 +1223,52 @@ fn print_key_value_with_indent(indent: usize, key: &str, value: impl std::fmt::D
     );
 }
 
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn format_limit(limit: u64, format: impl Fn(u64) -> String) -> String {
+    if limit == 0 {
+        "unlimited".to_string()
+    } else {
+        format(limit)
+    }
+}
+
+fn format_expiry(at: std::time::SystemTime, now: std::time::SystemTime) -> String {
+    match at.duration_since(now) {
+        Ok(left) => format!("in {}", crate::utils::format_duration(left.as_secs())),
+        Err(_) => "expired".to_string(),
+    }
+}
+
+fn format_age(at: std::time::SystemTime, now: std::time::SystemTime) -> String {
+    if at == std::time::SystemTime::UNIX_EPOCH {
+        return "—".to_string();
+    }
+    let age = now.duration_since(at).unwrap_or_default();
+    format!("{} ago", crate::utils::format_duration(age.as_secs()))
+}
+
+fn plural_y(count: usize) -> &'static str {
+    if count == 1 {
+        "y"
+    } else {
+        "ies"
+    }
+}
+
 fn format_bool(value: bool) -> &'static str {
     if value {
         "true"
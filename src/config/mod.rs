@@ -54,6 +54,7 @@ impl ConfigManager {
     #[allow(dead_code)]
     pub fn new_with_config(mut config: Config) -> Self {
         config.precompile_patterns();
+        config.label_template_sets();
         let (tx, _) = broadcast::channel(16);
 
         Self {
@@ -210,6 +211,99 @@ impl ConfigManager {
             .clone()
     }
 
+    pub fn scrobble_config(&self) -> schema::ScrobbleConfig {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .scrobble
+            .clone()
+    }
+
+    pub fn bar_config(&self) -> schema::BarConfig {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .bar
+            .clone()
+    }
+
+    pub fn arbitration_config(&self) -> schema::ArbitrationConfig {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .arbitration
+            .clone()
+    }
+
+    pub fn path_map(&self) -> schema::PathMap {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .compiled_path_map
+            .clone()
+    }
+
+    pub fn mpd_config(&self) -> schema::MpdConfig {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .mpd
+            .clone()
+    }
+
+    pub fn lyrics_config(&self) -> schema::LyricsConfig {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .lyrics
+            .clone()
+    }
+
+    pub fn progress_config(&self) -> schema::ProgressConfig {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .progress
+            .clone()
+    }
+
+    pub fn sink_configs(&self) -> Vec<schema::SinkConfig> {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .sink
+            .clone()
+    }
+
+    pub fn filter_rules(&self) -> Vec<schema::FilterRule> {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .filter
+            .clone()
+    }
+
+    pub fn match_filter(&self, subject: &schema::FilterSubject<'_>) -> Option<schema::FilterRule> {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .match_filter(subject)
+            .cloned()
+    }
+
+    /// Every labelled template set, see `Config::label_template_sets`.
+    pub fn template_sets(
+        &self,
+    ) -> Vec<(
+        Option<schema::TemplateConfigLayer>,
+        Option<Vec<schema::ButtonTemplate>>,
+    )> {
+        self.config
+            .read()
+            .expect("Failed to read config: RwLock poisoned")
+            .template_sets()
+    }
+
     pub fn player_configs(&self) -> HashMap<String, schema::PlayerConfig> {
         self.config
             .read()
@@ -465,6 +559,7 @@ pub(crate) fn load_config_from_file(path: &Path) -> Result<Config, ConfigError>
     config.user_web_player = load_user_web_player_configs(path)?;
     config.rebuild_merged_web_player();
     config.precompile_patterns();
+    config.label_template_sets();
     Ok(config)
 }
 
@@ -480,6 +575,7 @@ pub(crate) fn parse_config_str(user_toml: &str) -> Result<Config, ConfigError> {
         .merge(Toml::string(user_toml));
     let mut config: Config = figment.extract().map_err(ConfigError::from)?;
     config.precompile_patterns();
+    config.label_template_sets();
     Ok(config)
 }
 
@@ -720,6 +816,203 @@ icon = "user-icon"
         assert!(!config.template.details.is_empty(), "defaults merged in");
     }
 
+    #[test]
+    fn filter_rules_load_alongside_defaults() {
+        let config = parse_config_str(
+            r#"
+[[filter]]
+artist = "Secret Band"
+action = "redact"
+redact = ["title", "cover"]
+"#,
+        )
+        .expect("valid toml");
+        let artists = vec!["Secret Band".to_string()];
+        let subject = schema::FilterSubject {
+            artists: &artists,
+            ..Default::default()
+        };
+        let rule = config.match_filter(&subject).expect("rule should match");
+        assert!(rule.redacts(schema::RedactField::Cover));
+        assert!(!rule.redacts(schema::RedactField::Album));
+    }
+
+    #[test]
+    fn player_cover_overrides_global_provider_list() {
+        let temp_dir = temp_config_dir();
+        let config_path = temp_dir.join("config.toml");
+
+        fs::write(
+            &config_path,
+            r#"
+[player.spotify.cover]
+provider = ["itunes", "deezer"]
+strategy = "race"
+"#,
+        )
+        .expect("failed to write config");
+
+        let config = load_config_from_file(&config_path).expect("config should load");
+        assert_eq!(
+            config.cover.provider.strategy,
+            schema::CoverStrategy::Sequential
+        );
+        let cover = config
+            .get_player_config("spotify", "spotify")
+            .cover
+            .expect("player cover table");
+        assert_eq!(
+            cover.provider,
+            Some(vec!["itunes".to_string(), "deezer".to_string()])
+        );
+        assert_eq!(cover.strategy, Some(schema::CoverStrategy::Race));
+        assert!(config.get_player_config("vlc", "vlc").cover.is_none());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn scrobble_section_defaults_to_disabled_lastfm() {
+        let config = parse_config_str("").expect("empty config should parse");
+        assert!(!config.scrobble.lastfm.enabled);
+        assert_eq!(
+            config.scrobble.lastfm.api_url,
+            "https://ws.audioscrobbler.com/2.0/"
+        );
+
+        let config = parse_config_str(
+            r#"
+[scrobble]
+max_queue = 10
+
+[scrobble.lastfm]
+enabled = true
+api_url = "https://libre.fm/2.0/"
+session_key = "sk"
+"#,
+        )
+        .expect("scrobble config should parse");
+        assert_eq!(config.scrobble.max_queue, 10);
+        assert!(config.scrobble.lastfm.enabled);
+        assert_eq!(config.scrobble.lastfm.api_url, "https://libre.fm/2.0/");
+        assert_eq!(config.scrobble.lastfm.session_key.as_deref(), Some("sk"));
+    }
+
+    #[test]
+    fn sink_entries_parse_by_type() {
+        let config = parse_config_str(
+            r#"
+[[sink]]
+type = "jsonl"
+
+[[sink]]
+type = "webhook"
+url = "http://localhost:8123/hook"
+headers = { Authorization = "Bearer t" }
+
+[[sink]]
+type = "http"
+
+[[sink]]
+type = "http"
+port = 9000
+allowed_origins = ["http://localhost:3000"]
+"#,
+        )
+        .expect("sink config should parse");
+        assert_eq!(
+            config.sink[0],
+            schema::SinkConfig::Jsonl {
+                path: "-".to_string()
+            }
+        );
+        match &config.sink[1] {
+            schema::SinkConfig::Webhook { url, headers } => {
+                assert_eq!(url, "http://localhost:8123/hook");
+                assert_eq!(headers["Authorization"], "Bearer t");
+            }
+            other => panic!("expected webhook sink, got {other:?}"),
+        }
+        assert_eq!(
+            config.sink[2],
+            schema::SinkConfig::Http {
+                port: 8576,
+                allowed_origins: Vec::new()
+            }
+        );
+        assert_eq!(
+            config.sink[3],
+            schema::SinkConfig::Http {
+                port: 9000,
+                allowed_origins: vec!["http://localhost:3000".to_string()]
+            }
+        );
+
+        assert!(parse_config_str("[[sink]]\ntype = \"webhook\"\n").is_err());
+    }
+
+    #[test]
+    fn arbitration_parses_rule_order() {
+        let config = parse_config_str(
+            r#"
+[arbitration]
+enabled = true
+order = ["priority", "recent"]
+priority = ["spotify"]
+losers = "hold"
+"#,
+        )
+        .expect("arbitration config should parse");
+        assert_eq!(
+            config.arbitration.order,
+            vec![
+                schema::ArbitrationRule::Priority,
+                schema::ArbitrationRule::Recent
+            ]
+        );
+        assert_eq!(config.arbitration.losers, schema::LoserAction::Hold);
+        assert!(parse_config_str("[arbitration]\norder = [\"loudest\"]\n").is_err());
+    }
+
+    #[test]
+    fn file_resolvers_are_selected_per_player() {
+        let temp_dir = temp_config_dir();
+        let config_path = temp_dir.join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+[player.deadbeef]
+file_resolver = { type = "command", command = ["deadbeef", "--nowplaying-tf", "%path%"] }
+
+[player.flatpak_player]
+file_resolver = { type = "prefix", from = "/app/music", to = "~/Music" }
+"#,
+        )
+        .expect("failed to write config");
+        let config = load_config_from_file(&config_path).expect("file resolvers should parse");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(
+            config.get_player_config("cmus", "cmus").file_resolver,
+            Some(schema::FileResolverConfig::Cmus)
+        );
+        assert_eq!(
+            config
+                .get_player_config("DeaDBeeF", "deadbeef")
+                .file_resolver
+                .unwrap()
+                .to_string(),
+            "command `deadbeef --nowplaying-tf %path%`"
+        );
+        assert!(matches!(
+            config
+                .get_player_config("flatpak_player", "flatpak_player")
+                .file_resolver,
+            Some(schema::FileResolverConfig::Prefix { .. })
+        ));
+        assert_eq!(config.get_player_config("vlc", "vlc").file_resolver, None);
+    }
+
     #[test]
     fn parse_config_str_rejects_invalid_toml() {
         assert!(parse_config_str("[template\ndetails = ").is_err());
@@ -1,6 +1,8 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 use url::Url;
 
 use crate::utils::normalize_player_identity;
@@ -49,9 +51,43 @@ const DEFAULT_TEMPLATE_SMALL_TEXT: &str = "{{{player}}}";
 const DEFAULT_COVER_FILE_NAMES: [&str; 5] = ["cover", "folder", "front", "album", "art"];
 const DEFAULT_COVER_PROVIDERS: [&str; 2] = ["catbox", "musicbrainz"];
 const DEFAULT_COVER_LOCAL_SEARCH_DEPTH: usize = 2;
+const DEFAULT_COVER_CACHE_MAX_ENTRIES: usize = 1024;
+const DEFAULT_COVER_CACHE_MAX_BYTES: u64 = 32 * 1024 * 1024;
+const DEFAULT_COVER_NORMALIZE_ENABLED: bool = true;
+const DEFAULT_COVER_NORMALIZE_MAX_DIMENSION: u32 = 512;
+const DEFAULT_COVER_NORMALIZE_QUALITY: u8 = 85;
+const DEFAULT_COVER_NORMALIZE_MAX_INPUT_BYTES: u64 = 16 * 1024 * 1024;
 const DEFAULT_MUSICBRAINZ_MIN_SCORE: u8 = 95;
+const DEFAULT_DISCOGS_MIN_SCORE: u8 = 80;
+const DEFAULT_ITUNES_MIN_SCORE: u8 = 80;
+const DEFAULT_ITUNES_COUNTRY: &str = "us";
+const DEFAULT_ITUNES_ARTWORK_SIZE: u32 = 600;
+const DEFAULT_DEEZER_MIN_SCORE: u8 = 80;
 const DEFAULT_CATBOX_USE_LITTER: bool = true;
 const DEFAULT_CATBOX_LITTER_HOURS: u8 = 24;
+const DEFAULT_HTTP_UPLOAD_FIELD: &str = "file";
+const DEFAULT_S3_REGION: &str = "us-east-1";
+const DEFAULT_S3_PREFIX: &str = "mprisence/";
+const DEFAULT_S3_PATH_STYLE: bool = true;
+const DEFAULT_S3_PRESIGN_EXPIRY: u64 = 86400;
+const DEFAULT_SCROBBLE_MAX_QUEUE: usize = 5000;
+const DEFAULT_LASTFM_API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+const DEFAULT_LASTFM_AUTH_URL: &str = "https://www.last.fm/api/auth/";
+const DEFAULT_LISTENBRAINZ_API_URL: &str = "https://api.listenbrainz.org";
+const DEFAULT_SINK_JSONL_PATH: &str = "-";
+const DEFAULT_SINK_HTTP_PORT: u16 = 8576;
+const DEFAULT_BAR_TEXT: &str =
+    "{{{status_icon}}} {{#if artist_display}}{{{artist_display}}} - {{/if}}{{{title}}}";
+const DEFAULT_BAR_TOOLTIP: &str =
+    "{{{title}}}{{#if artist_display}}\n{{{artist_display}}}{{/if}}{{#if album}}\n{{{album}}}{{/if}}\n{{{player}}}";
+const DEFAULT_BAR_INTERVAL: u64 = 1000;
+const DEFAULT_MPD_ADDRESS: &str = "localhost:6600";
+const DEFAULT_LYRICS_MIN_INTERVAL: u64 = 5000;
+const DEFAULT_PROGRESS_BAR_WIDTH: usize = 10;
+const DEFAULT_PROGRESS_BAR_FILLED: &str = "█";
+const DEFAULT_PROGRESS_BAR_EMPTY: &str = "░";
+/// SigV4 presigned URLs are capped at seven days.
+pub const MAX_S3_PRESIGN_EXPIRY: u64 = 604800;
 
 macro_rules! normalized_map_serde {
     ($mod_name:ident, $value_type:ident, $entity:literal) => {
@@ -202,6 +238,45 @@ pub struct Config {
     /// match pattern).  Populated by `precompile_patterns()`.
     #[serde(skip)]
     pub compiled_web_player_patterns: HashMap<String, Vec<CompiledPattern>>,
+
+    /// `[[filter]]` content rules, checked in order against every track.
+    #[serde(default)]
+    pub filter: Vec<FilterRule>,
+
+    /// Compiled matchers for `filter`, index-aligned. Populated by
+    /// `precompile_patterns()`.
+    #[serde(skip)]
+    pub compiled_filters: Vec<CompiledFilter>,
+
+    /// `[[path_map]]` rewrites of local paths reported by players.
+    #[serde(default)]
+    pub path_map: Vec<PathMapRule>,
+
+    /// Compiled `path_map`. Populated by `precompile_patterns()`.
+    #[serde(skip)]
+    pub compiled_path_map: PathMap,
+
+    #[serde(default)]
+    pub scrobble: ScrobbleConfig,
+
+    /// `[[sink]]` outputs that receive the rendered presence next to Discord.
+    #[serde(default)]
+    pub sink: Vec<SinkConfig>,
+
+    #[serde(default)]
+    pub bar: BarConfig,
+
+    #[serde(default)]
+    pub arbitration: ArbitrationConfig,
+
+    #[serde(default)]
+    pub mpd: MpdConfig,
+
+    #[serde(default)]
+    pub lyrics: LyricsConfig,
+
+    #[serde(default)]
+    pub progress: ProgressConfig,
 }
 
 fn default_interval() -> u64 {
@@ -241,6 +316,17 @@ impl Default for Config {
             merged_web_player: HashMap::default(),
             compiled_player_patterns: HashMap::default(),
             compiled_web_player_patterns: HashMap::default(),
+            filter: Vec::new(),
+            compiled_filters: Vec::new(),
+            path_map: Vec::new(),
+            compiled_path_map: PathMap::default(),
+            scrobble: ScrobbleConfig::default(),
+            sink: Vec::new(),
+            bar: BarConfig::default(),
+            arbitration: ArbitrationConfig::default(),
+            mpd: MpdConfig::default(),
+            lyrics: LyricsConfig::default(),
+            progress: ProgressConfig::default(),
         }
     }
 }
@@ -363,19 +449,15 @@ impl Config {
 
     /// Resolve the merged `[web_player.default]` layer and its
     /// `ignore_unmatched` policy.
+    /// `[web_player.default]` as merged and labelled by
+    /// `rebuild_merged_web_player` and `label_template_sets`.
     fn resolve_default_web_player_base(&self) -> (WebPlayerConfigLayer, bool) {
-        let mut layer = WebPlayerConfigLayer::default();
-        let mut ignore_unmatched = true;
-
-        if let Some(bundled_default) = self.bundled_web_player.get("default") {
-            ignore_unmatched = bundled_default.ignore_unmatched.unwrap_or(ignore_unmatched);
-            layer.merge_from(bundled_default.clone());
-        }
-        if let Some(user_default) = self.user_web_player.get("default") {
-            ignore_unmatched = user_default.ignore_unmatched.unwrap_or(ignore_unmatched);
-            layer.merge_from(user_default.clone());
-        }
-
+        let layer = self
+            .merged_web_player
+            .get("default")
+            .cloned()
+            .unwrap_or_default();
+        let ignore_unmatched = layer.ignore_unmatched.unwrap_or(true);
         (layer, ignore_unmatched)
     }
 
@@ -407,6 +489,53 @@ impl Config {
         self.merged_web_player = merged;
     }
 
+    /// Label every template field and button with the set it belongs to:
+    /// `player.{key}`, `bundled_player.{key}`, `web_player.{key}` or
+    /// `filter.{index}`. The template manager compiles each set once under
+    /// those names, and the labels survive layer merging, so a resolved
+    /// player config renders every field from the set that supplied it.
+    /// Must be called after `rebuild_merged_web_player()`.
+    pub fn label_template_sets(&mut self) {
+        for (prefix, layers) in [
+            ("bundled_player", &mut self.bundled_player),
+            ("player", &mut self.user_player),
+        ] {
+            for (key, layer) in layers.iter_mut() {
+                label_template_set(
+                    &format!("{prefix}.{key}"),
+                    layer.template.as_mut(),
+                    layer.buttons.as_deref_mut(),
+                );
+            }
+        }
+        for (key, layer) in self.merged_web_player.iter_mut() {
+            label_template_set(
+                &format!("web_player.{key}"),
+                layer.template.as_mut(),
+                layer.buttons.as_deref_mut(),
+            );
+        }
+        for (index, rule) in self.filter.iter_mut().enumerate() {
+            label_template_set(&format!("filter.{index}"), rule.template.as_mut(), None);
+        }
+    }
+
+    /// Every template table and button list labelled by
+    /// `label_template_sets`, for the template manager to compile.
+    pub fn template_sets(&self) -> Vec<(Option<TemplateConfigLayer>, Option<Vec<ButtonTemplate>>)> {
+        let players = self
+            .bundled_player
+            .values()
+            .chain(self.user_player.values())
+            .map(|layer| (layer.template.clone(), layer.buttons.clone()));
+        let web_players = self
+            .merged_web_player
+            .values()
+            .map(|layer| (layer.template.clone(), layer.buttons.clone()));
+        let filters = self.filter.iter().map(|rule| (rule.template.clone(), None));
+        players.chain(web_players).chain(filters).collect()
+    }
+
     /// Pre-compile all player and web_player patterns so repeated matching
     /// avoids per-call `Regex::new()` overhead. Must be called after
     /// `rebuild_merged_web_player()` and every config reload.
@@ -445,6 +574,44 @@ impl Config {
             self.compiled_web_player_patterns
                 .insert(key.clone(), compiled);
         }
+
+        // --- filter rules ---
+        self.compiled_filters = self
+            .filter
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let conditions: Vec<(FilterField, CompiledPattern)> = rule
+                    .conditions()
+                    .map(|(field, pattern)| (field, Self::compile_single_pattern(pattern)))
+                    .collect();
+                if conditions.is_empty() {
+                    log::warn!(
+                        "[[filter]] #{} has no match fields and never applies",
+                        index
+                    );
+                }
+                if rule.action == FilterAction::Template && rule.template.is_none() {
+                    log::warn!(
+                        "[[filter]] #{} uses action = \"template\" without a template table",
+                        index
+                    );
+                }
+                CompiledFilter { conditions }
+            })
+            .collect();
+
+        // --- path maps ---
+        self.compiled_path_map = PathMap::compile(&self.path_map);
+    }
+
+    /// First `[[filter]]` rule whose match fields all match `subject`.
+    pub fn match_filter(&self, subject: &FilterSubject<'_>) -> Option<&FilterRule> {
+        self.filter
+            .iter()
+            .zip(&self.compiled_filters)
+            .find(|(_, compiled)| compiled.matches(subject))
+            .map(|(rule, _)| rule)
     }
 
     fn compile_single_pattern(pattern: &str) -> CompiledPattern {
@@ -1210,6 +1377,95 @@ details = "new details"
 
         assert_eq!(template.details.as_ref(), "new details");
     }
+
+    #[test]
+    fn template_buttons_deserialize_as_array_of_tables() {
+        let template: TemplateConfig = toml::from_str(
+            r#"
+[[buttons]]
+label = "Search on Bandcamp"
+url = "https://bandcamp.com/search?q={{title}}"
+"#,
+        )
+        .expect("template buttons should deserialize");
+
+        assert_eq!(template.buttons.len(), 1);
+        assert_eq!(template.buttons[0].label.as_ref(), "Search on Bandcamp");
+    }
+
+    #[test]
+    fn player_buttons_override_and_fall_through_layers() {
+        let buttons = vec![ButtonTemplate {
+            label: "Open".into(),
+            url: "{{{url}}}".into(),
+            set: None,
+        }];
+        let mut cfg = Config::default();
+        cfg.bundled_player.insert(
+            "vlc".to_string(),
+            PlayerConfigLayer {
+                buttons: Some(buttons.clone()),
+                ..Default::default()
+            },
+        );
+        cfg.user_player
+            .insert("vlc".to_string(), layer(Some(true), None, None));
+
+        let res = cfg.get_player_config("vlc", "vlc");
+        assert_eq!(res.buttons, Some(buttons));
+
+        let unmatched = cfg.get_player_config("mpv", "mpv");
+        assert_eq!(unmatched.buttons, None);
+    }
+
+    #[test]
+    fn player_template_tables_layer_per_field() {
+        let mut cfg = Config::default();
+        cfg.bundled_player.insert(
+            "vlc".to_string(),
+            PlayerConfigLayer {
+                template: Some(TemplateConfigLayer {
+                    details: Some("bundled details".into()),
+                    state: Some("bundled state".into()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        cfg.user_player.insert(
+            "vlc*".to_string(),
+            PlayerConfigLayer {
+                template: Some(TemplateConfigLayer {
+                    state: Some("user state".into()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+
+        let template = cfg
+            .get_player_config("vlc", "vlc")
+            .template
+            .expect("player template layer");
+        assert_eq!(template.details.as_deref(), Some("bundled details"));
+        assert_eq!(template.state.as_deref(), Some("user state"));
+        assert_eq!(template.large_text, None);
+    }
+
+    #[test]
+    fn player_template_table_deserializes_from_toml() {
+        let layer: PlayerConfigLayer = toml::from_str(
+            r#"
+[template]
+details = "{{{title}}}"
+"#,
+        )
+        .expect("player template table should deserialize");
+
+        let template = layer.template.expect("template table");
+        assert_eq!(template.details.as_deref(), Some("{{{title}}}"));
+        assert_eq!(template.state, None);
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -1224,173 +1480,995 @@ pub struct TemplateConfig {
 
     #[serde(default = "default_template_small_text")]
     pub small_text: Box<str>,
-}
 
-fn default_template_details() -> Box<str> {
-    DEFAULT_TEMPLATE_DETAIL.into()
+    #[serde(default)]
+    pub buttons: Vec<ButtonTemplate>,
 }
 
-fn default_template_state() -> Box<str> {
-    DEFAULT_TEMPLATE_STATE.into()
-}
+/// Per-player `template` table layered over the global `[template]`.
+/// Unset fields fall through to lower-priority matches and finally to the
+/// global templates.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateConfigLayer {
+    #[serde(default)]
+    pub details: Option<Box<str>>,
 
-fn default_template_large_text() -> Box<str> {
-    DEFAULT_TEMPLATE_LARGE_TEXT.into()
-}
+    #[serde(default)]
+    pub state: Option<Box<str>>,
 
-fn default_template_small_text() -> Box<str> {
-    DEFAULT_TEMPLATE_SMALL_TEXT.into()
+    #[serde(default)]
+    pub large_text: Option<Box<str>>,
+
+    #[serde(default)]
+    pub small_text: Option<Box<str>>,
+
+    /// Template set each field came from, e.g. `player.vlc`. Filled in by
+    /// `Config::label_template_sets`, never read from the config file.
+    #[serde(skip)]
+    pub sets: BTreeMap<&'static str, Arc<str>>,
 }
 
-impl Default for TemplateConfig {
-    fn default() -> Self {
-        TemplateConfig {
-            details: default_template_details(),
-            state: default_template_state(),
-            large_text: default_template_large_text(),
-            small_text: default_template_small_text(),
+impl TemplateConfigLayer {
+    pub fn merge_from(&mut self, other: TemplateConfigLayer) {
+        for (field, _) in other.entries() {
+            match other.sets.get(field) {
+                Some(set) => self.sets.insert(field, set.clone()),
+                None => self.sets.remove(field),
+            };
         }
+        self.details = other.details.or(self.details.take());
+        self.state = other.state.or(self.state.take());
+        self.large_text = other.large_text.or(self.large_text.take());
+        self.small_text = other.small_text.or(self.small_text.take());
     }
-}
 
-impl<'de> Deserialize<'de> for TemplateConfig {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        struct TemplateConfigRaw {
-            #[serde(default)]
-            details: Option<Box<str>>,
-            #[serde(default)]
-            detail: Option<Box<str>>,
-            #[serde(default = "default_template_state")]
-            state: Box<str>,
-            #[serde(default = "default_template_large_text")]
-            large_text: Box<str>,
-            #[serde(default = "default_template_small_text")]
-            small_text: Box<str>,
+    /// `(template name, source)` for every field this layer sets.
+    pub fn entries(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("details", self.details.as_deref()),
+            ("state", self.state.as_deref()),
+            ("large_text", self.large_text.as_deref()),
+            ("small_text", self.small_text.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(name, source)| source.map(|source| (name, source)))
+    }
+
+    /// Mark every field this layer sets as coming from `set`.
+    pub fn label(&mut self, set: &Arc<str>) {
+        let fields: Vec<&'static str> = self.entries().map(|(field, _)| field).collect();
+        for field in fields {
+            self.sets.insert(field, set.clone());
         }
+    }
 
-        let raw = TemplateConfigRaw::deserialize(deserializer)?;
-        Ok(TemplateConfig {
-            details: raw
-                .details
-                .or(raw.detail)
-                .unwrap_or_else(default_template_details),
-            state: raw.state,
-            large_text: raw.large_text,
-            small_text: raw.small_text,
-        })
+    /// The set `field` came from, when it is set and labelled.
+    pub fn set(&self, field: &str) -> Option<&str> {
+        self.sets.get(field).map(AsRef::as_ref)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TimeConfig {
-    #[serde(default = "default_time_show")]
-    pub show: bool,
-
-    #[serde(default = "default_time_as_elapsed")]
-    pub as_elapsed: bool,
+fn label_template_set(
+    set: &str,
+    template: Option<&mut TemplateConfigLayer>,
+    buttons: Option<&mut [ButtonTemplate]>,
+) {
+    let set: Arc<str> = set.into();
+    if let Some(template) = template {
+        template.label(&set);
+    }
+    for button in buttons.into_iter().flatten() {
+        button.set = Some(set.clone());
+    }
 }
 
-fn default_time_show() -> bool {
-    DEFAULT_TIME_SHOW
+/// Layer `other` onto an optional template table, field by field.
+fn merge_template_layer(
+    current: Option<TemplateConfigLayer>,
+    other: Option<TemplateConfigLayer>,
+) -> Option<TemplateConfigLayer> {
+    match (current, other) {
+        (Some(mut current), Some(other)) => {
+            current.merge_from(other);
+            Some(current)
+        }
+        (current, other) => other.or(current),
+    }
 }
 
-fn default_time_as_elapsed() -> bool {
-    DEFAULT_TIME_AS_ELAPSED
-}
+/// A Discord activity button whose label and URL are Handlebars templates
+/// rendered against the same context as the activity texts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ButtonTemplate {
+    pub label: Box<str>,
+    pub url: Box<str>,
 
-impl Default for TimeConfig {
-    fn default() -> Self {
-        TimeConfig {
-            show: default_time_show(),
-            as_elapsed: default_time_as_elapsed(),
-        }
-    }
+    /// Template set the button came from; see `TemplateConfigLayer::sets`.
+    #[serde(skip)]
+    pub set: Option<Arc<str>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CoverConfig {
-    #[serde(default = "default_cover_file_names")]
-    pub file_names: Vec<String>,
+/// A `[[filter]]` content rule. Every match field that is set must match
+/// (patterns use the same exact / wildcard / `re:` syntax as player keys);
+/// multi-valued fields such as `artist` match if any value does.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterRule {
+    #[serde(default)]
+    pub title: Option<Box<str>>,
 
     #[serde(default)]
-    pub provider: CoverProviderConfig,
+    pub artist: Option<Box<str>>,
 
-    #[serde(default = "default_cover_local_search_depth")]
-    pub local_search_depth: usize,
-}
+    #[serde(default)]
+    pub album: Option<Box<str>>,
 
-fn default_cover_file_names() -> Vec<String> {
-    DEFAULT_COVER_FILE_NAMES
-        .iter()
-        .map(|&s| s.to_string())
-        .collect()
-}
+    #[serde(default)]
+    pub album_artist: Option<Box<str>>,
 
-fn default_cover_local_search_depth() -> usize {
-    DEFAULT_COVER_LOCAL_SEARCH_DEPTH
+    #[serde(default)]
+    pub genre: Option<Box<str>>,
+
+    /// Matched against `xesam:url`.
+    #[serde(default)]
+    pub url: Option<Box<str>>,
+
+    #[serde(default)]
+    pub action: FilterAction,
+
+    /// Fields replaced by `replacement` when `action = "redact"`. Empty means
+    /// everything that identifies the track.
+    #[serde(default)]
+    pub redact: Vec<RedactField>,
+
+    #[serde(default)]
+    pub replacement: Option<Box<str>>,
+
+    /// Layered over the player's templates when `action = "template"`.
+    #[serde(default)]
+    pub template: Option<TemplateConfigLayer>,
 }
 
-impl Default for CoverConfig {
-    fn default() -> Self {
-        CoverConfig {
-            file_names: default_cover_file_names(),
-            provider: CoverProviderConfig::default(),
-            local_search_depth: default_cover_local_search_depth(),
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    /// Clear the activity while the track plays.
+    #[default]
+    Hide,
+    /// Replace the chosen metadata fields before template rendering.
+    Redact,
+    /// Render with the rule's `template` table instead.
+    Template,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Url,
+    /// Skip cover art and show the player icon instead.
+    Cover,
+}
+
+pub const DEFAULT_REDACT_FIELDS: [RedactField; 6] = [
+    RedactField::Title,
+    RedactField::Artist,
+    RedactField::Album,
+    RedactField::AlbumArtist,
+    RedactField::Url,
+    RedactField::Cover,
+];
+pub const DEFAULT_REDACT_REPLACEMENT: &str = "Private";
+
+impl FilterRule {
+    /// `(field, pattern)` for every match field this rule sets.
+    pub fn conditions(&self) -> impl Iterator<Item = (FilterField, &str)> {
+        [
+            (FilterField::Title, self.title.as_deref()),
+            (FilterField::Artist, self.artist.as_deref()),
+            (FilterField::Album, self.album.as_deref()),
+            (FilterField::AlbumArtist, self.album_artist.as_deref()),
+            (FilterField::Genre, self.genre.as_deref()),
+            (FilterField::Url, self.url.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(field, pattern)| pattern.map(|pattern| (field, pattern)))
+    }
+
+    pub fn redact_fields(&self) -> &[RedactField] {
+        if self.redact.is_empty() {
+            &DEFAULT_REDACT_FIELDS
+        } else {
+            &self.redact
         }
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MusicbrainzConfig {
-    #[serde(default = "default_musicbrainz_min_score")]
-    pub min_score: u8,
+    pub fn replacement(&self) -> &str {
+        self.replacement
+            .as_deref()
+            .unwrap_or(DEFAULT_REDACT_REPLACEMENT)
+    }
+
+    pub fn redacts(&self, field: RedactField) -> bool {
+        self.action == FilterAction::Redact && self.redact_fields().contains(&field)
+    }
+
+    /// Layer this rule's template table over the player's own overrides.
+    pub fn apply_template(&self, player_config: &mut PlayerConfig) {
+        if self.action == FilterAction::Template {
+            player_config.template =
+                merge_template_layer(player_config.template.take(), self.template.clone());
+        }
+    }
 }
 
-fn default_musicbrainz_min_score() -> u8 {
-    DEFAULT_MUSICBRAINZ_MIN_SCORE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Url,
 }
 
-impl Default for MusicbrainzConfig {
-    fn default() -> Self {
-        Self {
-            min_score: default_musicbrainz_min_score(),
+impl FilterField {
+    /// The `[[filter]]` key for this field.
+    pub fn key(self) -> &'static str {
+        match self {
+            FilterField::Title => "title",
+            FilterField::Artist => "artist",
+            FilterField::Album => "album",
+            FilterField::AlbumArtist => "album_artist",
+            FilterField::Genre => "genre",
+            FilterField::Url => "url",
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CoverProviderConfig {
-    #[serde(default = "default_cover_providers")]
-    pub provider: Vec<String>,
+/// The track values a `[[filter]]` rule is matched against.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilterSubject<'a> {
+    pub title: Option<&'a str>,
+    pub artists: &'a [String],
+    pub album: Option<&'a str>,
+    pub album_artists: &'a [String],
+    pub genres: &'a [String],
+    pub url: Option<&'a str>,
+}
 
-    #[serde(default)]
-    pub imgbb: ImgBBConfig,
+impl FilterSubject<'_> {
+    fn values(&self, field: FilterField) -> Vec<&str> {
+        fn many(values: &[String]) -> Vec<&str> {
+            values.iter().map(String::as_str).collect()
+        }
+        match field {
+            FilterField::Title => self.title.into_iter().collect(),
+            FilterField::Artist => many(self.artists),
+            FilterField::Album => self.album.into_iter().collect(),
+            FilterField::AlbumArtist => many(self.album_artists),
+            FilterField::Genre => many(self.genres),
+            FilterField::Url => self.url.into_iter().collect(),
+        }
+    }
+}
 
-    #[serde(default)]
-    pub musicbrainz: MusicbrainzConfig,
+#[derive(Debug, Clone)]
+pub struct CompiledFilter {
+    conditions: Vec<(FilterField, CompiledPattern)>,
+}
 
-    #[serde(default)]
-    pub catbox: CatboxConfig,
+impl CompiledFilter {
+    fn matches(&self, subject: &FilterSubject<'_>) -> bool {
+        !self.conditions.is_empty()
+            && self.conditions.iter().all(|(field, pattern)| {
+                subject
+                    .values(*field)
+                    .into_iter()
+                    .any(|value| pattern.matches(value))
+            })
+    }
 }
 
-fn default_cover_providers() -> Vec<String> {
-    DEFAULT_COVER_PROVIDERS
-        .iter()
-        .map(|&s| s.to_string())
-        .collect()
+/// `[[path_map]]`: maps paths players report (Flatpak portal paths,
+/// sandbox or container paths, remote mounts) to where the file is on this
+/// machine, so tags and local cover art can be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathMapRule {
+    /// Path prefix, or `re:<regex>` matched against the whole path.
+    pub from: String,
+    /// Replacement prefix; regex rules may refer to groups as `$1`.
+    pub to: String,
 }
 
-impl Default for CoverProviderConfig {
-    fn default() -> Self {
+#[derive(Debug, Clone)]
+enum CompiledPathRule {
+    Prefix { from: PathBuf, to: PathBuf },
+    Regex { regex: Regex, to: String },
+}
+
+/// Compiled `[[path_map]]` rules; the first that matches applies.
+#[derive(Debug, Clone, Default)]
+pub struct PathMap {
+    /// With each rule's index in `[[path_map]]`; invalid rules are dropped.
+    rules: Vec<(usize, CompiledPathRule)>,
+}
+
+/// A path map rule applied to a track URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathRewrite {
+    /// Index of the rule in `[[path_map]]`.
+    pub rule: usize,
+    pub from: String,
+    pub to: String,
+}
+
+impl std::fmt::Display for PathRewrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {} (rule #{})", self.from, self.to, self.rule)
+    }
+}
+
+impl PathMap {
+    pub fn compile(rules: &[PathMapRule]) -> Self {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rule)| match rule.from.strip_prefix("re:") {
+                Some(pattern) => match Regex::new(pattern) {
+                    Ok(regex) => Some((
+                        index,
+                        CompiledPathRule::Regex {
+                            regex,
+                            to: rule.to.clone(),
+                        },
+                    )),
+                    Err(err) => {
+                        log::warn!("[[path_map]] #{} has an invalid regex: {}", index, err);
+                        None
+                    }
+                },
+                None if rule.from.is_empty() => {
+                    log::warn!(
+                        "[[path_map]] #{} has an empty `from` and never applies",
+                        index
+                    );
+                    None
+                }
+                None => Some((
+                    index,
+                    CompiledPathRule::Prefix {
+                        from: PathBuf::from(&rule.from),
+                        to: crate::utils::expand_home(&rule.to),
+                    },
+                )),
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Rewrite a `file://` URL or absolute path with the first matching
+    /// rule, as a `file://` URL. `None` for other URLs or when no rule
+    /// matches.
+    pub fn rewrite(&self, url: &str) -> Option<PathRewrite> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let path = if url.starts_with("file://") {
+            Url::parse(url).ok()?.to_file_path().ok()?
+        } else if url.starts_with('/') {
+            PathBuf::from(url)
+        } else {
+            return None;
+        };
+
+        self.rules.iter().find_map(|(index, rule)| {
+            let mapped = match rule {
+                CompiledPathRule::Prefix { from, to } => {
+                    crate::utils::replace_path_prefix(&path, from, to)?
+                }
+                CompiledPathRule::Regex { regex, to } => {
+                    let path = path.to_str()?;
+                    if !regex.is_match(path) {
+                        return None;
+                    }
+                    crate::utils::expand_home(&regex.replace(path, to.as_str()))
+                }
+            };
+            Some(PathRewrite {
+                rule: *index,
+                from: url.to_string(),
+                to: Url::from_file_path(&mapped).ok()?.to_string(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod path_map_tests {
+    use super::*;
+
+    fn path_map(rules: &[(&str, &str)]) -> PathMap {
+        let rules: Vec<PathMapRule> = rules
+            .iter()
+            .map(|(from, to)| PathMapRule {
+                from: from.to_string(),
+                to: to.to_string(),
+            })
+            .collect();
+        PathMap::compile(&rules)
+    }
+
+    #[test]
+    fn prefix_rules_match_whole_components() {
+        let map = path_map(&[("/data/music", "/srv/music")]);
+        let rewrite = map
+            .rewrite("file:///data/music/Daft%20Punk/01.flac")
+            .expect("prefix should match");
+        assert_eq!(rewrite.to, "file:///srv/music/Daft%20Punk/01.flac");
+        assert_eq!(rewrite.rule, 0);
+        assert_eq!(
+            map.rewrite("/data/music/01.flac").map(|r| r.to).as_deref(),
+            Some("file:///srv/music/01.flac")
+        );
+        assert_eq!(map.rewrite("file:///data/musical/01.flac"), None);
+        assert_eq!(map.rewrite("https://example.com/data/music/01.flac"), None);
+    }
+
+    #[test]
+    fn regex_rules_substitute_groups_and_first_match_wins() {
+        let map = path_map(&[
+            (
+                "re:^/run/user/\\d+/doc/[0-9a-f]+/(.*)$",
+                "/home/me/Music/$1",
+            ),
+            ("", "/ignored"),
+            ("re:(", "/ignored"),
+            ("/run/user", "/never"),
+        ]);
+        let rewrite = map
+            .rewrite("file:///run/user/1000/doc/3f2a9b1c/01.flac")
+            .expect("regex should match");
+        assert_eq!(rewrite.to, "file:///home/me/Music/01.flac");
+        assert_eq!(rewrite.rule, 0);
+
+        // Invalid rules are skipped but keep their index for logs.
+        let rewrite = map
+            .rewrite("/run/user/1000/other.flac")
+            .expect("prefix should match");
+        assert_eq!(rewrite.rule, 3);
+        assert_eq!(rewrite.to, "file:///never/1000/other.flac");
+    }
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use super::*;
+
+    fn config_with_filters(toml_str: &str) -> Config {
+        #[derive(Deserialize)]
+        struct Filters {
+            filter: Vec<FilterRule>,
+        }
+        let parsed: Filters = toml::from_str(toml_str).expect("filters should deserialize");
+        let mut cfg = Config {
+            filter: parsed.filter,
+            ..Config::default()
+        };
+        cfg.precompile_patterns();
+        cfg
+    }
+
+    fn subject<'a>(
+        title: &'a str,
+        artists: &'a [String],
+        url: Option<&'a str>,
+    ) -> FilterSubject<'a> {
+        FilterSubject {
+            title: Some(title),
+            artists,
+            url,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn first_rule_with_all_fields_matching_wins() {
+        let cfg = config_with_filters(
+            r#"
+[[filter]]
+artist = "Secret Band"
+album = "Demos"
+action = "template"
+template = { details = "Demo" }
+
+[[filter]]
+artist = "Secret Band"
+
+[[filter]]
+url = "*list=PLprivate*"
+action = "redact"
+redact = ["title"]
+replacement = "Something private"
+"#,
+        );
+        let artists = vec!["Other".to_string(), "Secret Band".to_string()];
+
+        let rule = cfg
+            .match_filter(&subject("Song", &artists, None))
+            .expect("artist rule should match any artist");
+        assert_eq!(rule.action, FilterAction::Hide);
+
+        let rule = cfg
+            .match_filter(&subject(
+                "Song",
+                &[],
+                Some("https://youtube.com/watch?v=x&list=PLprivate1"),
+            ))
+            .expect("url wildcard should match");
+        assert!(rule.redacts(RedactField::Title));
+        assert!(!rule.redacts(RedactField::Cover));
+        assert_eq!(rule.replacement(), "Something private");
+
+        assert!(cfg
+            .match_filter(&subject("Song", &["Anyone".to_string()], None))
+            .is_none());
+    }
+
+    #[test]
+    fn rule_without_match_fields_never_applies() {
+        let cfg = config_with_filters(
+            r#"
+[[filter]]
+action = "hide"
+"#,
+        );
+        assert!(cfg.match_filter(&subject("Song", &[], None)).is_none());
+    }
+
+    #[test]
+    fn template_action_layers_over_player_template() {
+        let rule = FilterRule {
+            title: Some("re:(?i)lo-?fi".into()),
+            action: FilterAction::Template,
+            template: Some(TemplateConfigLayer {
+                details: Some("Lo-fi beats".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut player_config = PlayerConfig {
+            template: Some(TemplateConfigLayer {
+                details: Some("{{{title}}}".into()),
+                state: Some("{{{artist_display}}}".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        rule.apply_template(&mut player_config);
+
+        let template = player_config.template.expect("template");
+        assert_eq!(template.details.as_deref(), Some("Lo-fi beats"));
+        assert_eq!(template.state.as_deref(), Some("{{{artist_display}}}"));
+    }
+}
+
+fn default_template_details() -> Box<str> {
+    DEFAULT_TEMPLATE_DETAIL.into()
+}
+
+fn default_template_state() -> Box<str> {
+    DEFAULT_TEMPLATE_STATE.into()
+}
+
+fn default_template_large_text() -> Box<str> {
+    DEFAULT_TEMPLATE_LARGE_TEXT.into()
+}
+
+fn default_template_small_text() -> Box<str> {
+    DEFAULT_TEMPLATE_SMALL_TEXT.into()
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        TemplateConfig {
+            details: default_template_details(),
+            state: default_template_state(),
+            large_text: default_template_large_text(),
+            small_text: default_template_small_text(),
+            buttons: Vec::new(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TemplateConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct TemplateConfigRaw {
+            #[serde(default)]
+            details: Option<Box<str>>,
+            #[serde(default)]
+            detail: Option<Box<str>>,
+            #[serde(default = "default_template_state")]
+            state: Box<str>,
+            #[serde(default = "default_template_large_text")]
+            large_text: Box<str>,
+            #[serde(default = "default_template_small_text")]
+            small_text: Box<str>,
+            #[serde(default)]
+            buttons: Vec<ButtonTemplate>,
+        }
+
+        let raw = TemplateConfigRaw::deserialize(deserializer)?;
+        Ok(TemplateConfig {
+            details: raw
+                .details
+                .or(raw.detail)
+                .unwrap_or_else(default_template_details),
+            state: raw.state,
+            large_text: raw.large_text,
+            small_text: raw.small_text,
+            buttons: raw.buttons,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeConfig {
+    #[serde(default = "default_time_show")]
+    pub show: bool,
+
+    #[serde(default = "default_time_as_elapsed")]
+    pub as_elapsed: bool,
+}
+
+fn default_time_show() -> bool {
+    DEFAULT_TIME_SHOW
+}
+
+fn default_time_as_elapsed() -> bool {
+    DEFAULT_TIME_AS_ELAPSED
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        TimeConfig {
+            show: default_time_show(),
+            as_elapsed: default_time_as_elapsed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverConfig {
+    #[serde(default = "default_cover_file_names")]
+    pub file_names: Vec<String>,
+
+    #[serde(default)]
+    pub provider: CoverProviderConfig,
+
+    #[serde(default = "default_cover_local_search_depth")]
+    pub local_search_depth: usize,
+
+    #[serde(default)]
+    pub cache: CoverCacheConfig,
+
+    #[serde(default)]
+    pub normalize: CoverNormalizeConfig,
+}
+
+fn default_cover_file_names() -> Vec<String> {
+    DEFAULT_COVER_FILE_NAMES
+        .iter()
+        .map(|&s| s.to_string())
+        .collect()
+}
+
+fn default_cover_local_search_depth() -> usize {
+    DEFAULT_COVER_LOCAL_SEARCH_DEPTH
+}
+
+impl Default for CoverConfig {
+    fn default() -> Self {
+        CoverConfig {
+            file_names: default_cover_file_names(),
+            provider: CoverProviderConfig::default(),
+            local_search_depth: default_cover_local_search_depth(),
+            cache: CoverCacheConfig::default(),
+            normalize: CoverNormalizeConfig::default(),
+        }
+    }
+}
+
+/// Size bounds for the on-disk cover cache. Least recently used entries are
+/// evicted when a store goes over either limit; 0 disables a limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverCacheConfig {
+    #[serde(default = "default_cover_cache_max_bytes")]
+    pub max_bytes: u64,
+
+    #[serde(default = "default_cover_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_cover_cache_max_bytes() -> u64 {
+    DEFAULT_COVER_CACHE_MAX_BYTES
+}
+
+fn default_cover_cache_max_entries() -> usize {
+    DEFAULT_COVER_CACHE_MAX_ENTRIES
+}
+
+impl Default for CoverCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_cover_cache_max_bytes(),
+            max_entries: default_cover_cache_max_entries(),
+        }
+    }
+}
+
+/// Preprocessing of local and embedded art before it reaches upload providers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverNormalizeConfig {
+    #[serde(default = "default_cover_normalize_enabled")]
+    pub enabled: bool,
+
+    /// Longest edge in pixels; larger images are downscaled to fit.
+    #[serde(default = "default_cover_normalize_max_dimension")]
+    pub max_dimension: u32,
+
+    #[serde(default)]
+    pub format: CoverImageFormat,
+
+    /// JPEG quality (1-100). WebP output is always lossless and ignores
+    /// it, so it is usually larger than JPEG for photos.
+    #[serde(default = "default_cover_normalize_quality")]
+    pub quality: u8,
+
+    /// Images larger than this are dropped instead of decoded.
+    #[serde(default = "default_cover_normalize_max_input_bytes")]
+    pub max_input_bytes: u64,
+}
+
+fn default_cover_normalize_enabled() -> bool {
+    DEFAULT_COVER_NORMALIZE_ENABLED
+}
+
+fn default_cover_normalize_max_dimension() -> u32 {
+    DEFAULT_COVER_NORMALIZE_MAX_DIMENSION
+}
+
+fn default_cover_normalize_quality() -> u8 {
+    DEFAULT_COVER_NORMALIZE_QUALITY
+}
+
+fn default_cover_normalize_max_input_bytes() -> u64 {
+    DEFAULT_COVER_NORMALIZE_MAX_INPUT_BYTES
+}
+
+impl Default for CoverNormalizeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cover_normalize_enabled(),
+            max_dimension: default_cover_normalize_max_dimension(),
+            format: CoverImageFormat::default(),
+            quality: default_cover_normalize_quality(),
+            max_input_bytes: default_cover_normalize_max_input_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverImageFormat {
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MusicbrainzConfig {
+    #[serde(default = "default_musicbrainz_min_score")]
+    pub min_score: u8,
+}
+
+fn default_musicbrainz_min_score() -> u8 {
+    DEFAULT_MUSICBRAINZ_MIN_SCORE
+}
+
+impl Default for MusicbrainzConfig {
+    fn default() -> Self {
+        Self {
+            min_score: default_musicbrainz_min_score(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscogsConfig {
+    /// Personal access token from https://www.discogs.com/settings/developers
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// Minimum match score (0-100) computed from barcode, catalog number,
+    /// album title and artist.
+    #[serde(default = "default_discogs_min_score")]
+    pub min_score: u8,
+}
+
+fn default_discogs_min_score() -> u8 {
+    DEFAULT_DISCOGS_MIN_SCORE
+}
+
+impl Default for DiscogsConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            min_score: default_discogs_min_score(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItunesConfig {
+    /// Minimum fuzzy similarity (0-100) both title and artist must reach.
+    #[serde(default = "default_itunes_min_score")]
+    pub min_score: u8,
+
+    /// Two-letter store country to search.
+    #[serde(default = "default_itunes_country")]
+    pub country: String,
+
+    /// Edge length in pixels of the returned artwork.
+    #[serde(default = "default_itunes_artwork_size")]
+    pub artwork_size: u32,
+}
+
+fn default_itunes_min_score() -> u8 {
+    DEFAULT_ITUNES_MIN_SCORE
+}
+
+fn default_itunes_country() -> String {
+    DEFAULT_ITUNES_COUNTRY.to_string()
+}
+
+fn default_itunes_artwork_size() -> u32 {
+    DEFAULT_ITUNES_ARTWORK_SIZE
+}
+
+impl Default for ItunesConfig {
+    fn default() -> Self {
+        Self {
+            min_score: default_itunes_min_score(),
+            country: default_itunes_country(),
+            artwork_size: default_itunes_artwork_size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeezerConfig {
+    /// Minimum fuzzy similarity (0-100) both title and artist must reach.
+    #[serde(default = "default_deezer_min_score")]
+    pub min_score: u8,
+}
+
+fn default_deezer_min_score() -> u8 {
+    DEFAULT_DEEZER_MIN_SCORE
+}
+
+impl Default for DeezerConfig {
+    fn default() -> Self {
+        Self {
+            min_score: default_deezer_min_score(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverProviderConfig {
+    #[serde(default = "default_cover_providers")]
+    pub provider: Vec<String>,
+
+    #[serde(default)]
+    pub strategy: CoverStrategy,
+
+    #[serde(default)]
+    pub imgbb: ImgBBConfig,
+
+    #[serde(default)]
+    pub musicbrainz: MusicbrainzConfig,
+
+    #[serde(default)]
+    pub discogs: DiscogsConfig,
+
+    #[serde(default)]
+    pub itunes: ItunesConfig,
+
+    #[serde(default)]
+    pub deezer: DeezerConfig,
+
+    #[serde(default)]
+    pub catbox: CatboxConfig,
+
+    #[serde(default)]
+    pub http_upload: HttpUploadConfig,
+
+    #[serde(default)]
+    pub s3: S3Config,
+}
+
+/// How the provider list is walked.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverStrategy {
+    /// One provider at a time, in list order.
+    #[default]
+    Sequential,
+    /// Metadata-only providers run concurrently and the first hit wins;
+    /// upload providers still go one at a time.
+    Race,
+}
+
+/// `[player.<name>.cover]`: provider list and strategy for one player.
+/// Unset fields fall back to `[cover.provider]`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct PlayerCoverConfig {
+    #[serde(default)]
+    pub provider: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub strategy: Option<CoverStrategy>,
+}
+
+impl PlayerCoverConfig {
+    pub fn merge_from(&mut self, other: PlayerCoverConfig) {
+        self.provider = other.provider.or(self.provider.take());
+        self.strategy = other.strategy.or(self.strategy);
+    }
+}
+
+fn merge_cover_layer(
+    current: Option<PlayerCoverConfig>,
+    other: Option<PlayerCoverConfig>,
+) -> Option<PlayerCoverConfig> {
+    match (current, other) {
+        (Some(mut current), Some(other)) => {
+            current.merge_from(other);
+            Some(current)
+        }
+        (current, other) => other.or(current),
+    }
+}
+
+fn default_cover_providers() -> Vec<String> {
+    DEFAULT_COVER_PROVIDERS
+        .iter()
+        .map(|&s| s.to_string())
+        .collect()
+}
+
+impl Default for CoverProviderConfig {
+    fn default() -> Self {
         CoverProviderConfig {
             provider: default_cover_providers(),
+            strategy: CoverStrategy::default(),
             imgbb: ImgBBConfig::default(),
             musicbrainz: MusicbrainzConfig::default(),
+            discogs: DiscogsConfig::default(),
+            itunes: ItunesConfig::default(),
+            deezer: DeezerConfig::default(),
             catbox: CatboxConfig::default(),
+            http_upload: HttpUploadConfig::default(),
+            s3: S3Config::default(),
         }
     }
 }
@@ -1444,6 +2522,476 @@ impl Default for CatboxConfig {
     }
 }
 
+/// Self-hosted image endpoint for the `http_upload` provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpUploadConfig {
+    /// Endpoint the cover is POSTed to as `multipart/form-data`.
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Form field name carrying the image bytes.
+    #[serde(default = "default_http_upload_field")]
+    pub field: String,
+
+    /// Extra request headers, e.g. `Authorization`.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// Extra text fields sent alongside the image.
+    #[serde(default)]
+    pub form: HashMap<String, String>,
+
+    /// JSON pointer (RFC 6901) to the hosted URL in a JSON response.
+    #[serde(default)]
+    pub json_pointer: Option<String>,
+
+    /// Regex for the hosted URL; the first capture group wins if present.
+    /// Ignored when `json_pointer` is set. With neither, the whole response
+    /// body must be the URL.
+    #[serde(default)]
+    pub url_regex: Option<String>,
+
+    /// Seconds until the host deletes the upload; 0 means never.
+    #[serde(default)]
+    pub expiration: u64,
+}
+
+fn default_http_upload_field() -> String {
+    DEFAULT_HTTP_UPLOAD_FIELD.to_string()
+}
+
+impl Default for HttpUploadConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            field: default_http_upload_field(),
+            headers: HashMap::new(),
+            form: HashMap::new(),
+            json_pointer: None,
+            url_regex: None,
+            expiration: 0,
+        }
+    }
+}
+
+/// S3-compatible bucket (AWS, MinIO, R2, ...) for the `s3` provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Config {
+    /// Service endpoint, e.g. `https://minio.example.com:9000`.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    #[serde(default)]
+    pub bucket: Option<String>,
+
+    /// Key prefix; objects are stored as `{prefix}{blake3}.{ext}`.
+    #[serde(default = "default_s3_prefix")]
+    pub prefix: String,
+
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+
+    #[serde(default)]
+    pub access_key: Option<String>,
+
+    #[serde(default)]
+    pub secret_key: Option<String>,
+
+    /// Address the bucket as `{endpoint}/{bucket}` rather than
+    /// `{bucket}.{endpoint}`. MinIO needs this unless DNS is set up for it.
+    #[serde(default = "default_s3_path_style")]
+    pub path_style: bool,
+
+    /// Public base URL for the bucket contents. When set, covers are linked
+    /// as `{public_url}/{key}` instead of through presigned URLs.
+    #[serde(default)]
+    pub public_url: Option<String>,
+
+    /// Lifetime in seconds of presigned URLs (max 7 days).
+    #[serde(default = "default_s3_presign_expiry")]
+    pub presign_expiry: u64,
+}
+
+fn default_s3_prefix() -> String {
+    DEFAULT_S3_PREFIX.to_string()
+}
+
+fn default_s3_region() -> String {
+    DEFAULT_S3_REGION.to_string()
+}
+
+fn default_s3_path_style() -> bool {
+    DEFAULT_S3_PATH_STYLE
+}
+
+fn default_s3_presign_expiry() -> u64 {
+    DEFAULT_S3_PRESIGN_EXPIRY
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            bucket: None,
+            prefix: default_s3_prefix(),
+            region: default_s3_region(),
+            access_key: None,
+            secret_key: None,
+            path_style: default_s3_path_style(),
+            public_url: None,
+            presign_expiry: default_s3_presign_expiry(),
+        }
+    }
+}
+
+/// `[scrobble]`: listening history submitted from the same player tracking
+/// that drives presence. Every service is off until configured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrobbleConfig {
+    /// Scrobbles kept per service while it is unreachable. The oldest are
+    /// dropped beyond this.
+    #[serde(default = "default_scrobble_max_queue")]
+    pub max_queue: usize,
+
+    #[serde(default)]
+    pub lastfm: LastfmConfig,
+
+    #[serde(default)]
+    pub listenbrainz: ListenbrainzConfig,
+}
+
+fn default_scrobble_max_queue() -> usize {
+    DEFAULT_SCROBBLE_MAX_QUEUE
+}
+
+impl Default for ScrobbleConfig {
+    fn default() -> Self {
+        Self {
+            max_queue: default_scrobble_max_queue(),
+            lastfm: LastfmConfig::default(),
+            listenbrainz: ListenbrainzConfig::default(),
+        }
+    }
+}
+
+/// Last.fm or a service speaking its 2.0 API (e.g. Libre.fm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastfmConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// API root; `https://libre.fm/2.0/` for Libre.fm.
+    #[serde(default = "default_lastfm_api_url")]
+    pub api_url: String,
+
+    /// Page the user approves access on during `mprisence scrobble auth`;
+    /// `https://libre.fm/api/auth/` for Libre.fm.
+    #[serde(default = "default_lastfm_auth_url")]
+    pub auth_url: String,
+
+    /// Application credentials from https://www.last.fm/api/account/create
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    #[serde(default)]
+    pub api_secret: Option<String>,
+
+    /// Session key printed by `mprisence scrobble auth`.
+    #[serde(default)]
+    pub session_key: Option<String>,
+}
+
+fn default_lastfm_api_url() -> String {
+    DEFAULT_LASTFM_API_URL.to_string()
+}
+
+fn default_lastfm_auth_url() -> String {
+    DEFAULT_LASTFM_AUTH_URL.to_string()
+}
+
+impl Default for LastfmConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_url: default_lastfm_api_url(),
+            auth_url: default_lastfm_auth_url(),
+            api_key: None,
+            api_secret: None,
+            session_key: None,
+        }
+    }
+}
+
+/// ListenBrainz, or a self-hosted instance of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenbrainzConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// API root, without the `/1/` path.
+    #[serde(default = "default_listenbrainz_api_url")]
+    pub api_url: String,
+
+    /// User token from https://listenbrainz.org/settings/
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+fn default_listenbrainz_api_url() -> String {
+    DEFAULT_LISTENBRAINZ_API_URL.to_string()
+}
+
+impl Default for ListenbrainzConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_url: default_listenbrainz_api_url(),
+            token: None,
+        }
+    }
+}
+
+/// A `[[sink]]` entry: another consumer of the activity pushed to Discord.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkConfig {
+    /// One JSON event per line, appended to `path`; `-` is stdout.
+    Jsonl {
+        #[serde(default = "default_sink_jsonl_path")]
+        path: String,
+    },
+    /// Each JSON event POSTed to `url`, with any extra `headers`.
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// `GET /now-playing`, an SSE stream and the local cover, served on
+    /// `127.0.0.1:port` for browser-source overlays. Web pages may only
+    /// read it cross-origin from `allowed_origins`.
+    Http {
+        #[serde(default = "default_sink_http_port")]
+        port: u16,
+        #[serde(default)]
+        allowed_origins: Vec<String>,
+    },
+}
+
+fn default_sink_jsonl_path() -> String {
+    DEFAULT_SINK_JSONL_PATH.to_string()
+}
+
+fn default_sink_http_port() -> u16 {
+    DEFAULT_SINK_HTTP_PORT
+}
+
+/// `[bar]`: what `mprisence bar` prints for status bars. Templates take the
+/// same variables as `[template]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarConfig {
+    #[serde(default = "default_bar_text")]
+    pub text: String,
+
+    /// Waybar tooltip; unused by the plain-text formats.
+    #[serde(default = "default_bar_tooltip")]
+    pub tooltip: String,
+
+    /// Milliseconds between player scans.
+    #[serde(default = "default_bar_interval")]
+    pub interval: u64,
+}
+
+fn default_bar_text() -> String {
+    DEFAULT_BAR_TEXT.to_string()
+}
+
+fn default_bar_tooltip() -> String {
+    DEFAULT_BAR_TOOLTIP.to_string()
+}
+
+fn default_bar_interval() -> u64 {
+    DEFAULT_BAR_INTERVAL
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            text: default_bar_text(),
+            tooltip: default_bar_tooltip(),
+            interval: default_bar_interval(),
+        }
+    }
+}
+
+/// `[arbitration]`: which player owns the activity when several are
+/// active. Discord shows one activity at a time, so without it the last
+/// player to push wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbitrationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Criteria in the order they are applied; the first that tells two
+    /// players apart decides.
+    #[serde(default = "default_arbitration_order")]
+    pub order: Vec<ArbitrationRule>,
+
+    /// Player identities or bus names, most preferred first, for the
+    /// `priority` criterion. Unlisted players rank after listed ones.
+    #[serde(default)]
+    pub priority: Vec<String>,
+
+    #[serde(default)]
+    pub losers: LoserAction,
+}
+
+fn default_arbitration_order() -> Vec<ArbitrationRule> {
+    vec![
+        ArbitrationRule::Playing,
+        ArbitrationRule::Priority,
+        ArbitrationRule::Music,
+        ArbitrationRule::Recent,
+    ]
+}
+
+impl Default for ArbitrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            order: default_arbitration_order(),
+            priority: Vec::new(),
+            losers: LoserAction::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArbitrationRule {
+    /// Playing over paused over stopped.
+    Playing,
+    /// Earlier in `priority` first.
+    Priority,
+    /// Listening activities over watching ones.
+    Music,
+    /// The player that started playing most recently.
+    Recent,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoserAction {
+    /// Clear the outranked player's activity.
+    #[default]
+    Clear,
+    /// Stop updating the outranked player's activity but leave it in place.
+    Hold,
+}
+
+/// `[mpd]`: read MPD directly over its protocol and track it as a player
+/// without D-Bus, for setups without a working mpDris2.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MpdConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// `host:port` (port 6600 when omitted), or the path of MPD's Unix
+    /// socket.
+    #[serde(default = "default_mpd_address")]
+    pub address: String,
+
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// MPD's `music_directory` as seen from this machine. When unset it is
+    /// asked from MPD, which only answers over the Unix socket.
+    #[serde(default)]
+    pub music_directory: Option<PathBuf>,
+}
+
+fn default_mpd_address() -> String {
+    DEFAULT_MPD_ADDRESS.to_string()
+}
+
+impl Default for MpdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: default_mpd_address(),
+            password: None,
+            music_directory: None,
+        }
+    }
+}
+
+/// `[lyrics]`: synced lyrics for the `{{lyric_line}}` template variable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LyricsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Least time in milliseconds between pushes made to advance the line.
+    /// Lines that go by faster are skipped.
+    #[serde(default = "default_lyrics_min_interval")]
+    pub min_interval: u64,
+}
+
+fn default_lyrics_min_interval() -> u64 {
+    DEFAULT_LYRICS_MIN_INTERVAL
+}
+
+impl Default for LyricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_interval: default_lyrics_min_interval(),
+        }
+    }
+}
+
+/// `[progress]`: the `{{progress_bar}}` helper and re-rendering to keep
+/// `position_*` and `progress_*` template variables fresh.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressConfig {
+    /// Re-render and push the activity this often in milliseconds while
+    /// playing; 0 renders only on player changes.
+    #[serde(default)]
+    pub refresh_interval: u64,
+
+    /// Cells in `{{progress_bar}}`.
+    #[serde(default = "default_progress_bar_width")]
+    pub bar_width: usize,
+
+    #[serde(default = "default_progress_bar_filled")]
+    pub bar_filled: String,
+
+    #[serde(default = "default_progress_bar_empty")]
+    pub bar_empty: String,
+}
+
+fn default_progress_bar_width() -> usize {
+    DEFAULT_PROGRESS_BAR_WIDTH
+}
+
+fn default_progress_bar_filled() -> String {
+    DEFAULT_PROGRESS_BAR_FILLED.to_string()
+}
+
+fn default_progress_bar_empty() -> String {
+    DEFAULT_PROGRESS_BAR_EMPTY.to_string()
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval: 0,
+            bar_width: default_progress_bar_width(),
+            bar_filled: default_progress_bar_filled(),
+            bar_empty: default_progress_bar_empty(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy)]
 #[serde(rename_all = "lowercase")]
 #[derive(Default)]
@@ -1514,6 +3062,18 @@ pub struct PlayerConfigLayer {
 
     #[serde(default)]
     pub override_activity_type: Option<ActivityType>,
+
+    #[serde(default)]
+    pub buttons: Option<Vec<ButtonTemplate>>,
+
+    #[serde(default)]
+    pub template: Option<TemplateConfigLayer>,
+
+    #[serde(default)]
+    pub cover: Option<PlayerCoverConfig>,
+
+    #[serde(default)]
+    pub file_resolver: Option<FileResolverConfig>,
 }
 
 impl PlayerConfigLayer {
@@ -1542,6 +3102,18 @@ impl PlayerConfigLayer {
         if let Some(value) = self.override_activity_type {
             base.override_activity_type = Some(value);
         }
+        if let Some(value) = &self.buttons {
+            base.buttons = Some(value.clone());
+        }
+        if self.template.is_some() {
+            base.template = merge_template_layer(base.template.take(), self.template.clone());
+        }
+        if self.cover.is_some() {
+            base.cover = merge_cover_layer(base.cover.take(), self.cover.clone());
+        }
+        if let Some(value) = &self.file_resolver {
+            base.file_resolver = Some(value.clone());
+        }
 
         base
     }
@@ -1556,6 +3128,10 @@ impl PlayerConfigLayer {
         self.allow_streaming = other.allow_streaming.or(self.allow_streaming);
         self.status_display_type = other.status_display_type.or(self.status_display_type);
         self.override_activity_type = other.override_activity_type.or(self.override_activity_type);
+        self.buttons = other.buttons.or(self.buttons.take());
+        self.template = merge_template_layer(self.template.take(), other.template);
+        self.cover = merge_cover_layer(self.cover.take(), other.cover);
+        self.file_resolver = other.file_resolver.or(self.file_resolver.take());
     }
 }
 
@@ -1584,6 +3160,23 @@ pub struct PlayerConfig {
 
     #[serde(default)]
     pub override_activity_type: Option<ActivityType>,
+
+    /// Replaces `template.buttons` for this player when set. An empty list
+    /// disables buttons entirely.
+    #[serde(default)]
+    pub buttons: Option<Vec<ButtonTemplate>>,
+
+    /// Template fields overriding the global `[template]` for this player.
+    #[serde(default)]
+    pub template: Option<TemplateConfigLayer>,
+
+    /// Cover provider list and strategy overriding `[cover.provider]`.
+    #[serde(default)]
+    pub cover: Option<PlayerCoverConfig>,
+
+    /// How to find the playing file when `xesam:url` is missing or unusable.
+    #[serde(default)]
+    pub file_resolver: Option<FileResolverConfig>,
 }
 
 fn default_player_ignore() -> bool {
@@ -1621,6 +3214,39 @@ impl Default for PlayerConfig {
             allow_streaming: default_player_allow_streaming(),
             status_display_type: default_player_status_display_type(),
             override_activity_type: None,
+            buttons: None,
+            template: None,
+            cover: None,
+            file_resolver: None,
+        }
+    }
+}
+
+/// A player's local file resolver.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FileResolverConfig {
+    /// Ask `cmus-remote -Q`.
+    Cmus,
+    /// Run a program (no shell) that prints the file path or `file://` URL.
+    /// The track is passed in `MPRISENCE_PLAYER`, `MPRISENCE_BUS_NAME`,
+    /// `MPRISENCE_TRACK_ID`, `MPRISENCE_TITLE` and `MPRISENCE_URL`.
+    Command { command: Vec<String> },
+    /// Replace the `from` prefix of the reported path with `to`. An empty
+    /// `from` matches relative paths.
+    Prefix {
+        #[serde(default)]
+        from: String,
+        to: String,
+    },
+}
+
+impl std::fmt::Display for FileResolverConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileResolverConfig::Cmus => write!(f, "cmus"),
+            FileResolverConfig::Command { command } => write!(f, "command `{}`", command.join(" ")),
+            FileResolverConfig::Prefix { from, to } => write!(f, "prefix {:?} -> {:?}", from, to),
         }
     }
 }
@@ -1668,6 +3294,12 @@ pub struct WebPlayerConfigLayer {
 
     #[serde(default)]
     pub override_activity_type: Option<ActivityType>,
+
+    #[serde(default)]
+    pub buttons: Option<Vec<ButtonTemplate>>,
+
+    #[serde(default)]
+    pub template: Option<TemplateConfigLayer>,
 }
 
 impl WebPlayerConfigLayer {
@@ -1696,12 +3328,15 @@ impl WebPlayerConfigLayer {
         self.title_suffix = other.title_suffix.or(self.title_suffix.take());
         self.name = other.name.or(self.name.take());
         self.ignore = other.ignore.or(self.ignore);
+        self.ignore_unmatched = other.ignore_unmatched.or(self.ignore_unmatched);
         self.app_id = other.app_id.or(self.app_id.take());
         self.icon = other.icon.or(self.icon.take());
         self.show_icon = other.show_icon.or(self.show_icon);
         self.allow_streaming = other.allow_streaming.or(self.allow_streaming);
         self.status_display_type = other.status_display_type.or(self.status_display_type);
         self.override_activity_type = other.override_activity_type.or(self.override_activity_type);
+        self.buttons = other.buttons.or(self.buttons.take());
+        self.template = merge_template_layer(self.template.take(), other.template);
     }
 
     fn apply_into_web_player(&self, mut base: WebPlayerConfig) -> WebPlayerConfig {
@@ -1736,6 +3371,12 @@ impl WebPlayerConfigLayer {
         if let Some(value) = self.override_activity_type {
             base.override_activity_type = Some(value);
         }
+        if let Some(value) = &self.buttons {
+            base.buttons = Some(value.clone());
+        }
+        if self.template.is_some() {
+            base.template = merge_template_layer(base.template.take(), self.template.clone());
+        }
         base
     }
 }
@@ -1766,6 +3407,10 @@ pub struct WebPlayerConfig {
     pub status_display_type: Option<StatusDisplayType>,
     #[serde(default)]
     pub override_activity_type: Option<ActivityType>,
+    #[serde(default)]
+    pub buttons: Option<Vec<ButtonTemplate>>,
+    #[serde(default)]
+    pub template: Option<TemplateConfigLayer>,
 }
 
 impl WebPlayerConfig {
@@ -1798,6 +3443,8 @@ impl WebPlayerConfig {
         if let Some(act) = self.override_activity_type {
             p.override_activity_type = Some(act);
         }
+        p.buttons = self.buttons;
+        p.template = self.template;
         p
     }
 }
@@ -1865,6 +3512,24 @@ mod web_player_tests {
         assert!(!player.allow_streaming);
     }
 
+    #[test]
+    fn website_into_player_config_carries_buttons() {
+        let layer = WebPlayerConfigLayer {
+            match_pattern: Some("music.youtube.com".to_string()),
+            buttons: Some(vec![ButtonTemplate {
+                label: "Open on YouTube Music".into(),
+                url: "{{{url}}}".into(),
+                set: None,
+            }]),
+            ..Default::default()
+        };
+        let resolved = layer.apply_into_web_player(WebPlayerConfig::default());
+        let player = resolved.into_player_config();
+
+        let buttons = player.buttons.expect("web player buttons");
+        assert_eq!(buttons[0].label.as_ref(), "Open on YouTube Music");
+    }
+
     fn build_cfg(setup: impl FnOnce(&mut Config)) -> Config {
         let mut cfg = Config::default();
         setup(&mut cfg);
@@ -2,12 +2,16 @@ use std::path::Path;
 
 use mpris::{PlaybackStatus, PlayerFinder};
 use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
 use tiny_http::Method;
 
 use crate::config::{self, ConfigManager};
 use crate::error::Error;
 use crate::metadata::{MediaMetadata, MetadataSource};
-use crate::player::{canonical_player_bus_name, is_playerctld_no_active_error};
+use crate::player::{
+    canonical_player_bus_name, file_resolver::FileResolutions, is_playerctld_no_active_error,
+    MediaPlayer,
+};
 use crate::template::{RenderContext, TemplateManager};
 use crate::utils::{format_playback_status_icon, normalize_player_identity};
 
@@ -124,6 +128,8 @@ fn list_players(config_path: &Path) -> (u16, &'static str, String) {
 
 fn collect_players(config_path: &Path) -> Result<Vec<PlayerEntry>, Error> {
     let manager = ConfigManager::new_with_config(effective_config(config_path));
+    let path_map = manager.path_map();
+    let resolutions = FileResolutions::default();
     let mut finder = PlayerFinder::new()?;
     finder.set_player_timeout_ms(2000);
     let mut entries = Vec::new();
@@ -134,16 +140,21 @@ fn collect_players(config_path: &Path) -> Result<Vec<PlayerEntry>, Error> {
             Err(e) => return Err(e.into()),
         };
         player.set_dbus_timeout_ms(2000);
+        let player = MediaPlayer::Mpris(player);
         let status = player
             .get_playback_status()
             .unwrap_or(PlaybackStatus::Stopped);
+        let identity = player.identity().to_string();
+        let player_bus_name = canonical_player_bus_name(player.bus_name());
         let metadata = player
             .get_metadata()
-            .map(|m| MetadataSource::from_mpris_with_override(m, None).to_media_metadata())
+            .map(|m| {
+                let key = SmolStr::new(&player_bus_name);
+                let resolved_url = resolutions.resolve_url_blocking(&key, &manager, &player, &m);
+                MetadataSource::from_mpris_mapped(m, resolved_url, &path_map).to_media_metadata()
+            })
             .unwrap_or_default();
         let context = RenderContext::new(&player, status, metadata, None);
-        let identity = player.identity().to_string();
-        let player_bus_name = canonical_player_bus_name(player.bus_name());
         entries.push(PlayerEntry {
             config_key: normalize_player_identity(&identity),
             allowed: manager.is_player_allowed(&identity, &player_bus_name),
@@ -256,6 +267,10 @@ fn sample_context() -> RenderContext {
         status: Some("Playing".to_string()),
         status_icon: Some(format_playback_status_icon(PlaybackStatus::Playing).to_string()),
         volume: Some(0.5),
+        position_secs: Some(83),
+        position_display: Some("01:23".to_string()),
+        progress_percent: Some(38),
+        lyric_line: Some("Sample lyric line".to_string()),
         metadata: MediaMetadata {
             title: Some("Sample Track".to_string()),
             artists: vec!["Sample Artist".to_string()],
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+use super::protocol::RpcError;
+
+#[derive(Error, Debug)]
+pub enum ControlError {
+    #[error("mprisence is not running (no control socket at {0})")]
+    NotRunning(PathBuf),
+
+    #[error("Control socket already in use at {0}")]
+    AlreadyRunning(PathBuf),
+
+    #[error("Runtime directory {0} is not a directory owned by this user")]
+    ForeignRuntimeDir(PathBuf),
+
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Daemon returned error {}: {}", .0.code, .0.message)]
+    Rpc(RpcError),
+
+    #[error("Daemon closed the connection without replying")]
+    Closed,
+
+    #[error("Timed out waiting for the daemon")]
+    Timeout,
+
+    #[error("Invalid duration '{0}' (expected e.g. 90s, 30m, 1h30m)")]
+    InvalidDuration(String),
+}
@@ -0,0 +1,262 @@
+//! Out-of-band triggers for the hidden (paused) state: a flag file in the
+//! runtime dir and `SIGUSR1`. Both feed the same `ControlCommand` channel as
+//! the socket, so `Mprisence` stays the only owner of the state.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use log::{debug, info, warn};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+use super::{protocol::ControlRequest, runtime_dir, ControlCommand, ControlError};
+
+const FLAG_NAME: &str = "hidden";
+pub const FLAG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `$XDG_RUNTIME_DIR/mprisence/hidden`. While it exists presences stay
+/// hidden; its contents, if any, are a snooze duration such as `30m`.
+pub fn flag_path() -> PathBuf {
+    runtime_dir().join(FLAG_NAME)
+}
+
+/// Remove the flag file so the watcher doesn't re-hide after a resume.
+pub fn remove_flag() {
+    remove_flag_at(&flag_path());
+}
+
+fn remove_flag_at(path: &Path) {
+    match std::fs::remove_file(path) {
+        Ok(()) => debug!("Removed hidden flag {:?}", path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove hidden flag {:?}: {}", path, e),
+    }
+}
+
+/// Parse `90s`, `30m`, `1h30m`, `7d` or a bare number of minutes.
+pub fn parse_duration(input: &str) -> Result<Duration, ControlError> {
+    let invalid = || ControlError::InvalidDuration(input.to_string());
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(minutes) = trimmed.parse::<u64>() {
+        return minutes
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(invalid);
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in trimmed.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let value: u64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        let unit = match c.to_ascii_lowercase() {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        total = value
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// When a snooze of `duration` started at `now` ends. Durations past what
+/// `SystemTime` can represent are rejected rather than overflowing.
+pub fn snooze_deadline(now: SystemTime, duration: Duration) -> Result<SystemTime, ControlError> {
+    now.checked_add(duration)
+        .ok_or_else(|| ControlError::InvalidDuration(format!("{}s", duration.as_secs())))
+}
+
+/// Resolves at `until`; never resolves when there is no deadline. Used as a
+/// `select!` branch so a snooze expires on the run loop.
+pub async fn wait_until(until: Option<SystemTime>) {
+    match until {
+        Some(until) => {
+            let remaining = until.duration_since(SystemTime::now()).unwrap_or_default();
+            tokio::time::sleep(remaining).await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Send a request without waiting for the reply.
+async fn fire(tx: &mpsc::Sender<ControlCommand>, request: ControlRequest) -> bool {
+    let (reply, _) = oneshot::channel();
+    tx.send(ControlCommand { request, reply }).await.is_ok()
+}
+
+fn read_flag(path: &Path) -> Option<u64> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) if contents.trim().is_empty() => None,
+        Ok(contents) => match parse_duration(&contents) {
+            Ok(duration) => Some(duration.as_secs()),
+            Err(e) => {
+                warn!("{} in {:?}; hiding until the file is removed", e, path);
+                None
+            }
+        },
+        Err(e) => {
+            debug!("Failed to read hidden flag {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Poll the flag file and translate create/modify into `Pause` and removal
+/// into `Resume`.
+pub fn spawn_flag_watcher(
+    path: PathBuf,
+    tx: mpsc::Sender<ControlCommand>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last_seen: Option<SystemTime> = None;
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let modified = std::fs::metadata(&path)
+                .map(|meta| meta.modified().unwrap_or(SystemTime::UNIX_EPOCH))
+                .ok();
+            if modified == last_seen {
+                continue;
+            }
+            last_seen = modified;
+
+            let request = match modified {
+                Some(_) => {
+                    let duration_secs = read_flag(&path);
+                    info!("Hidden flag present at {:?}", path);
+                    ControlRequest::Pause { duration_secs }
+                }
+                None => {
+                    info!("Hidden flag removed");
+                    ControlRequest::Resume
+                }
+            };
+            if !fire(&tx, request).await {
+                break;
+            }
+        }
+    })
+}
+
+/// Toggle the hidden state on `SIGUSR1`.
+pub fn spawn_toggle_signal(
+    tx: mpsc::Sender<ControlCommand>,
+) -> Result<JoinHandle<()>, ControlError> {
+    let mut signals = signal(SignalKind::user_defined1())?;
+    Ok(tokio::spawn(async move {
+        while signals.recv().await.is_some() {
+            debug!("SIGUSR1 received, toggling hidden state");
+            if !fire(&tx, ControlRequest::Toggle).await {
+                break;
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_snooze_durations() {
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration(" 15\n").unwrap(), Duration::from_secs(900));
+        assert_eq!(
+            parse_duration("2d12h").unwrap(),
+            Duration::from_secs(216000)
+        );
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("0m").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_that_overflow() {
+        assert!(parse_duration("18446744073709551615").is_err());
+        assert!(parse_duration("18446744073709551615s").is_ok());
+        assert!(parse_duration("300000000000000000d").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+
+        let now = SystemTime::now();
+        assert!(snooze_deadline(now, Duration::from_secs(u64::MAX)).is_err());
+        assert_eq!(
+            snooze_deadline(now, Duration::from_secs(60)).unwrap(),
+            now + Duration::from_secs(60)
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_until_past_deadline_resolves_immediately() {
+        let past = SystemTime::now() - Duration::from_secs(5);
+        tokio::time::timeout(Duration::from_millis(100), wait_until(Some(past)))
+            .await
+            .expect("expired deadline should resolve");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), wait_until(None))
+                .await
+                .is_err()
+        );
+    }
+
+    async fn next(rx: &mut mpsc::Receiver<ControlCommand>) -> ControlRequest {
+        tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("watcher should react")
+            .expect("channel open")
+            .request
+    }
+
+    #[tokio::test]
+    async fn flag_file_creation_and_removal_toggle_hidden_state() {
+        let dir = std::env::temp_dir().join(format!(
+            "mprisence-hidden-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(FLAG_NAME);
+
+        let (tx, mut rx) = mpsc::channel(4);
+        let watcher = spawn_flag_watcher(path.clone(), tx, Duration::from_millis(10));
+        std::fs::write(&path, "30m\n").unwrap();
+        assert_eq!(
+            next(&mut rx).await,
+            ControlRequest::Pause {
+                duration_secs: Some(1800)
+            }
+        );
+
+        remove_flag_at(&path);
+        assert_eq!(next(&mut rx).await, ControlRequest::Resume);
+
+        watcher.abort();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,408 @@
+//! Control socket for a running daemon: newline-delimited JSON-RPC 2.0 over a
+//! Unix socket in `$XDG_RUNTIME_DIR/mprisence/`. The server only parses and
+//! forwards requests; `Mprisence` answers them on its own run loop so all
+//! presence state stays single-owner.
+
+mod error;
+pub mod hidden;
+pub mod protocol;
+
+use std::{
+    env,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use log::{debug, info, trace, warn};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+    time::timeout,
+};
+
+pub use error::ControlError;
+use protocol::{
+    ControlRequest, RpcError, RpcRequest, RpcResponse, INTERNAL_ERROR, INVALID_REQUEST,
+    JSONRPC_VERSION, PARSE_ERROR,
+};
+
+const SOCKET_NAME: &str = "control.sock";
+/// Upper bound for a single client call. `refresh` may have to talk to every
+/// player over D-Bus, so this is generous.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A request waiting for `Mprisence` to answer it.
+pub struct ControlCommand {
+    pub request: ControlRequest,
+    pub reply: oneshot::Sender<Result<Value, RpcError>>,
+}
+
+/// `$XDG_RUNTIME_DIR/mprisence`, falling back to the temp dir like the
+/// Discord IPC lookup does. The fallback is per user (`mprisence-<uid>`):
+/// the temp dir is shared, and another user's directory cannot be used.
+pub fn runtime_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(base) => PathBuf::from(base).join("mprisence"),
+        None => match current_uid() {
+            Ok(uid) => env::temp_dir().join(format!("mprisence-{uid}")),
+            Err(_) => env::temp_dir().join("mprisence"),
+        },
+    }
+}
+
+/// Uid of this process: the owner of `/proc/self`.
+fn current_uid() -> std::io::Result<u32> {
+    Ok(std::fs::metadata("/proc/self")?.uid())
+}
+
+/// Create `dir` if needed and make it private. Refuses a directory owned by
+/// someone else (or a symlink to one), which another user could have
+/// created in a shared temp dir to read or block the socket.
+fn ensure_private_dir(dir: &Path) -> Result<(), ControlError> {
+    std::fs::create_dir_all(dir)?;
+    let meta = std::fs::symlink_metadata(dir)?;
+    if !meta.is_dir() || meta.uid() != current_uid()? {
+        return Err(ControlError::ForeignRuntimeDir(dir.to_path_buf()));
+    }
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+pub fn socket_path() -> PathBuf {
+    runtime_dir().join(SOCKET_NAME)
+}
+
+/// Owns the listening socket, if one could be bound, and the hidden-state
+/// triggers; removes the socket file when dropped.
+pub struct ControlServer {
+    path: Option<PathBuf>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        if let Some(path) = &self.path {
+            if let Err(e) = std::fs::remove_file(path) {
+                debug!("Failed to remove control socket {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// Start the hidden-state triggers and the control socket. The triggers
+/// run even when the socket cannot be bound: without the SIGUSR1 handler a
+/// `kill -USR1` toggle would terminate the daemon.
+pub async fn serve(tx: mpsc::Sender<ControlCommand>) -> ControlServer {
+    serve_with(&socket_path(), hidden::flag_path(), tx).await
+}
+
+async fn serve_with(
+    socket: &Path,
+    flag: PathBuf,
+    tx: mpsc::Sender<ControlCommand>,
+) -> ControlServer {
+    let mut server = ControlServer {
+        path: None,
+        tasks: vec![hidden::spawn_flag_watcher(
+            flag,
+            tx.clone(),
+            hidden::FLAG_POLL_INTERVAL,
+        )],
+    };
+    match hidden::spawn_toggle_signal(tx.clone()) {
+        Ok(task) => server.tasks.push(task),
+        Err(e) => warn!("SIGUSR1 toggle unavailable: {}", e),
+    }
+    match serve_at(socket, tx).await {
+        Ok(mut socket) => {
+            server.path = socket.path.take();
+            server.tasks.append(&mut socket.tasks);
+        }
+        Err(e) => warn!("Control socket unavailable: {}", e),
+    }
+    server
+}
+
+async fn serve_at(
+    path: &Path,
+    tx: mpsc::Sender<ControlCommand>,
+) -> Result<ControlServer, ControlError> {
+    if let Some(dir) = path.parent() {
+        ensure_private_dir(dir)?;
+    }
+
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(ControlError::AlreadyRunning(path.to_path_buf()));
+        }
+        debug!("Removing stale control socket {:?}", path);
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    info!("Control socket listening on {}", path.display());
+
+    let task = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(stream, tx.clone()));
+                }
+                Err(e) => {
+                    warn!("Control socket accept failed: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    });
+
+    Ok(ControlServer {
+        path: Some(path.to_path_buf()),
+        tasks: vec![task],
+    })
+}
+
+async fn handle_connection(stream: UnixStream, tx: mpsc::Sender<ControlCommand>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                debug!("Control connection read failed: {}", e);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        trace!("← ctl: {}", line);
+
+        let response = dispatch_line(&line, &tx).await;
+        let mut encoded = match serde_json::to_string(&response) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                warn!("Failed to encode control response: {}", e);
+                break;
+            }
+        };
+        encoded.push('\n');
+        if let Err(e) = writer.write_all(encoded.as_bytes()).await {
+            debug!("Control connection write failed: {}", e);
+            break;
+        }
+    }
+}
+
+async fn dispatch_line(line: &str, tx: &mpsc::Sender<ControlCommand>) -> RpcResponse {
+    let request: RpcRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return RpcResponse::from_result(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, e.to_string())),
+            )
+        }
+    };
+    if request.jsonrpc != JSONRPC_VERSION {
+        return RpcResponse::from_result(
+            request.id,
+            Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")),
+        );
+    }
+
+    let result = match ControlRequest::from_rpc(&request.method, request.params) {
+        Ok(control_request) => forward(control_request, tx).await,
+        Err(err) => Err(err),
+    };
+    RpcResponse::from_result(request.id, result)
+}
+
+async fn forward(
+    request: ControlRequest,
+    tx: &mpsc::Sender<ControlCommand>,
+) -> Result<Value, RpcError> {
+    let (reply, rx) = oneshot::channel();
+    tx.send(ControlCommand { request, reply })
+        .await
+        .map_err(|_| RpcError::new(INTERNAL_ERROR, "daemon is shutting down"))?;
+    rx.await
+        .map_err(|_| RpcError::new(INTERNAL_ERROR, "daemon dropped the request"))?
+}
+
+/// Send one request to the running daemon and return its `result`.
+pub async fn call(method: &str, params: Value) -> Result<Value, ControlError> {
+    call_at(&socket_path(), method, params).await
+}
+
+async fn call_at(path: &Path, method: &str, params: Value) -> Result<Value, ControlError> {
+    let stream = UnixStream::connect(path)
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused => {
+                ControlError::NotRunning(path.to_path_buf())
+            }
+            _ => ControlError::IO(e),
+        })?;
+    let (reader, mut writer) = stream.into_split();
+
+    let mut encoded = serde_json::to_string(&RpcRequest::new(1, method, params))?;
+    encoded.push('\n');
+    writer.write_all(encoded.as_bytes()).await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let line = timeout(CLIENT_TIMEOUT, lines.next_line())
+        .await
+        .map_err(|_| ControlError::Timeout)??
+        .ok_or(ControlError::Closed)?;
+
+    let response: RpcResponse = serde_json::from_str(&line)?;
+    match (response.result, response.error) {
+        (_, Some(err)) => Err(ControlError::Rpc(err)),
+        (Some(result), None) => Ok(result),
+        (None, None) => Ok(Value::Null),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::{METHOD_NOT_FOUND, PLAYER_NOT_FOUND};
+    use serde_json::json;
+
+    fn temp_socket_path(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        env::temp_dir()
+            .join(format!(
+                "mprisence-ctl-{}-{}-{}",
+                name,
+                std::process::id(),
+                nanos
+            ))
+            .join(SOCKET_NAME)
+    }
+
+    /// Answers requests the way the daemon would, without any players.
+    fn spawn_fake_daemon(mut rx: mpsc::Receiver<ControlCommand>) {
+        tokio::spawn(async move {
+            while let Some(cmd) = rx.recv().await {
+                let result = match cmd.request {
+                    ControlRequest::Pause { .. } => Ok(json!({"paused": true})),
+                    ControlRequest::Clear { player } => Err(RpcError::new(
+                        PLAYER_NOT_FOUND,
+                        format!("no player '{player}'"),
+                    )),
+                    other => Ok(json!(format!("{other:?}"))),
+                };
+                let _ = cmd.reply.send(result);
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn round_trips_requests_through_socket() {
+        let path = temp_socket_path("roundtrip");
+        let (tx, rx) = mpsc::channel(4);
+        spawn_fake_daemon(rx);
+        let server = serve_at(&path, tx).await.expect("server should bind");
+
+        let paused = call_at(&path, "pause", Value::Null).await.unwrap();
+        assert_eq!(paused, json!({"paused": true}));
+
+        let err = call_at(&path, "clear", json!({"player": "vlc"}))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ControlError::Rpc(RpcError { code, .. }) if code == PLAYER_NOT_FOUND)
+        );
+
+        let err = call_at(&path, "bogus", Value::Null).await.unwrap_err();
+        assert!(
+            matches!(err, ControlError::Rpc(RpcError { code, .. }) if code == METHOD_NOT_FOUND)
+        );
+
+        drop(server);
+        assert!(!path.exists(), "socket file should be removed on drop");
+    }
+
+    #[tokio::test]
+    async fn refuses_to_replace_live_socket_but_replaces_stale_one() {
+        let path = temp_socket_path("stale");
+        let (tx, _rx) = mpsc::channel(4);
+        let server = serve_at(&path, tx.clone()).await.expect("first bind");
+
+        assert!(matches!(
+            serve_at(&path, tx.clone()).await,
+            Err(ControlError::AlreadyRunning(_))
+        ));
+
+        // Leave a socket file behind with nothing listening on it.
+        let stale = std::os::unix::net::UnixListener::bind(path.with_extension("old")).unwrap();
+        drop(server);
+        std::fs::rename(path.with_extension("old"), &path).unwrap();
+        drop(stale);
+
+        let _server = serve_at(&path, tx).await.expect("stale socket is replaced");
+    }
+
+    #[tokio::test]
+    async fn reports_not_running_without_socket() {
+        let path = temp_socket_path("missing");
+        let err = call_at(&path, "status", Value::Null).await.unwrap_err();
+        assert!(matches!(err, ControlError::NotRunning(_)));
+    }
+
+    #[tokio::test]
+    async fn hidden_flag_is_watched_without_a_socket() {
+        let socket = temp_socket_path("nosocket");
+        let dir = socket.parent().unwrap().to_path_buf();
+        std::fs::create_dir_all(&dir).unwrap();
+        // A file where the socket directory should be makes the bind fail.
+        let blocker = dir.join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+        let flag = dir.join("hidden");
+        std::fs::write(&flag, "").unwrap();
+
+        let (tx, mut rx) = mpsc::channel(4);
+        let server = serve_with(&blocker.join(SOCKET_NAME), flag, tx).await;
+        assert!(server.path.is_none());
+
+        let cmd = timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("flag watcher should fire")
+            .unwrap();
+        assert!(matches!(cmd.request, ControlRequest::Pause { .. }));
+        drop(server);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn runtime_dir_must_be_a_private_directory() {
+        let dir = temp_socket_path("private").parent().unwrap().to_path_buf();
+        ensure_private_dir(&dir).expect("own directory is accepted");
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let link = dir.with_extension("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        assert!(matches!(
+            ensure_private_dir(&link),
+            Err(ControlError::ForeignRuntimeDir(_))
+        ));
+        let _ = std::fs::remove_file(link);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::template::ActivityTexts;
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// No tracked presence matches the `player` parameter.
+pub const PLAYER_NOT_FOUND: i64 = -32001;
+
+// ─── Wire format ──────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    pub fn new(id: u64, method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: Value::from(id),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn from_result(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(value) => (Some(value), None),
+            Err(err) => (None, Some(err)),
+        };
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+// ─── Daemon requests ──────────────────────────────────────────────
+
+/// A parsed control request, dispatched to `Mprisence` on its run loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlRequest {
+    /// List tracked presences and their last rendered activity texts.
+    Status,
+    /// Re-render and re-push one player (or all players when `None`).
+    Refresh {
+        player: Option<String>,
+    },
+    /// Clear one player's Discord activity until its next update.
+    Clear {
+        player: String,
+    },
+    /// Stop publishing activities globally; listeners keep running. With a
+    /// duration the daemon resumes on its own once it elapses.
+    Pause {
+        duration_secs: Option<u64>,
+    },
+    Resume,
+    /// Pause indefinitely if running, resume if paused.
+    Toggle,
+    /// Re-read the config file.
+    Reload,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PlayerParams {
+    #[serde(default)]
+    player: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PauseParams {
+    #[serde(default)]
+    duration_secs: Option<u64>,
+}
+
+impl ControlRequest {
+    pub fn from_rpc(method: &str, params: Value) -> Result<Self, RpcError> {
+        fn parse<T: Default + serde::de::DeserializeOwned>(params: &Value) -> Result<T, RpcError> {
+            if params.is_null() {
+                return Ok(T::default());
+            }
+            serde_json::from_value(params.clone())
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+        }
+        let player_params = || parse::<PlayerParams>(&params);
+
+        match method {
+            "status" => Ok(Self::Status),
+            "refresh" => Ok(Self::Refresh {
+                player: player_params()?.player,
+            }),
+            "clear" => match player_params()?.player {
+                Some(player) => Ok(Self::Clear { player }),
+                None => Err(RpcError::new(INVALID_PARAMS, "missing 'player'")),
+            },
+            "pause" => Ok(Self::Pause {
+                duration_secs: parse::<PauseParams>(&params)?.duration_secs,
+            }),
+            "resume" => Ok(Self::Resume),
+            "toggle" => Ok(Self::Toggle),
+            "reload" => Ok(Self::Reload),
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{other}'"),
+            )),
+        }
+    }
+}
+
+// ─── Daemon replies ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub paused: bool,
+    /// Unix time at which a snooze ends; `None` while paused indefinitely.
+    #[serde(default)]
+    pub paused_until: Option<u64>,
+    pub presences: Vec<PresenceStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceStatus {
+    /// Registry key (normalized identity, or bus name for bridge players).
+    pub key: String,
+    pub identity: String,
+    pub bus_name: String,
+    pub playback_status: Option<String>,
+    /// Whether an activity is currently shown in Discord for this player.
+    pub activity_set: bool,
+    pub texts: Option<ActivityTexts>,
+    /// `[arbitration]` outcome, absent while arbitration is disabled.
+    #[serde(default)]
+    pub arbitration: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_player_params() {
+        assert_eq!(
+            ControlRequest::from_rpc("clear", json!({"player": "vlc"})),
+            Ok(ControlRequest::Clear {
+                player: "vlc".into()
+            })
+        );
+        assert_eq!(
+            ControlRequest::from_rpc("refresh", Value::Null),
+            Ok(ControlRequest::Refresh { player: None })
+        );
+    }
+
+    #[test]
+    fn parses_pause_duration() {
+        assert_eq!(
+            ControlRequest::from_rpc("pause", json!({"duration_secs": 1800})),
+            Ok(ControlRequest::Pause {
+                duration_secs: Some(1800)
+            })
+        );
+        assert_eq!(
+            ControlRequest::from_rpc("pause", Value::Null),
+            Ok(ControlRequest::Pause {
+                duration_secs: None
+            })
+        );
+    }
+
+    #[test]
+    fn clear_requires_player() {
+        let err = ControlRequest::from_rpc("clear", json!({})).unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn unknown_method_is_rejected() {
+        let err = ControlRequest::from_rpc("shutdown", Value::Null).unwrap_err();
+        assert_eq!(err.code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn response_omits_absent_result_or_error() {
+        let ok = RpcResponse::from_result(json!(1), Ok(json!(true)));
+        let encoded = serde_json::to_value(&ok).unwrap();
+        assert_eq!(encoded, json!({"jsonrpc": "2.0", "id": 1, "result": true}));
+
+        let err = RpcResponse::from_result(json!(2), Err(RpcError::new(INTERNAL_ERROR, "boom")));
+        let encoded = serde_json::to_value(&err).unwrap();
+        assert!(encoded.get("result").is_none());
+        assert_eq!(encoded["error"]["code"], json!(INTERNAL_ERROR));
+    }
+}
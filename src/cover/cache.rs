@@ -2,16 +2,16 @@ use log::{debug, error, trace, warn};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::{
+    collections::HashMap,
     fs, io,
     path::{Path, PathBuf},
     sync::Mutex,
     time::{Duration, SystemTime},
 };
 
+use crate::config::schema::CoverCacheConfig;
 use crate::cover::error::CoverArtError;
 
-const MAX_CACHE_ENTRIES: usize = 1024;
-const MAX_CACHE_SIZE_BYTES: u64 = 32 * 1024 * 1024; // 32 MB soft limit
 pub const MAX_CACHED_IMAGE_BYTES: usize = 8 * 1024 * 1024; // 8 MB per entry cap
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -19,28 +19,42 @@ pub struct CacheEntry {
     pub url: String,
     pub provider: String,
     pub expires_at: SystemTime,
-    #[serde(default = "CacheEntry::default_last_validated")]
+    #[serde(default = "CacheEntry::unix_epoch")]
     pub last_validated: SystemTime,
+    /// Entries written before this field existed read as the epoch until the
+    /// cache backfills them from the entry file's mtime on load.
+    #[serde(default = "CacheEntry::unix_epoch")]
+    pub stored_at: SystemTime,
     #[serde(default)]
     pub data_file: Option<String>,
+    /// When the hosted URL itself stops working, for providers whose uploads
+    /// expire. Unlike `expires_at` this is not capped by the cache TTL.
+    #[serde(default)]
+    pub url_expires_at: Option<SystemTime>,
 }
 
 impl CacheEntry {
-    fn default_last_validated() -> SystemTime {
+    fn unix_epoch() -> SystemTime {
         SystemTime::UNIX_EPOCH
     }
 }
 
-#[derive(Default)]
-struct CacheUsage {
-    entries: usize,
-    bytes: u64,
+/// Running totals of entry files and bytes on disk (metadata plus data).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: u64,
 }
 
 pub struct CoverCache {
     cache_dir: PathBuf,
     ttl: Duration,
     usage: Mutex<CacheUsage>,
+    max_entries: usize,
+    max_bytes: u64,
+    /// Last use per key, for LRU eviction. Seeded from entry file mtimes,
+    /// which `get_by_key` bumps so recency survives restarts.
+    last_used: Mutex<HashMap<String, SystemTime>>,
 }
 
 impl CoverCache {
@@ -163,13 +177,19 @@ impl CoverCache {
         Ok(None)
     }
 
-    pub fn new(ttl: Duration) -> Result<Self, CoverArtError> {
+    pub fn new(ttl: Duration, limits: &CoverCacheConfig) -> Result<Self, CoverArtError> {
         trace!(
             "Creating new cover cache instance with TTL: {}s",
             ttl.as_secs()
         );
-        let cache_dir = Self::get_cache_directory()?;
+        Self::with_dir(Self::get_cache_directory()?, ttl, limits)
+    }
 
+    pub fn with_dir(
+        cache_dir: PathBuf,
+        ttl: Duration,
+        limits: &CoverCacheConfig,
+    ) -> Result<Self, CoverArtError> {
         Self::ensure_directory(&cache_dir)?;
         debug!("Initialized cover cache in directory: {:?}", cache_dir);
 
@@ -177,6 +197,9 @@ impl CoverCache {
             cache_dir,
             ttl,
             usage: Mutex::new(CacheUsage::default()),
+            max_entries: limits.max_entries,
+            max_bytes: limits.max_bytes,
+            last_used: Mutex::new(HashMap::new()),
         };
         cache.recalculate_usage()?;
 
@@ -261,10 +284,11 @@ impl CoverCache {
                     let now = SystemTime::now();
                     if now > entry.expires_at {
                         debug!("Cache entry expired, removing file");
-                        let _ = fs::remove_file(&path);
+                        let _ = self.remove_entry_at_path(&path);
                         return Ok(None);
                     }
 
+                    self.touch(key, &path, now);
                     debug!("Found valid cache entry from provider: {}", entry.provider);
                     trace!("Cached URL: {}", entry.url);
                     Ok(Some(entry))
@@ -274,7 +298,7 @@ impl CoverCache {
                         "Failed to deserialize cache entry, removing corrupt file: {}",
                         e
                     );
-                    let _ = fs::remove_file(&path);
+                    let _ = self.remove_entry_at_path(&path);
                     Ok(None)
                 }
             },
@@ -322,29 +346,34 @@ impl CoverCache {
             })
             .unwrap_or(self.ttl);
 
-        let data_file = if let Some(bytes) = cached_bytes {
-            match self.persist_bytes(key, bytes)? {
+        // Drop the previous blob first so re-storing a key doesn't count its
+        // bytes twice.
+        if let Some(name) = self
+            .read_entry_from_path(&path)
+            .and_then(|existing| existing.data_file)
+        {
+            self.remove_data_file(&name);
+        }
+        let data_file = match cached_bytes {
+            Some(bytes) => match self.persist_bytes(key, bytes)? {
                 Some((file_name, len)) => {
                     self.adjust_usage(0, len as i64);
                     Some(file_name)
                 }
                 None => None,
-            }
-        } else if let Some(existing) = self.read_entry_from_path(&path) {
-            if let Some(name) = existing.data_file {
-                self.remove_data_file(&name);
-            }
-            None
-        } else {
-            None
+            },
+            None => None,
         };
 
+        let now = SystemTime::now();
         let entry = CacheEntry {
             url: url.to_string(),
             provider: provider.to_string(),
-            expires_at: SystemTime::now() + ttl,
-            last_validated: SystemTime::now(),
+            expires_at: now + ttl,
+            last_validated: now,
+            stored_at: now,
             data_file: data_file.clone(),
+            url_expires_at: provider_ttl.map(|ttl| now + ttl),
         };
 
         let metadata_len = match self.persist_entry(&path, &entry) {
@@ -360,9 +389,8 @@ impl CoverCache {
             if existed_before { 0 } else { 1 },
             metadata_len as i64 - previous_metadata_len as i64,
         );
-        if self.usage_exceeds_limits() {
-            self.enforce_limits()?;
-        }
+        self.last_used.lock().unwrap().insert(key.to_string(), now);
+        self.evict_lru(key)?;
 
         debug!(
             "Successfully stored cache entry from provider: {}",
@@ -403,6 +431,9 @@ impl CoverCache {
     }
 
     fn remove_entry_at_path(&self, path: &Path) -> Result<(), CoverArtError> {
+        if let Some(key) = path.file_name().and_then(|name| name.to_str()) {
+            self.last_used.lock().unwrap().remove(key);
+        }
         if path.exists() {
             let metadata_len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
             if let Some(existing) = self.read_entry_from_path(path) {
@@ -420,6 +451,122 @@ impl CoverCache {
         Ok(())
     }
 
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    pub fn usage(&self) -> CacheUsage {
+        *self.usage.lock().unwrap()
+    }
+
+    /// Configured entry limit; 0 means unlimited.
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Configured size limit in bytes; 0 means unlimited.
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Record a cache hit. The mtime bump is best effort.
+    fn touch(&self, key: &str, path: &Path, now: SystemTime) {
+        self.last_used.lock().unwrap().insert(key.to_string(), now);
+        if let Err(e) = fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(now))
+        {
+            trace!("Failed to bump cache entry mtime {:?}: {}", path, e);
+        }
+    }
+
+    /// Evict least recently used entries, with their data files, until usage
+    /// is back within limits. `keep` (the entry just stored) is never evicted.
+    fn evict_lru(&self, keep: &str) -> Result<(), CoverArtError> {
+        while self.usage_exceeds_limits() {
+            let victim = self
+                .last_used
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(key, _)| key.as_str() != keep)
+                .min_by_key(|(_, used)| **used)
+                .map(|(key, _)| key.clone());
+            let Some(victim) = victim else {
+                break;
+            };
+            debug!("Evicting least recently used cache entry {}", victim);
+            self.remove_by_key(&victim)?;
+        }
+        Ok(())
+    }
+
+    /// Every readable entry, expired ones included, sorted by key. Unlike
+    /// `get_by_key` nothing is removed while listing.
+    pub fn entries(&self) -> Result<Vec<(String, CacheEntry)>, CoverArtError> {
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&self.cache_dir)?.flatten() {
+            let path = dir_entry.path();
+            if path.is_dir() || path.extension().is_some() {
+                continue;
+            }
+            let Some(key) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if let Some(entry) = self.read_entry_from_path(&path) {
+                entries.push((key.to_string(), entry));
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Look an entry up by its full key or a unique prefix of it.
+    pub fn find(&self, prefix: &str) -> Result<Option<(String, CacheEntry)>, CoverArtError> {
+        let mut matches: Vec<_> = self
+            .entries()?
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .collect();
+        if let Some(exact) = matches.iter().position(|(key, _)| key == prefix) {
+            return Ok(Some(matches.swap_remove(exact)));
+        }
+        match matches.len() {
+            0 => Ok(None),
+            1 => Ok(matches.pop()),
+            n => Err(CoverArtError::other(format!(
+                "key prefix '{prefix}' is ambiguous ({n} entries)"
+            ))),
+        }
+    }
+
+    /// Size of an entry's cached image bytes, if any.
+    pub fn data_len(&self, entry: &CacheEntry) -> u64 {
+        entry
+            .data_file
+            .as_deref()
+            .and_then(|name| fs::metadata(self.data_path_from_name(name)).ok())
+            .map(|meta| meta.len())
+            .unwrap_or(0)
+    }
+
+    /// Remove every entry `filter` accepts, with its data file.
+    pub fn purge(
+        &self,
+        mut filter: impl FnMut(&str, &CacheEntry) -> bool,
+    ) -> Result<usize, CoverArtError> {
+        let mut removed = 0;
+        for (key, entry) in self.entries()? {
+            if filter(&key, &entry) {
+                self.remove_by_key(&key)?;
+                removed += 1;
+            }
+        }
+        debug!("Purged {} cache entries", removed);
+        Ok(removed)
+    }
+
     pub fn clean(&self) -> Result<usize, CoverArtError> {
         let mut cleaned = 0;
         let now = SystemTime::now();
@@ -502,71 +649,10 @@ impl CoverCache {
         Ok(data.len() as u64)
     }
 
-    fn enforce_limits(&self) -> Result<(), CoverArtError> {
-        let mut entries = Vec::new();
-        let mut total_size: u64 = 0;
-
-        for entry in fs::read_dir(&self.cache_dir)? {
-            let entry = entry?;
-            let metadata = entry.metadata()?;
-            if !metadata.is_file() {
-                continue;
-            }
-
-            let path = entry.path();
-            if path
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .map(|ext| ext.eq_ignore_ascii_case("bin"))
-                .unwrap_or(false)
-            {
-                continue;
-            }
-
-            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
-            let mut len = metadata.len();
-
-            if let Some(cache_entry) = self.read_entry_from_path(&path) {
-                if let Some(ref name) = cache_entry.data_file {
-                    if let Ok(data_metadata) = fs::metadata(self.data_path_from_name(name)) {
-                        len = len.saturating_add(data_metadata.len());
-                    }
-                }
-            }
-
-            total_size = total_size.saturating_add(len);
-            entries.push((path, modified, len));
-        }
-
-        if entries.len() <= MAX_CACHE_ENTRIES && total_size <= MAX_CACHE_SIZE_BYTES {
-            return Ok(());
-        }
-
-        entries.sort_by(|a, b| a.1.cmp(&b.1));
-
-        while entries.len() > MAX_CACHE_ENTRIES || total_size > MAX_CACHE_SIZE_BYTES {
-            if entries.is_empty() {
-                break;
-            }
-
-            let (path, _, len) = entries.remove(0);
-            match self.remove_entry_at_path(&path) {
-                Ok(_) => {
-                    warn!("Evicted cache entry {:?}", path);
-                    total_size = total_size.saturating_sub(len);
-                }
-                Err(_) => {
-                    warn!("Failed to evict cache entry {:?}", path);
-                }
-            }
-        }
-
-        Ok(())
-    }
-
     fn usage_exceeds_limits(&self) -> bool {
         let usage = self.usage.lock().unwrap();
-        usage.entries > MAX_CACHE_ENTRIES || usage.bytes > MAX_CACHE_SIZE_BYTES
+        (self.max_entries > 0 && usage.entries > self.max_entries)
+            || (self.max_bytes > 0 && usage.bytes > self.max_bytes)
     }
 
     fn adjust_usage(&self, entries_delta: isize, bytes_delta: i64) {
@@ -592,6 +678,7 @@ impl CoverCache {
 
     fn recalculate_usage(&self) -> Result<(), CoverArtError> {
         let mut usage = CacheUsage::default();
+        let mut last_used = HashMap::new();
 
         for entry in fs::read_dir(&self.cache_dir)? {
             let entry = entry?;
@@ -619,11 +706,22 @@ impl CoverCache {
             }
 
             usage.entries = usage.entries.saturating_add(1);
+            let cache_entry = self.read_entry_from_path(&path);
+            if let Some(legacy) = cache_entry
+                .as_ref()
+                .filter(|entry| entry.stored_at == SystemTime::UNIX_EPOCH)
+            {
+                self.backfill_stored_at(&path, legacy);
+            }
             if let Ok(meta) = fs::metadata(&path) {
                 usage.bytes = usage.bytes.saturating_add(meta.len());
+                if let Some(key) = path.file_name().and_then(|name| name.to_str()) {
+                    let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    last_used.insert(key.to_string(), modified);
+                }
             }
 
-            if let Some(cache_entry) = self.read_entry_from_path(&path) {
+            if let Some(cache_entry) = cache_entry {
                 if let Some(ref name) = cache_entry.data_file {
                     let data_path = self.data_path_from_name(name);
                     if let Ok(meta) = fs::metadata(&data_path) {
@@ -633,8 +731,221 @@ impl CoverCache {
             }
         }
 
-        let mut lock = self.usage.lock().unwrap();
-        *lock = usage;
+        *self.usage.lock().unwrap() = usage;
+        *self.last_used.lock().unwrap() = last_used;
         Ok(())
     }
+
+    /// Record when an entry from before `stored_at` existed was stored. Its
+    /// mtime still says so only until the first hit bumps it, so it is
+    /// written into the entry on load and the mtime is put back for LRU
+    /// seeding. Best effort: a failed write leaves the entry at the epoch.
+    fn backfill_stored_at(&self, path: &Path, legacy: &CacheEntry) {
+        let Ok(modified) = fs::metadata(path).and_then(|meta| meta.modified()) else {
+            return;
+        };
+        let mut entry = legacy.clone();
+        entry.stored_at = modified;
+        let result = self.persist_entry(path, &entry).and_then(|_| {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(modified))
+                .map_err(Into::into)
+        });
+        match result {
+            Ok(()) => trace!("Backfilled stored_at for legacy cache entry {:?}", path),
+            Err(e) => warn!("Failed to backfill stored_at for {:?}: {}", path, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> (CoverCache, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "mprisence-cache-{}-{}-{}",
+            name,
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0)
+        ));
+        let limits = CoverCacheConfig::default();
+        let cache = CoverCache::with_dir(dir.clone(), Duration::from_secs(3600), &limits).unwrap();
+        (cache, dir)
+    }
+
+    fn temp_cache_with_limits(
+        name: &str,
+        max_entries: usize,
+        max_bytes: u64,
+    ) -> (CoverCache, PathBuf) {
+        let (cache, dir) = temp_cache(name);
+        let limits = CoverCacheConfig {
+            max_entries,
+            max_bytes,
+        };
+        drop(cache);
+        let cache = CoverCache::with_dir(dir.clone(), Duration::from_secs(3600), &limits).unwrap();
+        (cache, dir)
+    }
+
+    #[test]
+    fn lists_finds_and_purges_entries() {
+        let (cache, dir) = temp_cache("purge");
+        cache
+            .store_with_key("abc123", "catbox", "https://a/1.png", None, Some(b"one"))
+            .unwrap();
+        cache
+            .store_with_key("abd456", "imgbb", "https://a/2.png", None, None)
+            .unwrap();
+        cache
+            .store_with_key("ff0000", "catbox", "https://a/3.png", None, None)
+            .unwrap();
+
+        let keys: Vec<_> = cache.entries().unwrap().into_iter().map(|e| e.0).collect();
+        assert_eq!(keys, ["abc123", "abd456", "ff0000"]);
+        assert_eq!(cache.usage().entries, 3);
+
+        let (key, entry) = cache.find("abc").unwrap().expect("unique prefix");
+        assert_eq!(key, "abc123");
+        assert_eq!(cache.data_len(&entry), 3);
+        assert!(cache.find("ab").is_err());
+        assert!(cache.find("zz").unwrap().is_none());
+
+        let removed = cache.purge(|_, entry| entry.provider == "catbox").unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(cache.usage().entries, 1);
+        assert!(!dir.join("abc123.bin").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn legacy_entries_are_aged_by_their_mtime_at_load() {
+        let (cache, dir) = temp_cache("legacy");
+        cache
+            .store_with_key("old", "catbox", "https://a/old.png", None, None)
+            .unwrap();
+        cache
+            .store_with_key("new", "catbox", "https://a/new.png", None, None)
+            .unwrap();
+
+        // Rewrite `old` as an entry from before `stored_at` was recorded.
+        let path = dir.join("old");
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("stored_at");
+        fs::write(&path, serde_json::to_vec(&json).unwrap()).unwrap();
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(2 * 3600);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(two_hours_ago))
+            .unwrap();
+
+        // Loading backfills the age; a later hit bumping the mtime must not
+        // make the entry look fresh.
+        let cache =
+            CoverCache::with_dir(dir.clone(), cache.ttl, &CoverCacheConfig::default()).unwrap();
+        let (_, entry) = cache.find("old").unwrap().unwrap();
+        assert_eq!(entry.stored_at, two_hours_ago);
+        assert_eq!(
+            fs::metadata(&path).unwrap().modified().unwrap(),
+            two_hours_ago
+        );
+        cache.get_by_key("old").unwrap();
+        let (_, entry) = cache.find("old").unwrap().unwrap();
+        assert_eq!(entry.stored_at, two_hours_ago);
+
+        let now = SystemTime::now();
+        let removed = cache
+            .purge(|_, entry| {
+                now.duration_since(entry.stored_at)
+                    .is_ok_and(|age| age >= Duration::from_secs(3600))
+            })
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert!(cache.find("new").unwrap().is_some());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn provider_ttl_sets_url_expiry() {
+        let (cache, dir) = temp_cache("ttl");
+        cache
+            .store_with_key(
+                "k",
+                "catbox",
+                "https://a/1.png",
+                Some(Duration::from_secs(7200)),
+                None,
+            )
+            .unwrap();
+        let entry = cache.get_by_key("k").unwrap().unwrap();
+        let url_expires_at = entry.url_expires_at.expect("provider ttl recorded");
+        // Cache expiry is capped at the cache TTL; the URL expiry is not.
+        assert!(url_expires_at > entry.expires_at);
+        assert!(entry.stored_at <= entry.expires_at);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry_over_entry_limit() {
+        let (cache, dir) = temp_cache_with_limits("lru", 2, 0);
+        cache
+            .store_with_key("a", "catbox", "https://a/a.png", None, Some(b"aaaa"))
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        cache
+            .store_with_key("b", "catbox", "https://a/b.png", None, None)
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        // Reading `a` makes `b` the least recently used.
+        assert!(cache.get_by_key("a").unwrap().is_some());
+        std::thread::sleep(Duration::from_millis(5));
+        cache
+            .store_with_key("c", "catbox", "https://a/c.png", None, None)
+            .unwrap();
+
+        assert!(cache.get_by_key("b").unwrap().is_none());
+        assert!(cache.get_by_key("a").unwrap().is_some());
+        assert!(cache.get_by_key("c").unwrap().is_some());
+        assert_eq!(cache.usage().entries, 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn evicts_blobs_over_byte_limit_but_keeps_new_entry() {
+        let (cache, dir) = temp_cache_with_limits("bytes", 0, 1000);
+        cache
+            .store_with_key("a", "catbox", "https://a/a.png", None, Some(&[1; 400]))
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        cache
+            .store_with_key("b", "catbox", "https://a/b.png", None, Some(&[2; 400]))
+            .unwrap();
+
+        assert!(!dir.join("a").exists());
+        assert!(!dir.join("a.bin").exists());
+        assert!(dir.join("b.bin").exists());
+        assert!(cache.usage().bytes <= 1000);
+
+        // Re-storing a key replaces its blob instead of counting it twice.
+        let before = cache.usage().bytes;
+        cache
+            .store_with_key("b", "catbox", "https://a/b.png", None, Some(&[2; 400]))
+            .unwrap();
+        assert!(dir.join("b").exists());
+        assert_eq!(cache.usage().bytes, before);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -1,27 +1,64 @@
+use futures::stream::{FuturesUnordered, StreamExt};
 use log::{debug, info, trace, warn};
+use parking_lot::Mutex;
 use reqwest::StatusCode;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
 use tokio::task::spawn_blocking;
+use tokio::time::MissedTickBehavior;
 use tokio_util::sync::CancellationToken;
 use url::{Host, Url};
 
 use crate::config;
+use crate::config::schema::{CoverProviderConfig, CoverStrategy, PlayerCoverConfig};
 use crate::metadata::MetadataSource;
 
 pub mod cache;
 pub mod error;
+pub mod normalize;
 pub mod providers;
 pub mod sources;
 
 use cache::{CacheEntry, CoverCache, MAX_CACHED_IMAGE_BYTES};
 use error::CoverArtError;
+use normalize::Normalizer;
 use providers::{create_shared_client, CoverArtProvider, CoverResult};
 use sources::{search_local_cover_art, ArtSource};
 
 const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60); // 24 hours
 const CACHE_VALIDATION_INTERVAL: Duration = Duration::from_secs(60 * 60); // 1 hour
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+/// Hosted URLs that expire are re-uploaded this long before they do.
+const REUPLOAD_LEAD: Duration = Duration::from_secs(10 * 60);
+/// Entries no longer on screen are kept fresh in the background for this
+/// long after they were last served.
+const RECENT_USE_WINDOW: Duration = Duration::from_secs(3 * 60 * 60);
+
+/// What the background maintenance pass should do with a cache entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Maintenance {
+    Reupload,
+    Revalidate,
+}
+
+impl Maintenance {
+    fn for_entry(entry: &CacheEntry, now: SystemTime) -> Option<Self> {
+        if entry
+            .url_expires_at
+            .is_some_and(|expires| expires <= now + REUPLOAD_LEAD)
+        {
+            return Some(Self::Reupload);
+        }
+        let stale = now
+            .duration_since(entry.last_validated)
+            .map(|elapsed| elapsed >= CACHE_VALIDATION_INTERVAL)
+            .unwrap_or(false);
+        stale.then_some(Self::Revalidate)
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct DirectUrlPolicy {
@@ -45,58 +82,397 @@ impl DirectUrlPolicy {
     }
 }
 
+/// Providers and strategy for a single lookup.
+struct ProviderPlan<'a> {
+    providers: Vec<&'a dyn CoverArtProvider>,
+    strategy: CoverStrategy,
+}
+
+/// One step of walking a provider plan.
+enum Step<'a> {
+    One(&'a dyn CoverArtProvider),
+    /// Metadata-only providers run concurrently; the first hit wins.
+    Race(Vec<&'a dyn CoverArtProvider>),
+}
+
+impl<'a> ProviderPlan<'a> {
+    /// Whether any provider uploads the image itself, and so needs the
+    /// normalized source rather than just the track's metadata.
+    fn uploads(&self) -> bool {
+        self.providers
+            .iter()
+            .any(|provider| !provider.supports_metadata_only())
+    }
+
+    /// Racing gathers every metadata-only provider into one step at the
+    /// position of the first; upload providers keep their order.
+    fn steps(&self, source: Option<&ArtSource>) -> Vec<Step<'a>> {
+        let mut steps: Vec<Step<'a>> = Vec::new();
+        let mut race_step = None;
+        for &provider in &self.providers {
+            let supported = match source {
+                Some(s) => provider.supports_source_type(s),
+                None => provider.supports_metadata_only(),
+            };
+            if !supported {
+                trace!(
+                    "Provider {} does not support this source type",
+                    provider.name()
+                );
+                continue;
+            }
+            if self.strategy == CoverStrategy::Race && provider.supports_metadata_only() {
+                match race_step {
+                    Some(index) => {
+                        if let Step::Race(group) = &mut steps[index] {
+                            group.push(provider);
+                        }
+                    }
+                    None => {
+                        race_step = Some(steps.len());
+                        steps.push(Step::Race(vec![provider]));
+                    }
+                }
+            } else {
+                steps.push(Step::One(provider));
+            }
+        }
+        steps
+    }
+}
+
 pub struct CoverManager {
-    providers: Vec<Box<dyn CoverArtProvider>>,
+    /// Every provider named in `[cover.provider]` or a `[player.*.cover]`
+    /// list, keyed by its configured name.
+    providers: Vec<(String, Box<dyn CoverArtProvider>)>,
+    /// The global `[cover.provider]` list.
+    order: Vec<String>,
+    strategy: CoverStrategy,
     cache: Arc<CoverCache>,
     config: Arc<config::ConfigManager>,
+    normalizer: Normalizer,
+    /// Cache keys served recently, kept fresh by `maintain`.
+    recent: Mutex<HashMap<String, RecentUse>>,
+}
+
+/// When a cache entry was last served or seen on screen, and the URL it
+/// served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecentUse {
+    used: Instant,
+    url: String,
 }
 
 impl CoverManager {
     pub fn new(config: &Arc<config::ConfigManager>) -> Result<Self, CoverArtError> {
         info!("Initializing cover art manager");
+        let cache = CoverCache::new(CACHE_TTL, &config.cover_config().cache)?;
+        Ok(Self::with_cache(config, cache))
+    }
+
+    /// A manager caching under `dir` instead of the user's cache directory.
+    #[cfg(test)]
+    pub(crate) fn in_dir(config: &Arc<config::ConfigManager>, dir: PathBuf) -> Self {
+        let cache = CoverCache::with_dir(dir, CACHE_TTL, &config.cover_config().cache).unwrap();
+        Self::with_cache(config, cache)
+    }
+
+    fn with_cache(config: &Arc<config::ConfigManager>, cache: CoverCache) -> Self {
         let cover_config = config.cover_config();
-        let cache = Arc::new(CoverCache::new(CACHE_TTL)?);
-        let mut providers: Vec<Box<dyn CoverArtProvider>> = Vec::new();
-
-        for provider_name in &cover_config.provider.provider {
-            match provider_name.as_str() {
-                "musicbrainz" => {
-                    debug!("Adding MusicBrainz provider");
-                    providers.push(Box::new(
-                        providers::musicbrainz::MusicbrainzProvider::with_config(
-                            cover_config.provider.musicbrainz.clone(),
-                        ),
-                    ));
-                }
-                "imgbb" => {
-                    if cover_config.provider.imgbb.api_key.is_some() {
-                        debug!("Adding ImgBB provider");
-                        providers.push(Box::new(providers::imgbb::ImgbbProvider::with_config(
-                            cover_config.provider.imgbb.clone(),
-                        )));
-                    } else {
-                        warn!("Skipping ImgBB provider - no API key configured");
-                    }
-                }
-                "catbox" => {
-                    debug!("Adding Catbox provider");
-                    providers.push(Box::new(providers::catbox::CatboxProvider::with_config(
-                        cover_config.provider.catbox.clone(),
-                    )));
+        let cache = Arc::new(cache);
+        let mut names = cover_config.provider.provider.clone();
+        for player in config.player_configs().values() {
+            let player_names = player.cover.as_ref().and_then(|c| c.provider.as_ref());
+            for name in player_names.into_iter().flatten() {
+                if !names.contains(name) {
+                    names.push(name.clone());
                 }
-                unknown => warn!("Skipping unknown provider: {}", unknown),
             }
         }
+        let providers: Vec<(String, Box<dyn CoverArtProvider>)> = names
+            .into_iter()
+            .filter_map(|name| {
+                let provider = Self::build_provider(&name, &cover_config.provider)?;
+                Some((name, provider))
+            })
+            .collect();
 
         if providers.is_empty() {
             warn!("No cover art providers configured");
         }
 
-        Ok(Self {
+        Self {
             providers,
+            order: cover_config.provider.provider.clone(),
+            strategy: cover_config.provider.strategy,
             cache,
             config: config.clone(),
-        })
+            normalizer: Normalizer::new(cover_config.normalize.clone()),
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keep maintaining the covers `previous` served recently. Called when
+    /// a config reload replaces the manager, so covers on screen are still
+    /// revalidated and re-uploaded before their hosted URL expires.
+    pub fn carry_recent_from(&self, previous: &CoverManager) {
+        let carried = previous.recent.lock().clone();
+        let mut recent = self.recent.lock();
+        for (key, carried) in carried {
+            let entry = recent.entry(key).or_insert_with(|| carried.clone());
+            if carried.used > entry.used {
+                *entry = carried;
+            }
+        }
+    }
+
+    fn build_provider(
+        name: &str,
+        config: &CoverProviderConfig,
+    ) -> Option<Box<dyn CoverArtProvider>> {
+        let provider: Box<dyn CoverArtProvider> = match name {
+            "musicbrainz" => {
+                debug!("Adding MusicBrainz provider");
+                Box::new(providers::musicbrainz::MusicbrainzProvider::with_config(
+                    config.musicbrainz.clone(),
+                ))
+            }
+            "discogs" => {
+                debug!("Adding Discogs provider");
+                Box::new(providers::discogs::DiscogsProvider::with_config(
+                    config.discogs.clone(),
+                ))
+            }
+            "itunes" => {
+                debug!("Adding iTunes provider");
+                Box::new(providers::itunes::ItunesProvider::with_config(
+                    config.itunes.clone(),
+                ))
+            }
+            "deezer" => {
+                debug!("Adding Deezer provider");
+                Box::new(providers::deezer::DeezerProvider::with_config(
+                    config.deezer.clone(),
+                ))
+            }
+            "imgbb" => {
+                if config.imgbb.api_key.is_none() {
+                    warn!("Skipping ImgBB provider - no API key configured");
+                    return None;
+                }
+                debug!("Adding ImgBB provider");
+                Box::new(providers::imgbb::ImgbbProvider::with_config(
+                    config.imgbb.clone(),
+                ))
+            }
+            "catbox" => {
+                debug!("Adding Catbox provider");
+                Box::new(providers::catbox::CatboxProvider::with_config(
+                    config.catbox.clone(),
+                ))
+            }
+            "http_upload" => {
+                match providers::http_upload::HttpUploadProvider::with_config(
+                    config.http_upload.clone(),
+                ) {
+                    Ok(provider) => {
+                        debug!("Adding HTTP upload provider");
+                        Box::new(provider)
+                    }
+                    Err(e) => {
+                        warn!("Skipping HTTP upload provider - {}", e);
+                        return None;
+                    }
+                }
+            }
+            "s3" => match providers::s3::S3Provider::with_config(config.s3.clone()) {
+                Ok(provider) => {
+                    debug!("Adding S3 provider");
+                    Box::new(provider)
+                }
+                Err(e) => {
+                    warn!("Skipping S3 provider - {}", e);
+                    return None;
+                }
+            },
+            unknown => {
+                warn!("Skipping unknown provider: {}", unknown);
+                return None;
+            }
+        };
+        Some(provider)
+    }
+
+    fn provider(&self, name: &str) -> Option<&dyn CoverArtProvider> {
+        self.providers
+            .iter()
+            .find(|(configured, _)| configured == name)
+            .map(|(_, provider)| provider.as_ref())
+    }
+
+    /// Providers and strategy for one lookup: the player's `[cover]` table
+    /// where set, else `[cover.provider]`.
+    fn plan(&self, player_cover: Option<&PlayerCoverConfig>) -> ProviderPlan<'_> {
+        let names = player_cover
+            .and_then(|cover| cover.provider.as_deref())
+            .unwrap_or(&self.order);
+        ProviderPlan {
+            providers: names
+                .iter()
+                .filter_map(|name| self.provider(name))
+                .collect(),
+            strategy: player_cover
+                .and_then(|cover| cover.strategy)
+                .unwrap_or(self.strategy),
+        }
+    }
+
+    /// Run `maintain` every minute in the background. URLs that were
+    /// replaced or dropped are sent on `stale` so the caller can re-push
+    /// presences still showing them. Only a weak reference is held, so the
+    /// loop stops once this manager is replaced on reload.
+    pub fn spawn_maintenance(self: &Arc<Self>, stale: mpsc::Sender<String>) {
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(MAINTENANCE_INTERVAL);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    trace!("Cover manager dropped, stopping cache maintenance");
+                    break;
+                };
+                for url in manager.maintain().await {
+                    if stale.send(url).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    fn touch(&self, key: &str, url: &str) {
+        let used = RecentUse {
+            used: Instant::now(),
+            url: url.to_string(),
+        };
+        self.recent.lock().insert(key.to_string(), used);
+    }
+
+    /// Mark the entries that served `url` as still on screen, so `maintain`
+    /// keeps them fresh for as long as a presence shows them.
+    pub fn touch_shown(&self, url: &str) {
+        let now = Instant::now();
+        for recent in self.recent.lock().values_mut() {
+            if recent.url == url {
+                recent.used = now;
+            }
+        }
+    }
+
+    /// Keys served or on screen within `RECENT_USE_WINDOW`; older ones are
+    /// forgotten.
+    fn recent_keys(&self) -> Vec<String> {
+        let mut recent = self.recent.lock();
+        recent.retain(|_, recent| recent.used.elapsed() < RECENT_USE_WINDOW);
+        recent.keys().cloned().collect()
+    }
+
+    /// Revalidate recently served entries and re-upload ones whose hosted
+    /// URL is about to expire, off the presence hot path. Returns the URLs
+    /// that are no longer served.
+    pub async fn maintain(&self) -> Vec<String> {
+        let keys = self.recent_keys();
+        trace!("Cover cache maintenance over {} recent entries", keys.len());
+
+        let mut stale = Vec::new();
+        for key in keys {
+            match self.maintain_entry(&key).await {
+                Ok(Some(url)) => stale.push(url),
+                Ok(None) => {}
+                Err(e) => warn!("Cover cache maintenance failed for {}: {}", key, e),
+            }
+        }
+        stale
+    }
+
+    async fn maintain_entry(&self, key: &str) -> Result<Option<String>, CoverArtError> {
+        let Some(mut entry) = self.cache_get_entry(key).await? else {
+            self.recent.lock().remove(key);
+            return Ok(None);
+        };
+        let Some(action) = Maintenance::for_entry(&entry, SystemTime::now()) else {
+            return Ok(None);
+        };
+
+        let old_url = entry.url.clone();
+        match action {
+            Maintenance::Revalidate => {
+                if Self::validate_cover_url(&old_url).await {
+                    entry.last_validated = SystemTime::now();
+                    self.cache_update_entry(key, &entry).await?;
+                    return Ok(None);
+                }
+                warn!(
+                    "Cached cover art URL {} failed revalidation (provider: {})",
+                    old_url, entry.provider
+                );
+            }
+            Maintenance::Reupload => debug!(
+                "Cover art from {} expires soon, re-uploading: {}",
+                entry.provider, old_url
+            ),
+        }
+
+        let provider = entry.provider.clone();
+        let replaced = match self.cache_load_bytes(entry).await? {
+            Some(bytes) => self.reupload(key, &provider, bytes).await?,
+            None => None,
+        };
+        if replaced.is_none() {
+            // Nothing to upload from; the next lookup re-resolves from the track.
+            debug!("Dropping cover art entry {} with no re-upload", key);
+            self.cache_remove_entry(key).await?;
+            self.recent.lock().remove(key);
+        }
+        Ok(Some(old_url))
+    }
+
+    /// Upload cached bytes again with the provider that hosted them and
+    /// store the result under the same key. `None` when that provider is no
+    /// longer configured or cannot upload; other hosts are never tried, since
+    /// the user did not pick them for this cover. Errors leave the entry for
+    /// a retry.
+    async fn reupload(
+        &self,
+        key: &str,
+        provider_name: &str,
+        bytes: Vec<u8>,
+    ) -> Result<Option<String>, CoverArtError> {
+        let source = ArtSource::Bytes(bytes.clone());
+        let Some(provider) = reupload_provider(&self.providers, provider_name, &source) else {
+            debug!("Provider {} cannot re-upload cover art", provider_name);
+            return Ok(None);
+        };
+
+        let metadata_source = MetadataSource::new(None, None);
+        let cancel = CancellationToken::new();
+        match provider.process(source, &metadata_source, &cancel).await? {
+            Some(CoverResult {
+                url,
+                provider: provider_name,
+                expiration,
+            }) => {
+                info!("Re-uploaded cover art via {}: {}", provider_name, url);
+                self.cache_store_entry(key, &provider_name, &url, expiration, Some(bytes))
+                    .await?;
+                Ok(Some(url))
+            }
+            None => {
+                debug!("Provider {} declined re-upload", provider.name());
+                Ok(None)
+            }
+        }
     }
 
     fn is_local_or_private_url(url_str: &str) -> bool {
@@ -254,6 +630,7 @@ impl CoverManager {
             }
         }
 
+        self.touch(cache_key, &entry.url);
         Some(entry.url)
     }
 
@@ -262,6 +639,7 @@ impl CoverManager {
         source: Option<ArtSource>,
         metadata_source: &MetadataSource,
         read_cache: bool,
+        player_cover: Option<&PlayerCoverConfig>,
         cancel: &CancellationToken,
     ) -> Result<Option<String>, CoverArtError> {
         if cancel.is_cancelled() {
@@ -269,42 +647,27 @@ impl CoverManager {
             return Ok(None);
         }
         let cache_key = metadata_source.cache_key();
+        let plan = self.plan(player_cover);
         let recovered_cache_bytes: Option<Vec<u8>>;
 
         // 1. Check Cache
         if read_cache {
-            if let Some(mut entry) = self.cache_get_entry(cache_key).await? {
+            if let Some(entry) = self.cache_get_entry(cache_key).await? {
                 let url = entry.url.clone();
-                let mut drop_reason: Option<&'static str> = None;
-
-                if entry.provider.eq_ignore_ascii_case("direct") {
-                    let policy = Self::direct_url_policy(&url);
-                    if !policy.allow_direct {
-                        drop_reason = Some(policy.reason);
-                    }
-                }
-
-                let needs_validation = entry
-                    .last_validated
-                    .elapsed()
-                    .map(|elapsed| elapsed >= CACHE_VALIDATION_INTERVAL)
-                    .unwrap_or(true);
-
-                if drop_reason.is_none()
-                    && (!needs_validation || Self::validate_cover_url(&url).await)
-                {
-                    if needs_validation {
-                        entry.last_validated = SystemTime::now();
-                        self.cache_update_entry(&cache_key, &entry).await?;
-                    }
-                    debug!(
-                        "Serving cached cover art (provider: {}, validated: {})",
-                        entry.provider, !needs_validation
-                    );
+                let drop_reason = entry
+                    .provider
+                    .eq_ignore_ascii_case("direct")
+                    .then(|| Self::direct_url_policy(&url))
+                    .filter(|policy| !policy.allow_direct)
+                    .map(|policy| policy.reason);
+
+                // Revalidation happens in `maintain`, never on a track change.
+                let Some(reason) = drop_reason else {
+                    self.touch(cache_key, &url);
+                    debug!("Serving cached cover art (provider: {})", entry.provider);
                     return Ok(Some(url));
-                }
+                };
 
-                let reason = drop_reason.unwrap_or("validation_failed");
                 warn!(
                     "Cached cover art URL {} is no longer eligible (provider: {}, reason: {}); removing entry",
                     url, entry.provider, reason
@@ -412,7 +775,13 @@ impl CoverManager {
 
                 if let Some(art_source) = local_art {
                     if let Some(url) = self
-                        .try_providers(Some(&art_source), metadata_source, &cache_key, cancel)
+                        .try_providers(
+                            &plan,
+                            Some(&art_source),
+                            metadata_source,
+                            &cache_key,
+                            cancel,
+                        )
                         .await?
                     {
                         return Ok(Some(url));
@@ -423,6 +792,7 @@ impl CoverManager {
 
         // 4. Try configured providers with the prepared source (or metadata-only)
         self.try_providers(
+            &plan,
             source_for_providers.as_ref(),
             metadata_source,
             &cache_key,
@@ -433,71 +803,118 @@ impl CoverManager {
 
     async fn try_providers(
         &self,
+        plan: &ProviderPlan<'_>,
         source: Option<&ArtSource>,
         metadata_source: &MetadataSource,
         cache_key: &str,
         cancel: &CancellationToken,
     ) -> Result<Option<String>, CoverArtError> {
+        // Only uploads need the image, so it is not decoded for a plan of
+        // metadata-only providers. A source that fails normalization leaves
+        // only metadata-only providers as well.
+        let normalized = match source {
+            Some(src) if plan.uploads() => self.normalizer.apply(src).await?,
+            _ => None,
+        };
+        let source = normalized.as_ref();
         let dummy = ArtSource::Url(String::new());
         let process_source = source.unwrap_or(&dummy);
 
-        for provider in &self.providers {
-            let supported = match source {
-                Some(s) => provider.supports_source_type(s),
-                None => provider.supports_metadata_only(),
+        for step in plan.steps(source) {
+            let found = match step {
+                Step::One(provider) => {
+                    Self::attempt(provider, process_source.clone(), metadata_source, cancel).await
+                }
+                Step::Race(group) => {
+                    Self::race(&group, process_source, metadata_source, cancel).await
+                }
             };
-            if !supported {
-                trace!(
-                    "Provider {} does not support this source type",
-                    provider.name()
-                );
+            let Some(CoverResult {
+                url,
+                provider: provider_name,
+                expiration,
+            }) = found
+            else {
                 continue;
-            }
+            };
 
-            debug!("Attempting cover art retrieval with {}", provider.name());
-            match provider
-                .process(process_source.clone(), metadata_source, cancel)
-                .await
-            {
-                Ok(Some(result)) => {
-                    let CoverResult {
-                        url,
-                        provider: provider_name,
-                        expiration,
-                    } = result;
-                    if !Self::validate_cover_url(&url).await {
-                        warn!(
-                            "Provider {} returned invalid cover art URL, skipping",
-                            provider_name
-                        );
-                        continue;
-                    }
-                    info!("Successfully retrieved cover art from {}", provider_name);
-                    let cache_payload = if let Some(src) = source {
-                        Self::prepare_cache_payload(src, &url).await?
-                    } else {
-                        Self::prepare_cache_payload(&ArtSource::Url(url.clone()), &url).await?
-                    };
-                    self.cache_store_entry(
-                        &cache_key,
-                        &provider_name,
-                        &url,
-                        expiration,
-                        cache_payload,
-                    )
-                    .await?;
-                    return Ok(Some(url));
-                }
-                Ok(None) => debug!("Provider {} found no cover art", provider.name()),
-                Err(e) => warn!("Provider {} failed: {}", provider.name(), e),
-            }
+            info!("Successfully retrieved cover art from {}", provider_name);
+            let cache_payload = if let Some(src) = source {
+                Self::prepare_cache_payload(src, &url).await?
+            } else {
+                Self::prepare_cache_payload(&ArtSource::Url(url.clone()), &url).await?
+            };
+            self.cache_store_entry(cache_key, &provider_name, &url, expiration, cache_payload)
+                .await?;
+            return Ok(Some(url));
         }
 
         debug!("No cover art found from any source");
         Ok(None)
     }
 
-    async fn validate_cover_url(url: &str) -> bool {
+    /// Run one provider and validate what it returns.
+    async fn attempt(
+        provider: &dyn CoverArtProvider,
+        source: ArtSource,
+        metadata_source: &MetadataSource,
+        cancel: &CancellationToken,
+    ) -> Option<CoverResult> {
+        debug!("Attempting cover art retrieval with {}", provider.name());
+        match provider.process(source, metadata_source, cancel).await {
+            Ok(Some(result)) => {
+                if Self::validate_cover_url(&result.url).await {
+                    return Some(result);
+                }
+                warn!(
+                    "Provider {} returned invalid cover art URL, skipping",
+                    result.provider
+                );
+            }
+            Ok(None) => debug!("Provider {} found no cover art", provider.name()),
+            Err(e) => warn!("Provider {} failed: {}", provider.name(), e),
+        }
+        None
+    }
+
+    /// Run `group` concurrently under a child of `cancel` and return the
+    /// first valid result. The child is cancelled once there is a winner, so
+    /// the remaining providers stop, and a newer track cancelling `cancel`
+    /// stops all of them.
+    async fn race(
+        group: &[&dyn CoverArtProvider],
+        source: &ArtSource,
+        metadata_source: &MetadataSource,
+        cancel: &CancellationToken,
+    ) -> Option<CoverResult> {
+        if let [provider] = group {
+            return Self::attempt(*provider, source.clone(), metadata_source, cancel).await;
+        }
+        debug!(
+            "Racing cover art providers: {}",
+            group
+                .iter()
+                .map(|p| p.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let race_cancel = cancel.child_token();
+        let mut racers: FuturesUnordered<_> = group
+            .iter()
+            .map(|provider| Self::attempt(*provider, source.clone(), metadata_source, &race_cancel))
+            .collect();
+        while let Some(found) = racers.next().await {
+            if let Some(result) = found {
+                trace!("{} won the provider race", result.provider);
+                race_cancel.cancel();
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    /// HEAD the URL, falling back to a GET probe. Non-HTTP URLs pass.
+    pub async fn validate_cover_url(url: &str) -> bool {
         if !url.starts_with("http://") && !url.starts_with("https://") {
             trace!("Skipping validation for non-HTTP cover art URL: {}", url);
             return true;
@@ -548,6 +965,19 @@ impl CoverManager {
         }
     }
 
+    /// Drop expired entries and orphaned data through this manager's cache,
+    /// so its usage counters stay accurate for eviction.
+    pub async fn clean_cache(&self) -> Result<(), CoverArtError> {
+        info!("Starting periodic cache cleanup");
+        let cache = self.cache.clone();
+        let cleaned = self.run_blocking("cleanup", move || cache.clean()).await?;
+        if cleaned > 0 {
+            info!("Cleaned {} expired cache entries", cleaned);
+        }
+
+        Ok(())
+    }
+
     async fn run_blocking<F, T>(&self, context: &'static str, f: F) -> Result<T, CoverArtError>
     where
         F: FnOnce() -> Result<T, CoverArtError> + Send + 'static,
@@ -581,6 +1011,7 @@ impl CoverManager {
         expiration: Option<Duration>,
         cached_bytes: Option<Vec<u8>>,
     ) -> Result<(), CoverArtError> {
+        self.touch(key, url);
         let cache = self.cache.clone();
         let (key, provider, url) = (key.to_string(), provider.to_string(), url.to_string());
         self.run_blocking("store", move || {
@@ -654,23 +1085,232 @@ impl CoverManager {
     }
 }
 
-pub async fn clean_cache() -> Result<(), CoverArtError> {
-    info!("Starting periodic cache cleanup");
-    let cache = CoverCache::new(CACHE_TTL)?;
-    let cleaned_result = spawn_blocking(move || cache.clean())
-        .await
-        .map_err(|e| CoverArtError::other(format!("Cache cleanup task failed: {}", e)))?;
-    let cleaned = cleaned_result?;
-    if cleaned > 0 {
-        info!("Cleaned {} expired cache entries", cleaned);
-    }
+/// Open the on-disk cover cache with the daemon's TTL.
+pub fn open_cache(limits: &config::schema::CoverCacheConfig) -> Result<CoverCache, CoverArtError> {
+    CoverCache::new(CACHE_TTL, limits)
+}
 
-    Ok(())
+/// The configured provider that produced `name`'s results, if it can
+/// upload `source`. Matched on the provider's own name, which is what cache
+/// entries record (`litterbox` for a `catbox` entry with `use_litter`).
+fn reupload_provider<'a>(
+    providers: &'a [(String, Box<dyn CoverArtProvider>)],
+    name: &str,
+    source: &ArtSource,
+) -> Option<&'a dyn CoverArtProvider> {
+    providers
+        .iter()
+        .map(|(_, provider)| provider.as_ref())
+        .find(|provider| provider.name() == name)
+        .filter(|provider| {
+            !provider.supports_metadata_only() && provider.supports_source_type(source)
+        })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::CoverManager;
+    use super::{
+        reupload_provider, CacheEntry, CoverArtError, CoverArtProvider, CoverCache, CoverManager,
+        CoverResult, CoverStrategy, Maintenance, ProviderPlan, RecentUse, Step, CACHE_TTL,
+        CACHE_VALIDATION_INTERVAL, RECENT_USE_WINDOW,
+    };
+    use crate::config;
+    use crate::cover::sources::ArtSource;
+    use crate::metadata::MetadataSource;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+    use std::time::{Duration, Instant, SystemTime};
+    use tokio_util::sync::CancellationToken;
+
+    /// Provider that answers after `delay`, remembering the token it ran under.
+    struct MockProvider {
+        name: &'static str,
+        metadata_only: bool,
+        delay: Duration,
+        url: Option<&'static str>,
+        token: Mutex<Option<CancellationToken>>,
+    }
+
+    impl MockProvider {
+        fn new(name: &'static str, metadata_only: bool, delay_ms: u64) -> Self {
+            Self {
+                name,
+                metadata_only,
+                delay: Duration::from_millis(delay_ms),
+                url: Some("mock://cover"),
+                token: Mutex::new(None),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CoverArtProvider for MockProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn supports_source_type(&self, _source: &ArtSource) -> bool {
+            true
+        }
+
+        fn supports_metadata_only(&self) -> bool {
+            self.metadata_only
+        }
+
+        async fn process(
+            &self,
+            _source: ArtSource,
+            _metadata_source: &MetadataSource,
+            cancel: &CancellationToken,
+        ) -> Result<Option<CoverResult>, CoverArtError> {
+            *self.token.lock() = Some(cancel.clone());
+            tokio::select! {
+                _ = tokio::time::sleep(self.delay) => {}
+                _ = cancel.cancelled() => return Ok(None),
+            }
+            Ok(self.url.map(|url| CoverResult {
+                url: format!("{url}/{}", self.name),
+                provider: self.name.to_string(),
+                expiration: None,
+            }))
+        }
+    }
+
+    fn step_names(steps: &[Step<'_>]) -> Vec<String> {
+        steps
+            .iter()
+            .map(|step| match step {
+                Step::One(p) => p.name().to_string(),
+                Step::Race(group) => {
+                    let names: Vec<_> = group.iter().map(|p| p.name()).collect();
+                    format!("race({})", names.join(","))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn race_strategy_groups_metadata_only_providers() {
+        let catbox = MockProvider::new("catbox", false, 0);
+        let musicbrainz = MockProvider::new("musicbrainz", true, 0);
+        let s3 = MockProvider::new("s3", false, 0);
+        let itunes = MockProvider::new("itunes", true, 0);
+        let mut plan = ProviderPlan {
+            providers: vec![&catbox, &musicbrainz, &s3, &itunes],
+            strategy: CoverStrategy::Sequential,
+        };
+        let bytes = ArtSource::Bytes(vec![1]);
+
+        assert_eq!(
+            step_names(&plan.steps(Some(&bytes))),
+            ["catbox", "musicbrainz", "s3", "itunes"]
+        );
+        plan.strategy = CoverStrategy::Race;
+        assert_eq!(
+            step_names(&plan.steps(Some(&bytes))),
+            ["catbox", "race(musicbrainz,itunes)", "s3"]
+        );
+        assert_eq!(step_names(&plan.steps(None)), ["race(musicbrainz,itunes)"]);
+    }
+
+    #[tokio::test]
+    async fn race_takes_first_hit_and_cancels_losers() {
+        let slow = MockProvider::new("slow", true, 5_000);
+        let empty = MockProvider {
+            url: None,
+            ..MockProvider::new("empty", true, 0)
+        };
+        let fast = MockProvider::new("fast", true, 20);
+        let cancel = CancellationToken::new();
+        let metadata = MetadataSource::new(None, None);
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(2),
+            CoverManager::race(
+                &[&slow, &empty, &fast],
+                &ArtSource::Url(String::new()),
+                &metadata,
+                &cancel,
+            ),
+        )
+        .await
+        .expect("race should not wait for the slow provider")
+        .expect("fast provider wins");
+        assert_eq!(result.provider, "fast");
+        assert!(slow
+            .token
+            .lock()
+            .as_ref()
+            .expect("slow provider started")
+            .is_cancelled());
+        assert!(!cancel.is_cancelled());
+
+        // Cancelling the caller's token stops every racer.
+        let slow = MockProvider::new("slow", true, 5_000);
+        let slower = MockProvider::new("slower", true, 10_000);
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            trigger.cancel();
+        });
+        let result = tokio::time::timeout(
+            Duration::from_secs(2),
+            CoverManager::race(
+                &[&slow, &slower],
+                &ArtSource::Url(String::new()),
+                &metadata,
+                &cancel,
+            ),
+        )
+        .await
+        .expect("cancellation ends the race");
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn only_plans_with_an_uploader_need_the_image() {
+        let musicbrainz = MockProvider::new("musicbrainz", true, 0);
+        let itunes = MockProvider::new("itunes", true, 0);
+        let catbox = MockProvider::new("catbox", false, 0);
+
+        let mut plan = ProviderPlan {
+            providers: vec![&musicbrainz, &itunes],
+            strategy: CoverStrategy::Sequential,
+        };
+        assert!(!plan.uploads());
+        plan.providers.push(&catbox);
+        assert!(plan.uploads());
+    }
+
+    #[test]
+    fn reupload_only_uses_the_provider_that_hosted_the_cover() {
+        let providers: Vec<(String, Box<dyn CoverArtProvider>)> = vec![
+            (
+                "musicbrainz".to_string(),
+                Box::new(MockProvider::new("musicbrainz", true, 0)),
+            ),
+            (
+                "catbox".to_string(),
+                Box::new(MockProvider::new("litterbox", false, 0)),
+            ),
+            (
+                "imgbb".to_string(),
+                Box::new(MockProvider::new("imgbb", false, 0)),
+            ),
+        ];
+        let source = ArtSource::Bytes(vec![1, 2, 3]);
+        let name = |provider: &str| {
+            reupload_provider(&providers, provider, &source).map(|provider| provider.name())
+        };
+
+        assert_eq!(name("litterbox"), Some("litterbox"));
+        assert_eq!(name("imgbb"), Some("imgbb"));
+        // Metadata-only and unconfigured providers drop the entry instead of
+        // falling through to another host.
+        assert_eq!(name("musicbrainz"), None);
+        assert_eq!(name("s3"), None);
+    }
 
     #[test]
     fn denies_local_private_hosts_for_direct_usage() {
@@ -716,4 +1356,96 @@ mod tests {
         assert!(policy.allow_direct);
         assert_eq!(policy.reason, "public_url");
     }
+
+    #[test]
+    fn maintenance_reuploads_before_expiry_and_revalidates_stale_entries() {
+        let now = SystemTime::now();
+        let entry = |url_expires_in: Option<u64>, validated_ago: Duration| CacheEntry {
+            url: "https://litter.catbox.moe/a.png".to_string(),
+            provider: "catbox".to_string(),
+            expires_at: now + Duration::from_secs(3600),
+            last_validated: now - validated_ago,
+            stored_at: now,
+            data_file: None,
+            url_expires_at: url_expires_in.map(|secs| now + Duration::from_secs(secs)),
+        };
+
+        assert_eq!(
+            Maintenance::for_entry(&entry(Some(300), Duration::ZERO), now),
+            Some(Maintenance::Reupload)
+        );
+        assert_eq!(
+            Maintenance::for_entry(&entry(Some(3600), Duration::ZERO), now),
+            None
+        );
+        assert_eq!(
+            Maintenance::for_entry(&entry(None, CACHE_VALIDATION_INTERVAL), now),
+            Some(Maintenance::Revalidate)
+        );
+        assert_eq!(
+            Maintenance::for_entry(&entry(None, Duration::from_secs(60)), now),
+            None
+        );
+    }
+
+    #[test]
+    fn reloaded_manager_keeps_maintaining_recent_covers() {
+        use std::sync::Arc;
+
+        let config = Arc::new(config::ConfigManager::new_with_config(
+            config::parse_config_str("").unwrap(),
+        ));
+        let manager = |name: &str| {
+            let dir = std::env::temp_dir().join(format!(
+                "mprisence-recent-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let cache = CoverCache::with_dir(dir, CACHE_TTL, &config.cover_config().cache).unwrap();
+            CoverManager::with_cache(&config, cache)
+        };
+        let previous = manager("previous");
+        previous.touch("on-screen", "https://covers.example/on-screen.jpg");
+        let reloaded = manager("reloaded");
+        reloaded.carry_recent_from(&previous);
+
+        let recent = reloaded.recent.lock();
+        assert!(recent.contains_key("on-screen"));
+        assert_eq!(recent["on-screen"], previous.recent.lock()["on-screen"]);
+        drop(recent);
+        let _ = std::fs::remove_dir_all(reloaded.cache.cache_dir());
+        let _ = std::fs::remove_dir_all(previous.cache.cache_dir());
+    }
+
+    #[test]
+    fn covers_on_screen_outlive_the_recent_use_window() {
+        use std::sync::Arc;
+
+        let config = Arc::new(config::ConfigManager::new_with_config(
+            config::parse_config_str("").unwrap(),
+        ));
+        let dir = std::env::temp_dir().join(format!("mprisence-shown-{}", std::process::id()));
+        let cache = CoverCache::with_dir(dir, CACHE_TTL, &config.cover_config().cache).unwrap();
+        let manager = CoverManager::with_cache(&config, cache);
+
+        let long_ago = Instant::now()
+            .checked_sub(RECENT_USE_WINDOW + Duration::from_secs(60))
+            .unwrap();
+        for (key, url) in [
+            ("on-screen", "https://covers.example/on-screen.jpg"),
+            ("gone", "https://covers.example/gone.jpg"),
+        ] {
+            manager.recent.lock().insert(
+                key.to_string(),
+                RecentUse {
+                    used: long_ago,
+                    url: url.to_string(),
+                },
+            );
+        }
+
+        manager.touch_shown("https://covers.example/on-screen.jpg");
+        assert_eq!(manager.recent_keys(), ["on-screen"]);
+        let _ = std::fs::remove_dir_all(manager.cache.cache_dir());
+    }
 }
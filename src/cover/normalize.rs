@@ -0,0 +1,267 @@
+//! Cover preprocessing before upload: downscale, re-encode and thereby strip
+//! metadata. Discord shows covers small, so big embedded PNGs only slow
+//! uploads down and run into host size limits.
+
+use image::{
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    ImageReader,
+};
+use log::{debug, trace, warn};
+use parking_lot::Mutex;
+use std::{collections::VecDeque, io::Cursor, sync::Arc};
+use tokio::task::spawn_blocking;
+
+use crate::config::schema::{CoverImageFormat, CoverNormalizeConfig};
+use crate::cover::error::CoverArtError;
+use crate::cover::sources::ArtSource;
+
+/// Normalized results kept in memory, keyed by input content hash.
+const MEMO_CAPACITY: usize = 32;
+
+pub struct Normalizer {
+    config: CoverNormalizeConfig,
+    memo: Mutex<VecDeque<(blake3::Hash, Arc<Vec<u8>>)>>,
+}
+
+impl Normalizer {
+    pub fn new(config: CoverNormalizeConfig) -> Self {
+        if config.enabled
+            && config.format == CoverImageFormat::Webp
+            && config.quality != CoverNormalizeConfig::default().quality
+        {
+            warn!(
+                "[cover.normalize] quality = {} is ignored: WebP covers are encoded \
+                 losslessly, use format = \"jpeg\" for smaller files",
+                config.quality
+            );
+        }
+        Self {
+            config,
+            memo: Mutex::new(VecDeque::with_capacity(MEMO_CAPACITY)),
+        }
+    }
+
+    /// Normalize byte-like sources into `ArtSource::Bytes`. URLs pass through
+    /// untouched. Returns `None` when the image is oversized or corrupt.
+    pub async fn apply(&self, source: &ArtSource) -> Result<Option<ArtSource>, CoverArtError> {
+        if !self.config.enabled || matches!(source, ArtSource::Url(_)) {
+            return Ok(Some(source.clone()));
+        }
+        if let ArtSource::File(path) = source {
+            let len = tokio::fs::metadata(path)
+                .await
+                .map(|m| m.len())
+                .unwrap_or(0);
+            if len > self.config.max_input_bytes {
+                warn!(
+                    "Dropping cover art {:?}: {} bytes exceeds max_input_bytes",
+                    path, len
+                );
+                return Ok(None);
+            }
+        }
+        let Some(bytes) = source.materialize_bytes().await? else {
+            return Ok(None);
+        };
+
+        let hash = blake3::hash(&bytes);
+        if let Some(hit) = self.lookup(&hash) {
+            trace!("Using memoized normalized cover art {}", hash.to_hex());
+            return Ok(Some(ArtSource::Bytes(hit.to_vec())));
+        }
+
+        let config = self.config.clone();
+        let result = spawn_blocking(move || normalize(&bytes, &config))
+            .await
+            .map_err(|e| CoverArtError::other(format!("Cover normalize task failed: {e}")))?;
+        match result {
+            Ok(out) => {
+                let out = Arc::new(out);
+                self.remember(hash, out.clone());
+                Ok(Some(ArtSource::Bytes(out.to_vec())))
+            }
+            Err(e) => {
+                warn!("Dropping cover art that failed normalization: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    fn lookup(&self, hash: &blake3::Hash) -> Option<Arc<Vec<u8>>> {
+        self.memo
+            .lock()
+            .iter()
+            .find(|(key, _)| key == hash)
+            .map(|(_, bytes)| bytes.clone())
+    }
+
+    fn remember(&self, hash: blake3::Hash, bytes: Arc<Vec<u8>>) {
+        let mut memo = self.memo.lock();
+        if memo.len() >= MEMO_CAPACITY {
+            memo.pop_front();
+        }
+        memo.push_back((hash, bytes));
+    }
+}
+
+/// Decode, downscale to `max_dimension` and re-encode. CPU-bound.
+pub fn normalize(bytes: &[u8], config: &CoverNormalizeConfig) -> Result<Vec<u8>, CoverArtError> {
+    if bytes.len() as u64 > config.max_input_bytes {
+        return Err(CoverArtError::other(format!(
+            "{} bytes exceeds max_input_bytes ({})",
+            bytes.len(),
+            config.max_input_bytes
+        )));
+    }
+
+    let img = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| CoverArtError::other(format!("image format detection failed: {e}")))?
+        .decode()
+        .map_err(|e| CoverArtError::other(format!("image decode failed: {e}")))?;
+    let (width, height) = (img.width(), img.height());
+    if width == 0 || height == 0 {
+        return Err(CoverArtError::other("image has no pixels"));
+    }
+
+    let max = config.max_dimension.max(1);
+    let img = if width > max || height > max {
+        img.resize(max, max, FilterType::Lanczos3)
+    } else {
+        img
+    };
+
+    let mut out = Vec::with_capacity(64 * 1024);
+    match config.format {
+        CoverImageFormat::Jpeg => {
+            let encoder = JpegEncoder::new_with_quality(&mut out, config.quality.clamp(1, 100));
+            img.into_rgb8().write_with_encoder(encoder)
+        }
+        // The image crate only encodes lossless WebP, so `quality` does not
+        // apply; photos usually come out larger than as JPEG.
+        CoverImageFormat::Webp => img
+            .into_rgba8()
+            .write_with_encoder(WebPEncoder::new_lossless(&mut out)),
+    }
+    .map_err(|e| CoverArtError::other(format!("image re-encode failed: {e}")))?;
+
+    debug!(
+        "Normalized cover art {}x{} ({} B) -> {:?} ({} B)",
+        width,
+        height,
+        bytes.len(),
+        config.format,
+        out.len()
+    );
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        RgbImage::from_pixel(width, height, image::Rgb([200, 40, 90]))
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    fn config(format: CoverImageFormat) -> CoverNormalizeConfig {
+        CoverNormalizeConfig {
+            max_dimension: 64,
+            format,
+            ..Default::default()
+        }
+    }
+
+    /// Photo-like noise, which does not compress away like a flat colour.
+    fn noisy_png(width: u32, height: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        RgbImage::from_fn(width, height, |x, y| {
+            let v = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)) as u8;
+            image::Rgb([v, v.wrapping_mul(7), v.wrapping_add(y as u8)])
+        })
+        .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+        .unwrap();
+        out
+    }
+
+    #[test]
+    fn downscales_and_reencodes() {
+        let input = noisy_png(512, 256);
+        let out = normalize(&input, &config(CoverImageFormat::Jpeg)).unwrap();
+        assert_eq!(image::guess_format(&out).unwrap(), ImageFormat::Jpeg);
+        let img = image::load_from_memory(&out).unwrap();
+        assert_eq!((img.width(), img.height()), (64, 32));
+        assert!(
+            out.len() * 10 < input.len(),
+            "{} B from {} B",
+            out.len(),
+            input.len()
+        );
+
+        let low_quality = normalize(
+            &input,
+            &CoverNormalizeConfig {
+                quality: 20,
+                ..config(CoverImageFormat::Jpeg)
+            },
+        )
+        .unwrap();
+        assert!(low_quality.len() < out.len());
+
+        let input = png(32, 32);
+        let out = normalize(&input, &config(CoverImageFormat::Webp)).unwrap();
+        assert_eq!(image::guess_format(&out).unwrap(), ImageFormat::WebP);
+        let img = image::load_from_memory(&out).unwrap();
+        assert_eq!((img.width(), img.height()), (32, 32));
+        // Lossless: every pixel survives, and a flat image stays tiny.
+        assert_eq!(
+            img.into_rgb8(),
+            image::load_from_memory(&input).unwrap().into_rgb8()
+        );
+        assert!(out.len() < 256, "{} B", out.len());
+    }
+
+    #[test]
+    fn rejects_corrupt_and_oversized_input() {
+        let cfg = config(CoverImageFormat::Jpeg);
+        assert!(normalize(b"definitely not an image", &cfg).is_err());
+
+        let mut truncated = png(16, 16);
+        truncated.truncate(truncated.len() / 2);
+        assert!(normalize(&truncated, &cfg).is_err());
+
+        let cfg = CoverNormalizeConfig {
+            max_input_bytes: 10,
+            ..cfg
+        };
+        assert!(normalize(&png(16, 16), &cfg).is_err());
+    }
+
+    #[tokio::test]
+    async fn memoizes_by_content_hash_and_passes_urls_through() {
+        let normalizer = Normalizer::new(config(CoverImageFormat::Jpeg));
+        let source = ArtSource::Bytes(png(128, 128));
+        let first = normalizer.apply(&source).await.unwrap();
+        assert!(matches!(first, Some(ArtSource::Bytes(_))));
+        assert_eq!(normalizer.memo.lock().len(), 1);
+        normalizer.apply(&source).await.unwrap();
+        assert_eq!(normalizer.memo.lock().len(), 1);
+
+        let url = ArtSource::Url("https://example.com/a.png".to_string());
+        assert!(matches!(
+            normalizer.apply(&url).await.unwrap(),
+            Some(ArtSource::Url(_))
+        ));
+        assert!(normalizer
+            .apply(&ArtSource::Bytes(b"junk".to_vec()))
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,13 +1,10 @@
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use catbox::{file, litter};
-use image::{imageops::FilterType, ImageFormat};
 use log::{debug, info, trace, warn};
-use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::fs;
-use tokio::task::spawn_blocking;
 
 use crate::config::schema::CatboxConfig;
 use crate::cover::error::CoverArtError;
@@ -117,20 +114,17 @@ impl CatboxProvider {
         Ok(())
     }
 
-    async fn upload_from_bytes(
-        &self,
-        data: &[u8],
-        cancel: &CancellationToken,
-    ) -> Result<String, CoverArtError> {
-        let prepared = self.prepare_upload_bytes(data.to_vec(), cancel).await?;
+    /// Upload bytes as-is: resizing and re-encoding already happened in
+    /// `[cover.normalize]`.
+    async fn upload_from_bytes(&self, data: &[u8]) -> Result<String, CoverArtError> {
         let temp_path = Self::temp_file_path();
         trace!(
             "Writing {} bytes to temporary file for Catbox upload: {:?}",
-            prepared.len(),
+            data.len(),
             temp_path
         );
 
-        fs::write(&temp_path, &prepared).await.map_err(|e| {
+        fs::write(&temp_path, data).await.map_err(|e| {
             CoverArtError::provider_error(self.provider_label(), &format!("write failed: {e}"))
         })?;
 
@@ -144,70 +138,6 @@ impl CatboxProvider {
         result
     }
 
-    /// Decode + resize if larger than `MAX_DIM` on either axis or oversized
-    /// in bytes. Skips work for small images. Re-encodes as JPEG q=85 because
-    /// album covers don't need alpha and JPEG shrinks far better than PNG.
-    /// CPU-bound; runs on a blocking task so the async runtime stays free.
-    async fn prepare_upload_bytes(
-        &self,
-        bytes: Vec<u8>,
-        cancel: &CancellationToken,
-    ) -> Result<Vec<u8>, CoverArtError> {
-        const MAX_DIM: u32 = 512;
-        const MAX_BYTES: usize = 256 * 1024;
-
-        if bytes.len() <= MAX_BYTES {
-            // Cheap path: trust small files — quick check would have to decode
-            // anyway, and album cover thumbnails are commonly already small.
-            return Ok(bytes);
-        }
-
-        let provider = self.provider_label();
-        let original_len = bytes.len();
-
-        if cancel.is_cancelled() {
-            debug!("{} image resize cancelled", provider);
-            return Err(CoverArtError::other("cancelled"));
-        }
-
-        spawn_blocking(move || -> Result<Vec<u8>, CoverArtError> {
-            let img = image::load_from_memory(&bytes).map_err(|e| {
-                CoverArtError::provider_error(provider, &format!("image decode failed: {e}"))
-            })?;
-            let (w, h) = (img.width(), img.height());
-
-            if w <= MAX_DIM && h <= MAX_DIM {
-                return Ok(bytes);
-            }
-
-            let resized = img.resize(MAX_DIM, MAX_DIM, FilterType::Lanczos3);
-            let (nw, nh) = (resized.width(), resized.height());
-            let mut out: Vec<u8> = Vec::with_capacity(64 * 1024);
-            let mut cursor = Cursor::new(&mut out);
-            resized
-                .into_rgb8()
-                .write_to(&mut cursor, ImageFormat::Jpeg)
-                .map_err(|e| {
-                    CoverArtError::provider_error(provider, &format!("image re-encode failed: {e}"))
-                })?;
-            debug!(
-                "{} resized cover art {}x{} ({} B) -> {}x{} ({} B JPEG)",
-                provider,
-                w,
-                h,
-                original_len,
-                nw,
-                nh,
-                out.len()
-            );
-            Ok(out)
-        })
-        .await
-        .map_err(|e| {
-            CoverArtError::provider_error(self.provider_label(), &format!("resize task: {e}"))
-        })?
-    }
-
     fn temp_file_path() -> PathBuf {
         let mut path = std::env::temp_dir();
         let timestamp = SystemTime::now()
@@ -262,12 +192,12 @@ impl CoverArtProvider for CatboxProvider {
                         &format!("read {:?}: {e}", path),
                     )
                 })?;
-                Some(self.upload_from_bytes(&bytes, cancel).await?)
+                Some(self.upload_from_bytes(&bytes).await?)
             }
-            ArtSource::Bytes(data) => Some(self.upload_from_bytes(&data, cancel).await?),
+            ArtSource::Bytes(data) => Some(self.upload_from_bytes(&data).await?),
             ArtSource::Base64(data) => {
                 let bytes = self.base64_to_bytes(&data)?;
-                Some(self.upload_from_bytes(&bytes, cancel).await?)
+                Some(self.upload_from_bytes(&bytes).await?)
             }
             ArtSource::Url(_) => return Ok(None),
         };
@@ -0,0 +1,270 @@
+use async_trait::async_trait;
+use log::{debug, info};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{create_shared_client, fuzzy, CoverArtProvider, CoverResult};
+use crate::config::schema::DeezerConfig;
+use crate::cover::error::CoverArtError;
+use crate::cover::sources::ArtSource;
+use crate::metadata::MetadataSource;
+use tokio_util::sync::CancellationToken;
+
+const PROVIDER: &str = "deezer";
+const DEEZER_API: &str = "https://api.deezer.com";
+const SEARCH_LIMIT: &str = "10";
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    data: Vec<Track>,
+    /// Deezer reports errors (quota, bad query) with a 200 and this object.
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Track {
+    #[serde(default)]
+    title: String,
+    artist: Artist,
+    album: Album,
+}
+
+#[derive(Debug, Deserialize)]
+struct Artist {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Album {
+    #[serde(default)]
+    cover_xl: Option<String>,
+    #[serde(default)]
+    cover_big: Option<String>,
+}
+
+impl Album {
+    fn cover(&self) -> Option<&str> {
+        self.cover_xl
+            .as_deref()
+            .or(self.cover_big.as_deref())
+            .filter(|url| !url.is_empty())
+    }
+}
+
+/// Searches Deezer's public track search by artist and title.
+#[derive(Clone)]
+pub struct DeezerProvider {
+    client: Client,
+    config: DeezerConfig,
+    api: String,
+}
+
+impl DeezerProvider {
+    pub fn with_config(config: DeezerConfig) -> Self {
+        Self::with_api(config, DEEZER_API)
+    }
+
+    fn with_api(config: DeezerConfig, api: &str) -> Self {
+        info!(
+            "Initializing Deezer provider with min_score: {}",
+            config.min_score
+        );
+        Self {
+            client: create_shared_client(),
+            config,
+            api: api.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn search(&self, title: &str, artist: &str) -> Result<Vec<Track>, CoverArtError> {
+        let quote = |value: &str| value.replace('"', "");
+        let mut url = url::Url::parse(&format!("{}/search", self.api))
+            .map_err(|e| CoverArtError::provider_error(PROVIDER, &format!("{e}")))?;
+        url.query_pairs_mut()
+            .append_pair(
+                "q",
+                &format!("artist:\"{}\" track:\"{}\"", quote(artist), quote(title)),
+            )
+            .append_pair("limit", SEARCH_LIMIT);
+        debug!("Searching Deezer: {}", url);
+
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(CoverArtError::provider_error(
+                PROVIDER,
+                &format!("search failed with {status}"),
+            ));
+        }
+        let data: SearchResponse = response
+            .json()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        if let Some(error) = data.error {
+            return Err(CoverArtError::provider_error(
+                PROVIDER,
+                &format!("search failed: {error}"),
+            ));
+        }
+        debug!("Found {} Deezer tracks", data.data.len());
+        Ok(data.data)
+    }
+
+    fn best_match<'a>(
+        &self,
+        title: &str,
+        artists: &[String],
+        tracks: &'a [Track],
+    ) -> Option<&'a str> {
+        let with_cover = tracks
+            .iter()
+            .filter_map(|track| Some((track, track.album.cover()?)));
+        fuzzy::best_scored(
+            title,
+            artists,
+            with_cover,
+            self.config.min_score,
+            |(track, _)| (&track.artist.name, &track.title),
+        )
+        .map(|(_, cover)| cover)
+    }
+}
+
+#[async_trait]
+impl CoverArtProvider for DeezerProvider {
+    fn name(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn supports_source_type(&self, _source: &ArtSource) -> bool {
+        true // Deezer works from metadata only
+    }
+
+    fn supports_metadata_only(&self) -> bool {
+        true
+    }
+
+    async fn process(
+        &self,
+        _source: ArtSource,
+        metadata_source: &MetadataSource,
+        cancel: &CancellationToken,
+    ) -> Result<Option<CoverResult>, CoverArtError> {
+        if cancel.is_cancelled() {
+            debug!("Deezer provider cancelled before start");
+            return Ok(None);
+        }
+        info!("Processing metadata with Deezer provider");
+
+        let Some((title, artists)) = super::title_and_artists(metadata_source) else {
+            debug!("No title and artist available for Deezer search");
+            return Ok(None);
+        };
+
+        let tracks = tokio::select! {
+            result = self.search(&title, &artists[0]) => result?,
+            _ = cancel.cancelled() => {
+                debug!("Deezer search cancelled");
+                return Ok(None);
+            }
+        };
+
+        match self.best_match(&title, &artists, &tracks) {
+            Some(url) => {
+                info!("Successfully found cover art via Deezer: {}", url);
+                Ok(Some(CoverResult {
+                    url: url.to_string(),
+                    provider: self.name().to_string(),
+                    expiration: None,
+                }))
+            }
+            None => {
+                debug!(
+                    "No Deezer track met the minimum score of {}",
+                    self.config.min_score
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cover::providers::tests::metadata;
+    use crate::test_util::spawn_server;
+
+    const FIXTURE: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/deezer_search.json"
+    ));
+
+    fn provider(api: &str) -> DeezerProvider {
+        DeezerProvider::with_api(DeezerConfig::default(), api)
+    }
+
+    #[tokio::test]
+    async fn picks_matching_track_at_full_resolution() {
+        let (api, rx) = spawn_server(vec![(200, FIXTURE)]);
+        let source = metadata(&[("xesam:title", "Get Lucky"), ("xesam:artist", "Daft Punk")]);
+
+        let result = provider(&api)
+            .process(
+                ArtSource::Url(String::new()),
+                &source,
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap()
+            .expect("cover");
+        assert_eq!(
+            result.url,
+            "https://e-cdns-images.dzcdn.net/images/cover/311bba0fc112d15f72c8b5a65f0456c1/1000x1000-000000-80-0-0.jpg"
+        );
+        assert_eq!(result.provider, "deezer");
+
+        let url = rx.recv().unwrap().url;
+        assert!(url.contains("q=artist%3A%22Daft+Punk%22+track%3A%22Get+Lucky%22"));
+    }
+
+    #[tokio::test]
+    async fn rejects_tribute_versions_and_reports_api_errors() {
+        let (api, _rx) = spawn_server(vec![(200, FIXTURE)]);
+        let source = metadata(&[
+            ("xesam:title", "Get Lucky"),
+            ("xesam:artist", "The Piano Guys"),
+        ]);
+        let result = provider(&api)
+            .process(
+                ArtSource::Url(String::new()),
+                &source,
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+        assert!(result.is_none());
+
+        let (api, _rx) = spawn_server(vec![(
+            200,
+            r#"{"error":{"type":"Exception","message":"Quota limit exceeded","code":4}}"#,
+        )]);
+        let source = metadata(&[("xesam:title", "Get Lucky"), ("xesam:artist", "Daft Punk")]);
+        assert!(provider(&api)
+            .process(
+                ArtSource::Url(String::new()),
+                &source,
+                &CancellationToken::new(),
+            )
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,450 @@
+use async_trait::async_trait;
+use log::{debug, info, trace};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+use super::{create_shared_client, CoverArtProvider, CoverResult};
+use crate::config::schema::DiscogsConfig;
+use crate::cover::error::CoverArtError;
+use crate::cover::sources::ArtSource;
+use crate::metadata::MetadataSource;
+use tokio_util::sync::CancellationToken;
+
+const PROVIDER: &str = "discogs";
+const DISCOGS_API: &str = "https://api.discogs.com";
+const SEARCH_PAGE_SIZE: &str = "10";
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    results: Vec<SearchResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResult {
+    id: u64,
+    /// "Artist - Album"
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    cover_image: Option<String>,
+    #[serde(default)]
+    barcode: Vec<String>,
+    #[serde(default)]
+    catno: Option<String>,
+}
+
+/// What we know about the release being looked up.
+#[derive(Debug, Default)]
+struct ReleaseQuery {
+    album: Option<String>,
+    artists: Vec<String>,
+    barcode: Option<String>,
+    catalog_number: Option<String>,
+}
+
+impl ReleaseQuery {
+    fn from_metadata(metadata_source: &MetadataSource) -> Self {
+        let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+        let mut artists: Vec<String> = metadata_source
+            .album_artists()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|a| !a.trim().is_empty())
+            .collect();
+        if artists.is_empty() {
+            artists = metadata_source
+                .artists()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|a| !a.trim().is_empty())
+                .collect();
+        }
+        Self {
+            album: non_empty(metadata_source.album()),
+            artists,
+            barcode: non_empty(metadata_source.barcode()),
+            catalog_number: non_empty(metadata_source.catalog_number()),
+        }
+    }
+
+    /// Search parameter sets, most specific first.
+    fn searches(&self) -> Vec<Vec<(&'static str, String)>> {
+        let artist = self.artists.first();
+        let mut searches = Vec::new();
+        if let Some(barcode) = &self.barcode {
+            searches.push(vec![("barcode", barcode.clone())]);
+        }
+        if let Some(catno) = &self.catalog_number {
+            let mut params = vec![("catno", catno.clone())];
+            if let Some(artist) = artist {
+                params.push(("artist", artist.clone()));
+            }
+            searches.push(params);
+        }
+        if let (Some(album), Some(artist)) = (&self.album, artist) {
+            searches.push(vec![
+                ("release_title", album.clone()),
+                ("artist", artist.clone()),
+            ]);
+        }
+        searches
+    }
+
+    /// Score a search result from 0 to 100. A barcode match is conclusive;
+    /// otherwise catalog number, album title and artist add up.
+    fn score(&self, result: &SearchResult) -> u8 {
+        if let Some(barcode) = &self.barcode {
+            let wanted = identifier(barcode);
+            if result.barcode.iter().any(|b| identifier(b) == wanted) {
+                return 100;
+            }
+        }
+
+        let (result_artist, result_album) = match result.title.split_once(" - ") {
+            Some((artist, album)) => (artist, album),
+            None => ("", result.title.as_str()),
+        };
+
+        let mut score = 0u32;
+        if let (Some(catno), Some(result_catno)) = (&self.catalog_number, &result.catno) {
+            if identifier(catno) == identifier(result_catno) {
+                score += 40;
+            }
+        }
+        if let Some(album) = &self.album {
+            score += similarity(album, result_album, 50);
+        }
+        score += self
+            .artists
+            .iter()
+            .map(|artist| similarity(artist, result_artist, 40))
+            .max()
+            .unwrap_or(0);
+        score.min(100) as u8
+    }
+}
+
+/// Uppercase alphanumerics only, so "EAN 0-12345" and "012345" compare equal.
+fn identifier(value: &str) -> String {
+    value
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Lowercase and drop Discogs artist disambiguators such as "Name (2)" or
+/// "Name*".
+fn normalize_name(value: &str) -> String {
+    let mut value = value.trim().trim_end_matches('*').trim().to_lowercase();
+    if value.ends_with(')') {
+        if let Some(open) = value.rfind(" (") {
+            if value[open + 2..value.len() - 1]
+                .chars()
+                .all(|c| c.is_ascii_digit())
+            {
+                value.truncate(open);
+            }
+        }
+    }
+    value
+}
+
+/// Full `weight` for an exact match, half when one contains the other.
+fn similarity(wanted: &str, found: &str, weight: u32) -> u32 {
+    let (wanted, found) = (normalize_name(wanted), normalize_name(found));
+    if wanted.is_empty() || found.is_empty() {
+        0
+    } else if wanted == found {
+        weight
+    } else if found.contains(&wanted) || wanted.contains(&found) {
+        weight / 2
+    } else {
+        0
+    }
+}
+
+/// Discogs serves a placeholder spacer image for releases without art.
+fn usable_cover(url: &Option<String>) -> Option<&str> {
+    url.as_deref()
+        .filter(|u| u.starts_with("http") && !u.ends_with("spacer.gif"))
+}
+
+#[derive(Clone)]
+pub struct DiscogsProvider {
+    client: Client,
+    config: DiscogsConfig,
+    api: String,
+}
+
+impl DiscogsProvider {
+    pub fn with_config(config: DiscogsConfig) -> Self {
+        Self::with_api(config, DISCOGS_API)
+    }
+
+    fn with_api(config: DiscogsConfig, api: &str) -> Self {
+        info!(
+            "Initializing Discogs provider with min_score: {} ({})",
+            config.min_score,
+            if config.token.is_some() {
+                "authenticated"
+            } else {
+                "no token"
+            }
+        );
+        Self {
+            client: create_shared_client(),
+            config,
+            api: api.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn search(
+        &self,
+        params: &[(&'static str, String)],
+    ) -> Result<Vec<SearchResult>, CoverArtError> {
+        let mut url = url::Url::parse(&format!("{}/database/search", self.api))
+            .map_err(|e| CoverArtError::provider_error(PROVIDER, &format!("{e}")))?;
+        url.query_pairs_mut()
+            .append_pair("type", "release")
+            .append_pair("per_page", SEARCH_PAGE_SIZE)
+            .extend_pairs(params.iter().map(|(k, v)| (*k, v.as_str())));
+        debug!("Searching Discogs releases: {}", url);
+
+        let mut request = self.client.get(url);
+        if let Some(token) = &self.config.token {
+            request = request.header("Authorization", format!("Discogs token={token}"));
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(CoverArtError::provider_error(
+                PROVIDER,
+                "search was rejected (401); set cover.provider.discogs.token",
+            ));
+        }
+        if !status.is_success() {
+            return Err(CoverArtError::provider_error(
+                PROVIDER,
+                &format!("search failed with {status}"),
+            ));
+        }
+
+        let data: SearchResponse = response
+            .json()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        debug!("Found {} Discogs releases", data.results.len());
+        Ok(data.results)
+    }
+
+    /// Best-scoring result with usable art that meets `min_score`.
+    fn best_match<'a>(&self, query: &ReleaseQuery, results: &'a [SearchResult]) -> Option<&'a str> {
+        results
+            .iter()
+            .filter_map(|result| {
+                let score = query.score(result);
+                let cover = usable_cover(&result.cover_image);
+                trace!(
+                    "Discogs release {} '{}' scored {} (cover: {})",
+                    result.id,
+                    result.title,
+                    score,
+                    cover.is_some()
+                );
+                if score < self.config.min_score {
+                    return None;
+                }
+                cover.map(|url| (score, url))
+            })
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, url)| url)
+    }
+}
+
+#[async_trait]
+impl CoverArtProvider for DiscogsProvider {
+    fn name(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn supports_source_type(&self, _source: &ArtSource) -> bool {
+        true // Discogs works from metadata only
+    }
+
+    fn supports_metadata_only(&self) -> bool {
+        true
+    }
+
+    async fn process(
+        &self,
+        _source: ArtSource,
+        metadata_source: &MetadataSource,
+        cancel: &CancellationToken,
+    ) -> Result<Option<CoverResult>, CoverArtError> {
+        if cancel.is_cancelled() {
+            debug!("Discogs provider cancelled before start");
+            return Ok(None);
+        }
+        info!("Processing metadata with Discogs provider");
+
+        let query = ReleaseQuery::from_metadata(metadata_source);
+        trace!("Discogs query: {:?}", query);
+        let searches = query.searches();
+        if searches.is_empty() {
+            debug!("Not enough metadata for a Discogs search");
+            return Ok(None);
+        }
+
+        for params in searches {
+            let results = tokio::select! {
+                result = self.search(&params) => result?,
+                _ = cancel.cancelled() => {
+                    debug!("Discogs search cancelled");
+                    return Ok(None);
+                }
+            };
+            if let Some(url) = self.best_match(&query, &results) {
+                info!("Successfully found cover art via Discogs: {}", url);
+                return Ok(Some(CoverResult {
+                    url: url.to_string(),
+                    provider: self.name().to_string(),
+                    expiration: None,
+                }));
+            }
+            debug!(
+                "No Discogs release met the minimum score of {}",
+                self.config.min_score
+            );
+        }
+
+        debug!("Discogs provider found no suitable cover art");
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cover::providers::tests::metadata;
+    use crate::test_util::spawn_server;
+
+    const NO_RESULTS: &str = r#"{"results":[]}"#;
+
+    fn provider(api: &str, token: Option<&str>) -> DiscogsProvider {
+        DiscogsProvider::with_api(
+            DiscogsConfig {
+                token: token.map(str::to_string),
+                ..Default::default()
+            },
+            api,
+        )
+    }
+
+    #[tokio::test]
+    async fn barcode_match_wins_and_sends_token() {
+        let (api, rx) = spawn_server(vec![(
+            200,
+            r#"{"results":[
+                {"id":1,"title":"Someone Else - Other","cover_image":"https://i.discogs.com/wrong.jpg","barcode":["111"]},
+                {"id":2,"title":"Boards (2) - Bootleg Live","cover_image":"https://i.discogs.com/right.jpg","barcode":["0 12345-67890 5"]}
+            ]}"#,
+        )]);
+        let source = metadata(&[
+            ("xesam:album", "Bootleg Live"),
+            ("xesam:artist", "Boards"),
+            ("xesam:barcode", "012345678905"),
+        ]);
+
+        let result = provider(&api, Some("secret"))
+            .process(
+                ArtSource::Url(String::new()),
+                &source,
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap()
+            .expect("cover");
+        assert_eq!(result.url, "https://i.discogs.com/right.jpg");
+        assert_eq!(result.provider, "discogs");
+
+        let captured = rx.recv().unwrap();
+        assert!(captured.url.starts_with("/database/search?"));
+        assert!(captured.url.contains("type=release"));
+        assert!(captured.url.contains("barcode=012345678905"));
+        assert_eq!(
+            captured.header("Authorization"),
+            Some("Discogs token=secret")
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_text_search_and_enforces_min_score() {
+        // The catalog number search finds nothing; the text search does.
+        let (api, rx) = spawn_server(vec![
+            (200, NO_RESULTS),
+            (
+                200,
+                r#"{"results":[
+                    {"id":3,"title":"Boards - Bootleg Live (Remastered Deluxe)","cover_image":"https://i.discogs.com/partial.jpg"},
+                    {"id":4,"title":"Boards* - Bootleg Live","cover_image":"https://st.discogs.com/images/spacer.gif"},
+                    {"id":5,"title":"Boards - Bootleg Live","cover_image":"https://i.discogs.com/exact.jpg","catno":"BRD 001"}
+                ]}"#,
+            ),
+        ]);
+        let source = metadata(&[
+            ("xesam:album", "Bootleg Live"),
+            ("xesam:albumArtist", "Boards"),
+            ("xesam:catalogNumber", "BRD-002"),
+        ]);
+
+        let result = provider(&api, None)
+            .process(
+                ArtSource::Url(String::new()),
+                &source,
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap()
+            .expect("cover");
+        assert_eq!(result.url, "https://i.discogs.com/exact.jpg");
+
+        let catno = rx.recv().unwrap();
+        assert!(catno.url.contains("catno=BRD-002"));
+        assert_eq!(catno.header("Authorization"), None);
+        let text = rx.recv().unwrap();
+        assert!(text.url.contains("release_title=Bootleg+Live"));
+        assert!(text.url.contains("artist=Boards"));
+    }
+
+    #[test]
+    fn scores_titles_artists_and_catalog_numbers() {
+        let query = ReleaseQuery {
+            album: Some("Bootleg Live".to_string()),
+            artists: vec!["Boards".to_string()],
+            barcode: None,
+            catalog_number: Some("brd 001".to_string()),
+        };
+        let result = |title: &str, catno: Option<&str>| SearchResult {
+            id: 0,
+            title: title.to_string(),
+            cover_image: None,
+            barcode: Vec::new(),
+            catno: catno.map(str::to_string),
+        };
+
+        assert_eq!(query.score(&result("Boards (3) - Bootleg Live", None)), 90);
+        assert_eq!(
+            query.score(&result("Boards - Bootleg Live", Some("BRD-001"))),
+            100
+        );
+        assert_eq!(query.score(&result("Boards - Bootleg Live 2", None)), 65);
+        assert_eq!(query.score(&result("Various - Unrelated", None)), 0);
+        assert!(ReleaseQuery::default().searches().is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! Loose title/artist matching for search APIs that return near-misses
+//! ("Song (Remastered 2011)", "Artist feat. Someone").
+
+use std::collections::HashMap;
+
+use log::trace;
+
+/// Lowercase, drop bracketed qualifiers and punctuation, collapse spaces.
+pub fn normalize(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut depth = 0usize;
+    for c in value.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth > 0 => {}
+            c if c.is_alphanumeric() => out.extend(c.to_lowercase()),
+            _ => out.push(' '),
+        }
+    }
+    let out = out.split_whitespace().collect::<Vec<_>>().join(" ");
+    // Bracketed-only titles like "(Intro)" would otherwise normalize to nothing.
+    if out.is_empty() {
+        value.trim().to_lowercase()
+    } else {
+        out
+    }
+}
+
+/// Similarity from 0 to 100: exact after normalizing, else the Sørensen–Dice
+/// coefficient over character bigrams.
+pub fn similarity(a: &str, b: &str) -> u8 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a.is_empty() || b.is_empty() {
+        return 0;
+    }
+    if a == b {
+        return 100;
+    }
+
+    let bigrams = |s: &str| {
+        let chars: Vec<char> = s.chars().collect();
+        let mut counts: HashMap<(char, char), usize> = HashMap::new();
+        for pair in chars.windows(2) {
+            *counts.entry((pair[0], pair[1])).or_default() += 1;
+        }
+        counts
+    };
+    let (left, right) = (bigrams(&a), bigrams(&b));
+    let total: usize = left.values().sum::<usize>() + right.values().sum::<usize>();
+    if total == 0 {
+        return 0;
+    }
+    let shared: usize = left
+        .iter()
+        .map(|(pair, count)| (*count).min(right.get(pair).copied().unwrap_or(0)))
+        .sum();
+    ((200 * shared) / total) as u8
+}
+
+/// Best similarity between any wanted artist and the result's artist line.
+/// A result credited as "A feat. B" or "A & B" fully matches "A".
+pub fn artist_similarity(wanted: &[String], found: &str) -> u8 {
+    let found_norm = normalize(found);
+    let found_words: Vec<&str> = found_norm.split(' ').collect();
+    wanted
+        .iter()
+        .map(|artist| {
+            let artist_norm = normalize(artist);
+            let width = artist_norm.split(' ').count();
+            let credited = !artist_norm.is_empty()
+                && found_words
+                    .windows(width)
+                    .any(|words| words.join(" ") == artist_norm);
+            if credited {
+                100
+            } else {
+                similarity(artist, found)
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Both title and artist have to clear the bar, so the score is the lower of
+/// the two.
+pub fn track_score(title: &str, artists: &[String], found_title: &str, found_artist: &str) -> u8 {
+    similarity(title, found_title).min(artist_similarity(artists, found_artist))
+}
+
+/// The best scoring of `candidates` at `min_score` or above, scored with
+/// [`track_score`] against the `(artist, title)` that `fields` reads off each.
+pub fn best_scored<C>(
+    title: &str,
+    artists: &[String],
+    candidates: impl IntoIterator<Item = C>,
+    min_score: u8,
+    fields: impl Fn(&C) -> (&str, &str),
+) -> Option<C> {
+    candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let (found_artist, found_title) = fields(&candidate);
+            let score = track_score(title, artists, found_title, found_artist);
+            trace!("'{found_title}' by '{found_artist}' scored {score}");
+            (score >= min_score).then_some((score, candidate))
+        })
+        // Keep the first of equally scored candidates; search APIs list their
+        // best guess first.
+        .fold(None, |best: Option<(u8, C)>, candidate| match best {
+            Some(best) if best.0 >= candidate.0 => Some(best),
+            _ => Some(candidate),
+        })
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_qualifiers_and_punctuation() {
+        assert_eq!(normalize("Don't Stop (Remastered 2011)"), "don t stop");
+        assert_eq!(normalize("  Song  [Live]  "), "song");
+        assert_eq!(normalize("(Intro)"), "(intro)");
+    }
+
+    #[test]
+    fn scores_near_misses_above_unrelated_tracks() {
+        assert_eq!(similarity("Halo", "HALO (Radio Edit)"), 100);
+        assert!(similarity("Colour Me In", "Color Me In") >= 80);
+        assert!(similarity("Colour Me In", "Paint It Black") < 30);
+
+        let artists = vec!["Daft Punk".to_string()];
+        assert_eq!(
+            artist_similarity(&artists, "Daft Punk & Pharrell Williams"),
+            100
+        );
+        assert!(artist_similarity(&artists, "Punk Daft") < 80);
+        assert!(track_score("Get Lucky", &artists, "Get Lucky", "Karaoke Hits") < 50);
+    }
+
+    #[test]
+    fn best_scored_keeps_the_first_of_equal_matches() {
+        let artists = vec!["Daft Punk".to_string()];
+        let results = [
+            ("Karaoke Hits", "Get Lucky", 1),
+            ("Daft Punk", "Get Lucky (Radio Edit)", 2),
+            ("Daft Punk", "Get Lucky", 3),
+        ];
+        let best = best_scored("Get Lucky", &artists, results, 80, |r| (r.0, r.1));
+        assert_eq!(best.map(|r| r.2), Some(2));
+        assert_eq!(
+            best_scored("Get Lucky", &artists, &results[..1], 80, |r| (r.0, r.1)),
+            None
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use log::{debug, info, trace};
+use regex::Regex;
+use reqwest::{multipart, Client};
+use std::time::Duration;
+use tokio::fs;
+
+use super::{create_shared_client, CoverArtProvider, CoverResult};
+use crate::config::schema::HttpUploadConfig;
+use crate::cover::error::CoverArtError;
+use crate::cover::sources::ArtSource;
+use crate::metadata::MetadataSource;
+use tokio_util::sync::CancellationToken;
+
+const PROVIDER: &str = "http_upload";
+
+/// Uploads cover bytes to a self-hosted endpoint as a multipart form and
+/// reads the hosted URL back from the response.
+pub struct HttpUploadProvider {
+    config: HttpUploadConfig,
+    url: String,
+    url_regex: Option<Regex>,
+    client: Client,
+}
+
+impl HttpUploadProvider {
+    pub fn with_config(config: HttpUploadConfig) -> Result<Self, CoverArtError> {
+        info!("Initializing HTTP upload provider");
+        let url = config
+            .url
+            .clone()
+            .ok_or_else(|| CoverArtError::provider_error(PROVIDER, "no upload url configured"))?;
+        let url_regex = config
+            .url_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| {
+                CoverArtError::provider_error(PROVIDER, &format!("invalid url_regex: {e}"))
+            })?;
+        Ok(Self {
+            config,
+            url,
+            url_regex,
+            client: create_shared_client(),
+        })
+    }
+
+    async fn upload(&self, bytes: Vec<u8>) -> Result<String, CoverArtError> {
+        let (mime, extension) = image::guess_format(&bytes)
+            .map(|format| {
+                (
+                    format.to_mime_type(),
+                    format.extensions_str().first().copied().unwrap_or("img"),
+                )
+            })
+            .unwrap_or(("application/octet-stream", "img"));
+        trace!(
+            "Uploading {} bytes ({}) to {} as field '{}'",
+            bytes.len(),
+            mime,
+            self.url,
+            self.config.field
+        );
+
+        let part = multipart::Part::bytes(bytes)
+            .file_name(format!("cover.{extension}"))
+            .mime_str(mime)
+            .map_err(|e| CoverArtError::provider_error(PROVIDER, &format!("{e}")))?;
+        let mut form = multipart::Form::new().part(self.config.field.clone(), part);
+        for (name, value) in &self.config.form {
+            form = form.text(name.clone(), value.clone());
+        }
+
+        let mut request = self.client.post(&self.url).multipart(form);
+        for (name, value) in &self.config.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        if !status.is_success() {
+            return Err(CoverArtError::provider_error(
+                PROVIDER,
+                &format!(
+                    "upload failed with {}: '{}'",
+                    status,
+                    body.chars().take(80).collect::<String>()
+                ),
+            ));
+        }
+
+        self.extract_url(&body)
+    }
+
+    /// Pull the hosted URL out of the response: JSON pointer first, then
+    /// regex (first capture group, or the whole match), else the raw body.
+    fn extract_url(&self, body: &str) -> Result<String, CoverArtError> {
+        let url = if let Some(pointer) = self.config.json_pointer.as_deref() {
+            let json: serde_json::Value =
+                serde_json::from_str(body).map_err(CoverArtError::json_error)?;
+            json.pointer(pointer)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    CoverArtError::provider_error(
+                        PROVIDER,
+                        &format!("no string at json_pointer '{pointer}'"),
+                    )
+                })?
+        } else if let Some(re) = &self.url_regex {
+            let captures = re.captures(body).ok_or_else(|| {
+                CoverArtError::provider_error(PROVIDER, "url_regex did not match the response")
+            })?;
+            captures
+                .get(1)
+                .or_else(|| captures.get(0))
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_default()
+        } else {
+            body.to_string()
+        };
+
+        let url = url.trim();
+        match url::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(url.to_string()),
+            _ => Err(CoverArtError::provider_error(
+                PROVIDER,
+                &format!(
+                    "response did not contain an http(s) URL: '{}'",
+                    url.chars().take(80).collect::<String>()
+                ),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl CoverArtProvider for HttpUploadProvider {
+    fn name(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn supports_source_type(&self, source: &ArtSource) -> bool {
+        matches!(
+            source,
+            ArtSource::File(_) | ArtSource::Bytes(_) | ArtSource::Base64(_)
+        )
+    }
+
+    async fn process(
+        &self,
+        source: ArtSource,
+        _metadata_source: &MetadataSource,
+        cancel: &CancellationToken,
+    ) -> Result<Option<CoverResult>, CoverArtError> {
+        if cancel.is_cancelled() {
+            debug!("HTTP upload provider cancelled before upload");
+            return Ok(None);
+        }
+        debug!("Processing cover art with HTTP upload provider");
+
+        let bytes = match source {
+            ArtSource::File(path) => fs::read(&path).await.map_err(|e| {
+                CoverArtError::provider_error(PROVIDER, &format!("read {:?}: {e}", path))
+            })?,
+            ArtSource::Bytes(data) => data,
+            ArtSource::Base64(data) => STANDARD
+                .decode(data.as_bytes())
+                .map_err(|e| CoverArtError::provider_error(PROVIDER, &format!("{e}")))?,
+            ArtSource::Url(_) => return Ok(None),
+        };
+
+        let url = tokio::select! {
+            result = self.upload(bytes) => result?,
+            _ = cancel.cancelled() => {
+                debug!("HTTP upload cancelled mid-request");
+                return Ok(None);
+            }
+        };
+        info!("HTTP upload provided hosted cover art: {}", url);
+
+        let expiration = if self.config.expiration > 0 {
+            Some(Duration::from_secs(self.config.expiration))
+        } else {
+            None
+        };
+
+        Ok(Some(CoverResult {
+            url,
+            provider: self.name().to_string(),
+            expiration,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{spawn_server, Captured};
+    use std::sync::mpsc;
+
+    // Minimal 1x1 PNG so the part gets an image/png content type.
+    const PNG: &[u8] = &[
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F,
+        0x15, 0xC4, 0x89,
+    ];
+
+    /// Serve a single request with `reply` at `/upload`.
+    fn spawn_host(status: u16, reply: &'static str) -> (String, mpsc::Receiver<Captured>) {
+        let (base, rx) = spawn_server(vec![(status, reply)]);
+        (format!("{base}/upload"), rx)
+    }
+
+    fn provider(url: String, configure: impl FnOnce(&mut HttpUploadConfig)) -> HttpUploadProvider {
+        let mut config = HttpUploadConfig {
+            url: Some(url),
+            ..Default::default()
+        };
+        configure(&mut config);
+        HttpUploadProvider::with_config(config).expect("valid config")
+    }
+
+    async fn run(provider: &HttpUploadProvider) -> Result<Option<CoverResult>, CoverArtError> {
+        provider
+            .process(
+                ArtSource::Bytes(PNG.to_vec()),
+                &MetadataSource::new(None, None),
+                &CancellationToken::new(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn posts_multipart_and_reads_json_pointer() {
+        let (url, rx) = spawn_host(200, r#"{"data":{"url":"https://img.example.com/a.png"}}"#);
+        let provider = provider(url, |config| {
+            config.field = "image".to_string();
+            config.json_pointer = Some("/data/url".to_string());
+            config
+                .headers
+                .insert("Authorization".to_string(), "Bearer secret".to_string());
+            config
+                .form
+                .insert("album".to_string(), "covers".to_string());
+            config.expiration = 3600;
+        });
+
+        let result = run(&provider).await.unwrap().expect("hosted url");
+        assert_eq!(result.url, "https://img.example.com/a.png");
+        assert_eq!(result.provider, "http_upload");
+        assert_eq!(result.expiration, Some(Duration::from_secs(3600)));
+
+        let captured = rx.recv().unwrap();
+        assert_eq!(captured.header("Authorization"), Some("Bearer secret"));
+        assert!(captured
+            .header("Content-Type")
+            .unwrap()
+            .starts_with("multipart/form-data"));
+        let body = captured.body_text();
+        assert!(body.contains(r#"name="image"; filename="cover.png""#));
+        assert!(body.contains("Content-Type: image/png"));
+        assert!(body.contains(r#"name="album""#));
+    }
+
+    #[tokio::test]
+    async fn extracts_url_with_regex_capture() {
+        let (url, _rx) = spawn_host(200, "<a href=\"https://img.example.com/b.jpg\">done</a>");
+        let provider = provider(url, |config| {
+            config.url_regex = Some(r#"href="([^"]+)""#.to_string());
+        });
+
+        let result = run(&provider).await.unwrap().expect("hosted url");
+        assert_eq!(result.url, "https://img.example.com/b.jpg");
+        assert_eq!(result.expiration, None);
+    }
+
+    #[tokio::test]
+    async fn rejects_error_status_and_non_url_bodies() {
+        let (url, _rx) = spawn_host(500, "https://img.example.com/c.png");
+        assert!(run(&provider(url, |_| {})).await.is_err());
+
+        let (url, _rx) = spawn_host(200, "<html>upload failed</html>");
+        assert!(run(&provider(url, |_| {})).await.is_err());
+
+        let (url, _rx) = spawn_host(200, "https://img.example.com/d.png\n");
+        let result = run(&provider(url, |_| {}))
+            .await
+            .unwrap()
+            .expect("raw body url");
+        assert_eq!(result.url, "https://img.example.com/d.png");
+    }
+
+    #[test]
+    fn requires_upload_url_and_valid_regex() {
+        assert!(HttpUploadProvider::with_config(HttpUploadConfig::default()).is_err());
+        assert!(HttpUploadProvider::with_config(HttpUploadConfig {
+            url: Some("http://localhost/upload".to_string()),
+            url_regex: Some("(".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use async_trait::async_trait;
+use log::{debug, info};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{create_shared_client, fuzzy, CoverArtProvider, CoverResult};
+use crate::config::schema::ItunesConfig;
+use crate::cover::error::CoverArtError;
+use crate::cover::sources::ArtSource;
+use crate::metadata::MetadataSource;
+use tokio_util::sync::CancellationToken;
+
+const PROVIDER: &str = "itunes";
+const ITUNES_API: &str = "https://itunes.apple.com";
+const SEARCH_LIMIT: &str = "10";
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    results: Vec<Track>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Track {
+    #[serde(default)]
+    track_name: String,
+    #[serde(default)]
+    artist_name: String,
+    #[serde(default)]
+    artwork_url100: Option<String>,
+}
+
+/// Artwork URLs end in a size segment such as `100x100bb.jpg`; the CDN
+/// renders any size asked for.
+fn upscale_artwork(url: &str, size: u32) -> String {
+    match url.rsplit_once('/') {
+        Some((base, last)) if last.contains("x") && last.contains("bb.") => {
+            format!("{base}/{size}x{size}bb.jpg")
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// Searches the public iTunes Search API by artist and title. Useful for
+/// streaming-only tracks that carry no album or MusicBrainz IDs.
+#[derive(Clone)]
+pub struct ItunesProvider {
+    client: Client,
+    config: ItunesConfig,
+    api: String,
+}
+
+impl ItunesProvider {
+    pub fn with_config(config: ItunesConfig) -> Self {
+        Self::with_api(config, ITUNES_API)
+    }
+
+    fn with_api(config: ItunesConfig, api: &str) -> Self {
+        info!(
+            "Initializing iTunes provider with min_score: {}, country: {}",
+            config.min_score, config.country
+        );
+        Self {
+            client: create_shared_client(),
+            config,
+            api: api.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn search(&self, title: &str, artist: &str) -> Result<Vec<Track>, CoverArtError> {
+        let mut url = url::Url::parse(&format!("{}/search", self.api))
+            .map_err(|e| CoverArtError::provider_error(PROVIDER, &format!("{e}")))?;
+        url.query_pairs_mut()
+            .append_pair("term", &format!("{artist} {title}"))
+            .append_pair("media", "music")
+            .append_pair("entity", "song")
+            .append_pair("country", &self.config.country)
+            .append_pair("limit", SEARCH_LIMIT);
+        debug!("Searching iTunes: {}", url);
+
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(CoverArtError::provider_error(
+                PROVIDER,
+                &format!("search failed with {status}"),
+            ));
+        }
+        // The API answers with text/javascript, so parse the body ourselves.
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| CoverArtError::NetworkError(format!("{PROVIDER}: {e}")))?;
+        let data: SearchResponse =
+            serde_json::from_slice(&body).map_err(CoverArtError::json_error)?;
+        debug!("Found {} iTunes tracks", data.results.len());
+        Ok(data.results)
+    }
+
+    fn best_match(&self, title: &str, artists: &[String], tracks: &[Track]) -> Option<String> {
+        let with_artwork = tracks
+            .iter()
+            .filter_map(|track| Some((track, track.artwork_url100.as_deref()?)));
+        let (_, artwork) = fuzzy::best_scored(
+            title,
+            artists,
+            with_artwork,
+            self.config.min_score,
+            |(track, _)| (&track.artist_name, &track.track_name),
+        )?;
+        Some(upscale_artwork(artwork, self.config.artwork_size))
+    }
+}
+
+#[async_trait]
+impl CoverArtProvider for ItunesProvider {
+    fn name(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn supports_source_type(&self, _source: &ArtSource) -> bool {
+        true // iTunes works from metadata only
+    }
+
+    fn supports_metadata_only(&self) -> bool {
+        true
+    }
+
+    async fn process(
+        &self,
+        _source: ArtSource,
+        metadata_source: &MetadataSource,
+        cancel: &CancellationToken,
+    ) -> Result<Option<CoverResult>, CoverArtError> {
+        if cancel.is_cancelled() {
+            debug!("iTunes provider cancelled before start");
+            return Ok(None);
+        }
+        info!("Processing metadata with iTunes provider");
+
+        let Some((title, artists)) = super::title_and_artists(metadata_source) else {
+            debug!("No title and artist available for iTunes search");
+            return Ok(None);
+        };
+
+        let tracks = tokio::select! {
+            result = self.search(&title, &artists[0]) => result?,
+            _ = cancel.cancelled() => {
+                debug!("iTunes search cancelled");
+                return Ok(None);
+            }
+        };
+
+        match self.best_match(&title, &artists, &tracks) {
+            Some(url) => {
+                info!("Successfully found cover art via iTunes: {}", url);
+                Ok(Some(CoverResult {
+                    url,
+                    provider: self.name().to_string(),
+                    expiration: None,
+                }))
+            }
+            None => {
+                debug!(
+                    "No iTunes track met the minimum score of {}",
+                    self.config.min_score
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cover::providers::tests::metadata;
+    use crate::test_util::spawn_server;
+
+    const FIXTURE: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/itunes_search.json"
+    ));
+
+    fn provider(api: &str, min_score: u8) -> ItunesProvider {
+        ItunesProvider::with_api(
+            ItunesConfig {
+                min_score,
+                ..Default::default()
+            },
+            api,
+        )
+    }
+
+    #[tokio::test]
+    async fn picks_matching_track_and_upscales_artwork() {
+        let (api, rx) = spawn_server(vec![(200, FIXTURE)]);
+        let source = metadata(&[("xesam:title", "Get Lucky"), ("xesam:artist", "Daft Punk")]);
+
+        let result = provider(&api, 80)
+            .process(
+                ArtSource::Url(String::new()),
+                &source,
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap()
+            .expect("cover");
+        assert_eq!(
+            result.url,
+            "https://is1-ssl.mzstatic.com/image/thumb/Music115/v4/e8/43/5f/e8435ffa-b6b9-b171-40ab-4ff3959ab661/886443919266.jpg/600x600bb.jpg"
+        );
+        assert_eq!(result.provider, "itunes");
+        assert_eq!(result.expiration, None);
+
+        let url = rx.recv().unwrap().url;
+        assert!(url.starts_with("/search?term=Daft+Punk+Get+Lucky"));
+        assert!(url.contains("entity=song"));
+        assert!(url.contains("country=us"));
+    }
+
+    #[tokio::test]
+    async fn rejects_results_below_min_score() {
+        let (api, _rx) = spawn_server(vec![(200, FIXTURE)]);
+        let source = metadata(&[("xesam:title", "Get Lucky"), ("xesam:artist", "Justice")]);
+
+        let result = provider(&api, 80)
+            .process(
+                ArtSource::Url(String::new()),
+                &source,
+                &CancellationToken::new(),
+            )
+            .await
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn upscales_only_sized_artwork_urls() {
+        assert_eq!(
+            upscale_artwork("https://cdn.example.com/a/b.jpg/100x100bb.jpg", 1200),
+            "https://cdn.example.com/a/b.jpg/1200x1200bb.jpg"
+        );
+        assert_eq!(
+            upscale_artwork("https://cdn.example.com/cover.jpg", 1200),
+            "https://cdn.example.com/cover.jpg"
+        );
+    }
+}
@@ -8,8 +8,14 @@ use crate::cover::sources::ArtSource;
 use crate::metadata::MetadataSource;
 
 pub mod catbox;
+pub mod deezer;
+pub mod discogs;
+mod fuzzy;
+pub mod http_upload;
 pub mod imgbb;
+pub mod itunes;
 pub mod musicbrainz;
+pub mod s3;
 
 const USER_AGENT: &str = concat!(
     env!("CARGO_PKG_NAME"),
@@ -24,7 +30,6 @@ const USER_AGENT: &str = concat!(
 pub struct CoverResult {
     pub url: String,
     pub provider: String,
-    #[allow(dead_code)]
     pub expiration: Option<Duration>,
 }
 
@@ -53,6 +58,24 @@ pub fn create_shared_client() -> Client {
         .expect("Failed to create HTTP client")
 }
 
+/// Title plus track artists (album artists as a fallback) for providers that
+/// search by track.
+fn title_and_artists(metadata_source: &MetadataSource) -> Option<(String, Vec<String>)> {
+    let title = metadata_source.title().filter(|t| !t.trim().is_empty())?;
+    let non_empty = |artists: Option<Vec<String>>| {
+        artists
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|a| !a.trim().is_empty())
+            .collect::<Vec<_>>()
+    };
+    let mut artists = non_empty(metadata_source.artists());
+    if artists.is_empty() {
+        artists = non_empty(metadata_source.album_artists());
+    }
+    (!artists.is_empty()).then_some((title, artists))
+}
+
 #[async_trait]
 pub trait CoverArtProvider: Send + Sync {
     fn name(&self) -> &'static str;
@@ -70,3 +93,37 @@ pub trait CoverArtProvider: Send + Sync {
         cancel: &CancellationToken,
     ) -> Result<Option<CoverResult>, CoverArtError>;
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use mpris::{Metadata, MetadataValue};
+    use std::collections::HashMap;
+
+    /// Metadata with the given xesam fields; artist fields become arrays.
+    pub fn metadata(fields: &[(&str, &str)]) -> MetadataSource {
+        let mut map: HashMap<String, MetadataValue> = HashMap::new();
+        for (key, value) in fields {
+            let value = if matches!(*key, "xesam:artist" | "xesam:albumArtist") {
+                MetadataValue::Array(vec![MetadataValue::String(value.to_string())])
+            } else {
+                MetadataValue::String(value.to_string())
+            };
+            map.insert(key.to_string(), value);
+        }
+        MetadataSource::new(Some(Metadata::from(map)), None)
+    }
+
+    #[test]
+    fn title_and_artists_falls_back_to_album_artists() {
+        let source = metadata(&[("xesam:title", "Song"), ("xesam:albumArtist", "Band")]);
+        assert_eq!(
+            title_and_artists(&source),
+            Some(("Song".to_string(), vec!["Band".to_string()]))
+        );
+        assert_eq!(
+            title_and_artists(&metadata(&[("xesam:title", "Song")])),
+            None
+        );
+    }
+}
@@ -22,6 +22,15 @@ pub enum Error {
 
     #[error("Player finding error: {0}")]
     PlayerFinding(#[from] FindingError),
+
+    #[error("Control error: {0}")]
+    Control(#[from] crate::control::ControlError),
+
+    #[error("Cover cache error: {0}")]
+    CoverCache(#[from] cover::error::CoverArtError),
+
+    #[error("Scrobble error: {0}")]
+    Scrobble(#[from] crate::scrobble::error::ScrobbleError),
 }
 
 #[derive(Error, Debug)]
@@ -1,11 +1,19 @@
+pub mod bar;
 pub mod config;
 pub mod config_ui;
+pub mod control;
 pub mod cover;
 pub mod discord;
 pub mod error;
+pub mod lyrics;
 pub mod metadata;
+pub mod mpd;
 pub mod player;
 pub mod presence;
+pub mod scrobble;
+pub mod sink;
 pub mod template;
+#[cfg(test)]
+pub(crate) mod test_util;
 pub mod utils;
 pub mod web_bridge;
@@ -0,0 +1,300 @@
+//! Synced lyrics for the `{{lyric_line}}` template variable. Read from an
+//! `.lrc` file next to the audio file, else from the file's own tags: an
+//! ID3v2 `SYLT` frame, or a lyrics tag (`USLT`, `LYRICS`, `©lyr`), which
+//! often holds LRC text. Unsynced lyrics have no current line and are
+//! ignored.
+
+use std::{
+    fs::{self, File},
+    path::Path,
+    time::Duration,
+};
+
+use lofty::{
+    config::ParseOptions,
+    file::{AudioFile, FileType, TaggedFile, TaggedFileExt},
+    id3::v2::{Frame, SyncTextContentType, SynchronizedTextFrame, TimestampFormat},
+    mpeg::MpegFile,
+    prelude::ItemKey,
+    tag::TagType,
+};
+use log::debug;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricLine {
+    pub start: Duration,
+    /// Empty for a break between lines.
+    pub text: String,
+}
+
+/// Timed lyric lines, sorted by start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lyrics {
+    lines: Vec<LyricLine>,
+}
+
+impl Lyrics {
+    /// Lyrics for the file at `path`, whose tags are in `tagged_file`.
+    pub fn load(path: Option<&Path>, tagged_file: Option<&TaggedFile>) -> Option<Self> {
+        if let Some(lyrics) = path.and_then(Self::from_sidecar) {
+            return Some(lyrics);
+        }
+        let tagged_file = tagged_file?;
+        let has_id3v2 =
+            tagged_file.file_type() == FileType::Mpeg && tagged_file.tag(TagType::Id3v2).is_some();
+        if let Some(lyrics) = path.filter(|_| has_id3v2).and_then(Self::from_sylt) {
+            return Some(lyrics);
+        }
+        let lyrics = tagged_file.tags().iter().find_map(|tag| {
+            [ItemKey::Lyrics, ItemKey::UnsyncLyrics]
+                .into_iter()
+                .filter_map(|key| tag.get_string(key))
+                .find_map(Self::parse_lrc)
+        })?;
+        debug!("Lyrics: {} lines from tags", lyrics.lines.len());
+        Some(lyrics)
+    }
+
+    fn from_sidecar(path: &Path) -> Option<Self> {
+        let sidecar = path.with_extension("lrc");
+        let lyrics = Self::parse_lrc(&fs::read_to_string(&sidecar).ok()?)?;
+        debug!("Lyrics: {} lines from {:?}", lyrics.lines.len(), sidecar);
+        Some(lyrics)
+    }
+
+    /// The first `SYLT` frame with millisecond timestamps. lofty keeps
+    /// `SYLT` out of the generic tag, so the ID3v2 tag is read again.
+    fn from_sylt(path: &Path) -> Option<Self> {
+        let mut file = File::open(path).ok()?;
+        let mpeg =
+            MpegFile::read_from(&mut file, ParseOptions::new().read_properties(false)).ok()?;
+        let lyrics = mpeg.id3v2()?.into_iter().find_map(|frame| {
+            let Frame::Binary(binary) = frame else {
+                return None;
+            };
+            if frame.id().as_str() != "SYLT" {
+                return None;
+            }
+            let sylt = SynchronizedTextFrame::parse(&binary.data, frame.flags()).ok()?;
+            if sylt.timestamp_format != TimestampFormat::MS
+                || !matches!(
+                    sylt.content_type,
+                    SyncTextContentType::Lyrics | SyncTextContentType::Other
+                )
+            {
+                return None;
+            }
+            let lines = sylt
+                .content
+                .into_iter()
+                .map(|(start, text)| LyricLine {
+                    start: Duration::from_millis(start.into()),
+                    text: text.trim().to_string(),
+                })
+                .collect();
+            Self::from_lines(lines)
+        })?;
+        debug!("Lyrics: {} lines from SYLT", lyrics.lines.len());
+        Some(lyrics)
+    }
+
+    /// Parse LRC text: `[mm:ss.xx]` line timestamps (several per line
+    /// allowed), an `[offset:ms]` tag, and enhanced-LRC `<mm:ss.xx>` word
+    /// timestamps, which are dropped. `None` when nothing is timed.
+    pub fn parse_lrc(text: &str) -> Option<Self> {
+        let mut offset_ms: i64 = 0;
+        let mut timed = Vec::new();
+        for line in text.lines() {
+            let mut rest = line.trim();
+            let mut starts = Vec::new();
+            while let Some((tag, after)) =
+                rest.strip_prefix('[').and_then(|tag| tag.split_once(']'))
+            {
+                if let Some(start) = parse_timestamp(tag) {
+                    starts.push(start);
+                } else if let Some(offset) = tag.strip_prefix("offset:") {
+                    offset_ms = offset
+                        .trim()
+                        .parse::<i64>()
+                        .unwrap_or(0)
+                        .clamp(-MAX_OFFSET_MS, MAX_OFFSET_MS);
+                }
+                rest = after;
+            }
+            let text = strip_word_timestamps(rest);
+            timed.extend(starts.into_iter().map(|start| (start, text.clone())));
+        }
+
+        // A positive offset shows lines sooner.
+        let lines = timed
+            .into_iter()
+            .map(|(start, text)| {
+                let start = if offset_ms >= 0 {
+                    start.saturating_sub(offset_ms.unsigned_abs())
+                } else {
+                    start.saturating_add(offset_ms.unsigned_abs())
+                };
+                LyricLine {
+                    start: Duration::from_millis(start),
+                    text,
+                }
+            })
+            .collect();
+        Self::from_lines(lines)
+    }
+
+    fn from_lines(mut lines: Vec<LyricLine>) -> Option<Self> {
+        if lines.is_empty() {
+            return None;
+        }
+        lines.sort_by_key(|line| line.start);
+        Some(Self { lines })
+    }
+
+    /// The line playing at `position`; `None` before the first line and in
+    /// breaks.
+    pub fn line_at(&self, position: Duration) -> Option<&str> {
+        let index = self
+            .lines
+            .partition_point(|line| line.start <= position)
+            .checked_sub(1)?;
+        Some(self.lines[index].text.as_str()).filter(|text| !text.is_empty())
+    }
+}
+
+/// Largest `[offset:]` honoured, a day either way; files from the wild
+/// can hold anything.
+const MAX_OFFSET_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// `mm:ss`, `mm:ss.xx` or `mm:ss:xx`, in milliseconds. `None` when it does
+/// not fit.
+fn parse_timestamp(tag: &str) -> Option<u64> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let (seconds, fraction) = match seconds.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, fraction),
+        None => (seconds, ""),
+    };
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !digits(minutes) || !digits(seconds) || !(fraction.is_empty() || digits(fraction)) {
+        return None;
+    }
+    // `.5` is 500 ms and `.05` is 50 ms.
+    let fraction_ms = format!("{:0<3}", &fraction[..fraction.len().min(3)])
+        .parse::<u64>()
+        .ok()?;
+    minutes
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(60_000)?
+        .checked_add(seconds.parse::<u64>().ok()?.checked_mul(1000)?)?
+        .checked_add(fraction_ms)
+}
+
+fn strip_word_timestamps(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>') else {
+            break;
+        };
+        stripped.push_str(&rest[..open]);
+        let tag = &rest[open + 1..open + close];
+        if parse_timestamp(tag).is_none() {
+            stripped.push_str(&rest[open..=open + close]);
+        }
+        rest = &rest[open + close + 1..];
+    }
+    stripped.push_str(rest);
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    #[test]
+    fn parses_lrc_with_tags_offsets_and_repeated_lines() {
+        let lyrics = Lyrics::parse_lrc(
+            "[ar:Daft Punk]\n\
+             [ti:Get Lucky]\n\
+             [offset:+500]\n\
+             [00:10.50]Like the legend of the phoenix\n\
+             [00:14.00][01:20.5]All ends with beginnings\n\
+             [00:18:20]\n\
+             [00:20.00]<00:20.00>What <00:20.40>keeps <00:20.80>the planet spinning\n",
+        )
+        .expect("synced lyrics");
+
+        assert_eq!(lyrics.line_at(secs(5.0)), None);
+        assert_eq!(
+            lyrics.line_at(secs(10.0)),
+            Some("Like the legend of the phoenix")
+        );
+        assert_eq!(lyrics.line_at(secs(13.6)), Some("All ends with beginnings"));
+        // `[00:18:20]` with no text is a break.
+        assert_eq!(lyrics.line_at(secs(18.0)), None);
+        assert_eq!(
+            lyrics.line_at(secs(19.6)),
+            Some("What keeps the planet spinning")
+        );
+        assert_eq!(lyrics.line_at(secs(80.0)), Some("All ends with beginnings"));
+    }
+
+    #[test]
+    fn unsynced_text_is_not_lyrics() {
+        assert_eq!(Lyrics::parse_lrc("Just some words\n[chorus]\n"), None);
+        assert_eq!(Lyrics::parse_lrc(""), None);
+    }
+
+    #[test]
+    fn parses_timestamp_precisions() {
+        assert_eq!(parse_timestamp("01:02"), Some(62_000));
+        assert_eq!(parse_timestamp("01:02.5"), Some(62_500));
+        assert_eq!(parse_timestamp("01:02.05"), Some(62_050));
+        assert_eq!(parse_timestamp("01:02.123"), Some(62_123));
+        assert_eq!(parse_timestamp("ar:Daft Punk"), None);
+        assert_eq!(parse_timestamp("offset:+500"), None);
+    }
+
+    #[test]
+    fn hostile_timestamps_and_offsets_do_not_overflow() {
+        assert_eq!(parse_timestamp("99999999999999999:00"), None);
+        assert_eq!(parse_timestamp("00:99999999999999999"), None);
+        // The minutes alone fit in a u64; adding the seconds does not.
+        assert_eq!(parse_timestamp("307445734561825:51"), Some(u64::MAX - 615));
+        assert_eq!(parse_timestamp("307445734561825:52"), None);
+
+        let lyrics = Lyrics::parse_lrc(
+            "[offset:-9223372036854775808]\n\
+             [99999999:00]Far\n\
+             [00:01]Near\n",
+        )
+        .expect("synced lyrics");
+        assert_eq!(lyrics.line_at(secs(0.5)), None);
+        assert_eq!(
+            lyrics.line_at(Duration::from_millis(1000 + MAX_OFFSET_MS as u64)),
+            Some("Near")
+        );
+
+        let lyrics = Lyrics::parse_lrc("[offset:9223372036854775807]\n[00:01]Soon\n")
+            .expect("synced lyrics");
+        assert_eq!(lyrics.line_at(Duration::ZERO), Some("Soon"));
+    }
+
+    #[test]
+    fn loads_sidecar_next_to_the_audio_file() {
+        let dir = std::env::temp_dir().join(format!("mprisence-lyrics-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("song.lrc"), "[00:01.00]Hello\n").unwrap();
+
+        let lyrics = Lyrics::load(Some(&dir.join("song.flac")), None).expect("sidecar lyrics");
+        assert_eq!(lyrics.line_at(secs(2.0)), Some("Hello"));
+        assert_eq!(Lyrics::load(Some(&dir.join("other.flac")), None), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,40 +1,55 @@
 use clap::Parser;
-use config::{get_config, ConfigManager};
+use config::{get_config, schema::LoserAction, ConfigManager};
 use cover::CoverManager;
 use error::MprisenceError;
 use log::{debug, error, info, trace, warn};
 use mpris::Event as MprisEvent;
-use mpris::PlayerFinder;
 use player::{
-    bridge_browser, canonical_player_bus_name, compute_presence_migrations,
+    arbitration::{Arbiter, Contender, Standing},
+    canonical_player_bus_name, compute_presence_migrations, discovery,
     events::{EventOutcome, PlayerEvent, PlayerEventKind},
-    is_mprisence_web_bridge_bus, is_playerctld_no_active_error, merge_url_duplicates,
-    select_richest_player, select_winner_idx, should_suppress_native, BucketSummary,
-    PlayerIdentifier,
+    select_richest_player, select_winner_idx, BucketSummary, PlayerIdentifier,
 };
 use presence::Presence;
+use serde_json::{json, Value};
+use sink::SinkManager;
 use smol_str::SmolStr;
 use std::{
     alloc::System,
     collections::{HashMap, HashSet},
-    sync::Arc,
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::sync::mpsc;
 
 #[global_allocator]
 static GLOBAL: System = System;
 
+/// How often presences check for template values that move with playback
+/// (the lyric line, `[progress]` refreshes) between updates.
+const PRESENCE_TICK: Duration = Duration::from_secs(1);
+
+mod bar;
 mod cli;
 mod config;
 mod config_ui;
+mod control;
 mod cover;
 mod discord;
 mod error;
+mod lyrics;
 mod metadata;
+mod mpd;
 mod player;
 mod presence;
+mod scrobble;
+mod sink;
 mod template;
+#[cfg(test)]
+mod test_util;
 mod utils;
 mod web_bridge;
 
@@ -83,10 +98,21 @@ pub struct Mprisence {
     /// Last seen deduplication selection for identities that currently expose
     /// multiple bus names.
     dedup_selection: HashMap<SmolStr, DedupSelection>,
+    /// `[arbitration]` state: which presence owns the activity.
+    arbiter: Arbiter,
     template_manager: Arc<template::TemplateManager>,
     cover_manager: Arc<CoverManager>,
+    sink_manager: Arc<SinkManager>,
     config_rx: config::ConfigChangeReceiver,
     config: Arc<ConfigManager>,
+    /// Hidden state: set by `mprisence ctl pause`, the runtime-dir flag file
+    /// or SIGUSR1. Shared with every `Presence`, which skips pushes while set.
+    paused: Arc<AtomicBool>,
+    /// End of a timed snooze; `None` when paused indefinitely or not paused.
+    paused_until: Option<SystemTime>,
+    /// Cover URLs replaced or dropped by background cache maintenance.
+    stale_cover_tx: mpsc::Sender<String>,
+    stale_cover_rx: mpsc::Receiver<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -107,14 +133,25 @@ impl Mprisence {
         trace!("Creating cover manager");
         let cover_manager = Arc::new(CoverManager::new(&config)?);
 
+        trace!("Creating sink manager");
+        let sink_manager = Arc::new(SinkManager::new(&config));
+
+        let (stale_cover_tx, stale_cover_rx) = mpsc::channel(16);
+
         debug!("Service initialization complete");
         Ok(Self {
             media_players: HashMap::new(),
             dedup_selection: HashMap::new(),
+            arbiter: Arbiter::default(),
             template_manager,
             cover_manager,
+            sink_manager,
             config_rx: config.subscribe(),
             config,
+            paused: Arc::new(AtomicBool::new(false)),
+            paused_until: None,
+            stale_cover_tx,
+            stale_cover_rx,
         })
     }
 
@@ -123,8 +160,13 @@ impl Mprisence {
         self.config = get_config();
 
         self.template_manager = Arc::new(template::TemplateManager::new(&self.config)?);
-        self.cover_manager = Arc::new(CoverManager::new(&self.config)?);
-        debug!("Template and cover managers updated successfully");
+        let cover_manager = Arc::new(CoverManager::new(&self.config)?);
+        cover_manager.carry_recent_from(&self.cover_manager);
+        self.cover_manager = cover_manager;
+        self.cover_manager
+            .spawn_maintenance(self.stale_cover_tx.clone());
+        self.sink_manager = Arc::new(SinkManager::new(&self.config));
+        debug!("Template, cover and sink managers updated successfully");
 
         for (_norm_id, presence) in self.media_players.iter_mut() {
             let pid = presence.player_id();
@@ -154,6 +196,7 @@ impl Mprisence {
                 presence.update_managers(
                     self.template_manager.clone(),
                     self.cover_manager.clone(),
+                    self.sink_manager.clone(),
                     self.config.clone(),
                 );
             }
@@ -175,6 +218,7 @@ impl Mprisence {
                     .is_player_allowed(&pid.identity, &pid.player_bus_name)
         });
 
+        self.arbitrate().await;
         debug!("All media players updated with new configuration");
         Ok(())
     }
@@ -190,115 +234,14 @@ impl Mprisence {
                     warn!("Failed to destroy Discord client: {}", e);
                 }
             }
-            return Ok(());
-        }
-
-        trace!("Scanning for active media players");
-
-        let mut player_finder = PlayerFinder::new()?;
-        player_finder.set_player_timeout_ms(5000);
-
-        // Phase 1: collect all allowed, non-ignored players and group them by
-        // normalised identity so that multiple bus names for the same underlying
-        // player (e.g. `mpd` and `playerctld`) are treated as one logical player.
-        let mut candidates: HashMap<SmolStr, Vec<mpris::Player>> = HashMap::new();
-
-        for player in player_finder.iter_players()? {
-            let player = match player {
-                Ok(p) => p,
-                Err(err) if is_playerctld_no_active_error(&err) => {
-                    debug!("Skipping playerctld proxy without an active player during discovery");
-                    continue;
-                }
-                Err(err) => return Err(err.into()),
-            };
-
-            let id = PlayerIdentifier::from(&player);
-
-            if !self
-                .config
-                .is_player_allowed(&id.identity, &id.player_bus_name)
-            {
-                trace!("Skipping disallowed player: {}", id.identity);
-                continue;
-            }
-
-            let metadata = player.get_metadata().ok();
-            let url = metadata
-                .as_ref()
-                .and_then(|m| m.url().map(|s| s.to_string()));
-            let title = metadata
-                .as_ref()
-                .and_then(|m| m.title().map(|s| s.to_string()));
-            let (player_config, _) = self.config.get_player_config_with_title_fallback(
-                &id.identity,
-                &id.player_bus_name,
-                url.as_deref(),
-                title.as_deref(),
-            );
-            if player_config.ignore {
-                trace!(
-                    "Skipping ignored player: {} ({})",
-                    id.identity,
-                    url.as_deref().unwrap_or("no URL")
-                );
-                continue;
+            // [[sink]] outputs keep receiving presence without Discord.
+            if self.sink_manager.is_empty() {
+                return Ok(());
             }
-
-            // Bridge players are one-per-tab: their canonical bus name
-            // (`mprisence_web.<site>.<hash>`) is unique per tab, so key by it.
-            // Native players are keyed by normalized identity so multiple bus
-            // names for the same player still collapse to one.
-            let norm_id = if is_mprisence_web_bridge_bus(&id.player_bus_name) {
-                id.player_bus_name.clone()
-            } else {
-                SmolStr::new(utils::normalize_player_identity(&id.identity))
-            };
-            candidates.entry(norm_id).or_default().push(player);
         }
 
-        // Phase 1.5: merge identity groups that share the same xesam:url, so
-        // that e.g. plasma-browser-integration and the native browser MPRIS
-        // endpoint exposing the same tab are tracked as one logical player.
-        let candidates = merge_url_duplicates(candidates);
-
-        // Phase 1.6a: when a native browser MPRIS endpoint exposes the same
-        // tab as a live bridge player, suppress the native endpoint so the tab
-        // is not double-counted. Keep unrelated native browser entries (e.g.
-        // SoundCloud in the browser when only YouTube tabs are bridged).
-        let bridged_sources: Vec<(String, String)> = candidates
-            .values()
-            .flatten()
-            .filter(|p| is_mprisence_web_bridge_bus(p.bus_name()))
-            // A D-Bus metadata error silently skips the player for this scan cycle.
-            .filter_map(|p| {
-                let metadata = p.get_metadata().ok()?;
-                let browser = bridge_browser(&metadata)?;
-                let url = metadata.url().map(|s| s.to_string())?;
-                Some((browser, url))
-            })
-            .collect();
-        let candidates: HashMap<SmolStr, Vec<mpris::Player>> = if bridged_sources.is_empty() {
-            candidates
-        } else {
-            candidates
-                .into_iter()
-                // A post-merge candidate group holds players of a single kind
-                // (bridge groups are keyed per-tab and never URL-merged), so a
-                // group is dropped only when every player in it is a native
-                // browser bus already represented by a bridge player.
-                .filter(|(_, players)| {
-                    !players.iter().all(|p| {
-                        let canon = canonical_player_bus_name(p.bus_name());
-                        let native_url = p
-                            .get_metadata()
-                            .ok()
-                            .and_then(|m| m.url().map(|s| s.to_string()));
-                        should_suppress_native(&canon, native_url.as_deref(), &bridged_sources)
-                    })
-                })
-                .collect()
-        };
+        trace!("Scanning for active media players");
+        let candidates = discovery::find_candidates(&self.config)?;
 
         // Phase 1.6: presence-key migration. When URL-merge changes the
         // post-merge norm_id of an existing logical player (e.g. plasma
@@ -428,11 +371,13 @@ impl Mprisence {
             trace!("Processing player {}", winner_id);
 
             if let Some(presence) = self.media_players.get_mut(&norm_id) {
-                if let Err(e) = presence.initialize_discord_client() {
-                    warn!(
-                        "Failed to initialize Discord client for {}: {}",
-                        winner_id.identity, e
-                    );
+                if discord_running {
+                    if let Err(e) = presence.initialize_discord_client() {
+                        warn!(
+                            "Failed to initialize Discord client for {}: {}",
+                            winner_id.identity, e
+                        );
+                    }
                 }
                 // In event-driven mode, signals drive most Discord updates. The discovery
                 // tick still calls presence.update() so that position jumps (seeks on
@@ -451,13 +396,25 @@ impl Mprisence {
                     winner_player,
                     self.template_manager.clone(),
                     self.cover_manager.clone(),
+                    self.sink_manager.clone(),
                     self.config.clone(),
+                    self.paused.clone(),
                 );
-                if let Err(e) = presence.initialize_discord_client() {
-                    warn!(
-                        "Failed to initialize Discord client for new player {}: {}",
-                        winner_id.identity, e
-                    );
+                // Hold a newcomer back until arbitration has decided, so it
+                // cannot take over the activity for one push.
+                if let Some(owner) = self.arbiter.owner() {
+                    presence.set_standing(Some(Standing::Outranked {
+                        by: owner.clone(),
+                        rule: None,
+                    }));
+                }
+                if discord_running {
+                    if let Err(e) = presence.initialize_discord_client() {
+                        warn!(
+                            "Failed to initialize Discord client for new player {}: {}",
+                            winner_id.identity, e
+                        );
+                    }
                 }
                 // Always do the initial Discord push when a new player is discovered,
                 // even in event-driven mode — signals only fire on *changes*, so the
@@ -514,26 +471,91 @@ impl Mprisence {
             current_norm_ids.contains(norm_id) && duplicate_norm_ids.contains(norm_id)
         });
 
+        self.arbitrate().await;
         Ok(())
     }
 
+    /// Apply `[arbitration]`: a presence that loses the activity is cleared
+    /// or held back, one that wins it back is pushed again.
+    async fn arbitrate(&mut self) {
+        let config = self.config.arbitration_config();
+        let contenders: Vec<Contender> = self
+            .media_players
+            .iter()
+            .map(|(key, presence)| Contender {
+                key: key.clone(),
+                identity: presence.player_id().identity.clone(),
+                bus_name: presence.player_id().player_bus_name.clone(),
+                status: presence.playback_status(),
+                activity_type: presence.activity_type(),
+                publishes: presence.publishes(),
+            })
+            .collect();
+        let mut standings = self.arbiter.arbitrate(&config, &contenders);
+
+        for (key, presence) in self.media_players.iter_mut() {
+            let standing = standings.remove(key);
+            if standing.is_some() && presence.standing() != standing.as_ref() {
+                debug!(
+                    "Arbitration: {} {}",
+                    key,
+                    standing
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_default()
+                );
+            }
+            match presence.set_standing(standing) {
+                Some(true) if config.losers == LoserAction::Clear => {
+                    if let Err(e) = presence.hold_back_activity(&format!(
+                        "Clearing Discord activity for {} (outranked)",
+                        key
+                    )) {
+                        warn!("Failed to clear presence for {}: {}", key, e);
+                    }
+                }
+                Some(false) => {
+                    info!("Arbitration: {} publishes again", key);
+                    if let Err(e) = presence.refresh().await {
+                        warn!("Failed to refresh presence for {}: {}", key, e);
+                    }
+                }
+                Some(true) | None => {}
+            }
+        }
+    }
+
     pub async fn run(&mut self) -> Result<(), MprisenceError> {
         info!("Starting mprisence service");
+        let (control_tx, mut control_rx) = mpsc::channel::<control::ControlCommand>(16);
+        // Keep the server alive for the whole run; dropping it removes the socket.
+        let _control_server = control::serve(control_tx).await;
+
+        self.cover_manager
+            .spawn_maintenance(self.stale_cover_tx.clone());
+        scrobble::spawn(self.config.clone(), self.paused.clone());
+        mpd::spawn(self.config.clone());
+
         if self.config.event_driven() {
             info!(
                 "Run mode: event-driven (D-Bus signal monitoring, fallback poll={}ms)",
                 self.config.fallback_poll_interval()
             );
-            self.run_event_driven().await
+            self.run_event_driven(&mut control_rx).await
         } else {
             info!("Run mode: polling (interval={}ms)", self.config.interval());
-            self.run_polling().await
+            self.run_polling(&mut control_rx).await
         }
     }
 
-    async fn run_polling(&mut self) -> Result<(), MprisenceError> {
+    async fn run_polling(
+        &mut self,
+        control_rx: &mut mpsc::Receiver<control::ControlCommand>,
+    ) -> Result<(), MprisenceError> {
         let mut interval = tokio::time::interval(Duration::from_millis(self.config.interval()));
         let mut cache_cleanup_interval = tokio::time::interval(Duration::from_secs(6 * 60 * 60));
+        let mut presence_tick = tokio::time::interval(PRESENCE_TICK);
+        presence_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         loop {
             tokio::select! {
@@ -543,9 +565,10 @@ impl Mprisence {
                         error!("Failed to update presence: {}", e);
                     }
                 },
+                _ = presence_tick.tick() => self.tick_presences().await,
                 _ = cache_cleanup_interval.tick() => {
                     debug!("Starting periodic cache cleanup");
-                    match cover::clean_cache().await {
+                    match self.cover_manager.clean_cache().await {
                         Ok(_) => debug!("Cache cleanup completed successfully"),
                         Err(e) => error!("Cache cleanup failed: {}", e)
                     }
@@ -568,6 +591,17 @@ impl Mprisence {
                         }
                     }
                 },
+                Some(cmd) = control_rx.recv() => {
+                    let result = self.handle_control(cmd.request).await;
+                    let _ = cmd.reply.send(result);
+                },
+                Some(url) = self.stale_cover_rx.recv() => {
+                    self.refresh_stale_cover(&url).await;
+                },
+                _ = control::hidden::wait_until(self.paused_until) => {
+                    info!("Snooze expired, resuming presence");
+                    self.resume().await;
+                },
                 else => {
                     warn!("All event sources have closed, initiating shutdown");
                     break;
@@ -578,7 +612,10 @@ impl Mprisence {
         Ok(())
     }
 
-    async fn run_event_driven(&mut self) -> Result<(), MprisenceError> {
+    async fn run_event_driven(
+        &mut self,
+        control_rx: &mut mpsc::Receiver<control::ControlCommand>,
+    ) -> Result<(), MprisenceError> {
         /// Drain the mpsc channel for TrackChanged events on the same player.
         /// When the user skips rapidly, multiple TrackChanged events queue up.
         /// Processing them all wastes cover art fetches on tracks the user has
@@ -625,6 +662,8 @@ impl Mprisence {
         let mut fallback_poll_interval =
             tokio::time::interval(Duration::from_millis(self.config.fallback_poll_interval()));
         let mut cache_cleanup_interval = tokio::time::interval(Duration::from_secs(6 * 60 * 60));
+        let mut presence_tick = tokio::time::interval(PRESENCE_TICK);
+        presence_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         // Prime once so listeners attach to whatever is already running.
         debug!("fallback poll (initial)");
@@ -661,9 +700,10 @@ impl Mprisence {
                     }
                     self.ensure_listeners(&event_tx);
                 },
+                _ = presence_tick.tick() => self.tick_presences().await,
                 _ = cache_cleanup_interval.tick() => {
                     debug!("Starting periodic cache cleanup");
-                    match cover::clean_cache().await {
+                    match self.cover_manager.clean_cache().await {
                         Ok(_) => debug!("Cache cleanup completed successfully"),
                         Err(e) => error!("Cache cleanup failed: {}", e)
                     }
@@ -688,6 +728,17 @@ impl Mprisence {
                         }
                     }
                 },
+                Some(cmd) = control_rx.recv() => {
+                    let result = self.handle_control(cmd.request).await;
+                    let _ = cmd.reply.send(result);
+                },
+                Some(url) = self.stale_cover_rx.recv() => {
+                    self.refresh_stale_cover(&url).await;
+                },
+                _ = control::hidden::wait_until(self.paused_until) => {
+                    info!("Snooze expired, resuming presence");
+                    self.resume().await;
+                },
                 else => {
                     warn!("All event sources have closed, initiating shutdown");
                     break;
@@ -698,6 +749,185 @@ impl Mprisence {
         Ok(())
     }
 
+    /// Let presences re-render between updates, e.g. for a new lyric line.
+    /// Covers still on screen are kept in cache maintenance however long
+    /// they stay there.
+    async fn tick_presences(&mut self) {
+        for (norm_id, presence) in self.media_players.iter_mut() {
+            if let Some(url) = presence.shown_cover() {
+                self.cover_manager.touch_shown(&url);
+            }
+            if let Err(e) = presence.tick().await {
+                warn!("Failed to refresh presence for {}: {}", norm_id, e);
+            }
+        }
+    }
+
+    /// Re-push presences whose cover was replaced by cache maintenance.
+    async fn refresh_stale_cover(&mut self, url: &str) {
+        if self.paused.load(Ordering::Relaxed) {
+            return;
+        }
+        for (norm_id, presence) in self.media_players.iter_mut() {
+            if presence.shows_cover(url) {
+                debug!("Cover art for {} was replaced, re-pushing", norm_id);
+                if let Err(e) = presence.refresh_cover().await {
+                    warn!("Failed to refresh cover art for {}: {}", norm_id, e);
+                }
+            }
+        }
+    }
+
+    async fn handle_control(
+        &mut self,
+        request: control::protocol::ControlRequest,
+    ) -> Result<Value, control::protocol::RpcError> {
+        use control::protocol::{
+            ControlRequest, DaemonStatus, PresenceStatus, RpcError, INTERNAL_ERROR, INVALID_PARAMS,
+            PLAYER_NOT_FOUND,
+        };
+
+        debug!("Control request: {:?}", request);
+        let internal = |e: &dyn std::fmt::Display| RpcError::new(INTERNAL_ERROR, e.to_string());
+        let not_found =
+            |player: &str| RpcError::new(PLAYER_NOT_FOUND, format!("no player '{}'", player));
+
+        match request {
+            ControlRequest::Status => {
+                let mut presences: Vec<PresenceStatus> = self
+                    .media_players
+                    .iter()
+                    .map(|(key, presence)| PresenceStatus {
+                        key: key.to_string(),
+                        identity: presence.player_id().identity.to_string(),
+                        bus_name: presence.player_id().player_bus_name.to_string(),
+                        playback_status: presence.playback_status().map(|s| format!("{:?}", s)),
+                        activity_set: presence.activity_is_set(),
+                        texts: presence.activity_texts().cloned(),
+                        arbitration: presence.standing().map(ToString::to_string),
+                    })
+                    .collect();
+                presences.sort_by(|a, b| a.key.cmp(&b.key));
+                serde_json::to_value(DaemonStatus {
+                    paused: self.paused.load(Ordering::Relaxed),
+                    paused_until: self
+                        .paused_until
+                        .and_then(|until| until.duration_since(UNIX_EPOCH).ok())
+                        .map(|since_epoch| since_epoch.as_secs()),
+                    presences,
+                })
+                .map_err(|e| internal(&e))
+            }
+            ControlRequest::Refresh { player } => {
+                let keys = match player {
+                    Some(player) => vec![self
+                        .find_presence_key(&player)
+                        .ok_or_else(|| not_found(&player))?],
+                    None => self.media_players.keys().cloned().collect(),
+                };
+                for key in &keys {
+                    if let Some(presence) = self.media_players.get_mut(key) {
+                        if let Err(e) = presence.refresh().await {
+                            warn!("Failed to refresh presence for {}: {}", key, e);
+                        }
+                    }
+                }
+                let keys: Vec<&str> = keys.iter().map(SmolStr::as_str).collect();
+                Ok(json!({ "refreshed": keys }))
+            }
+            ControlRequest::Clear { player } => {
+                let key = self
+                    .find_presence_key(&player)
+                    .ok_or_else(|| not_found(&player))?;
+                if let Some(presence) = self.media_players.get(&key) {
+                    presence
+                        .clear_activity(&format!("Clearing Discord activity for {} (ctl)", key))
+                        .map_err(|e| internal(&e))?;
+                }
+                Ok(json!({ "cleared": key.as_str() }))
+            }
+            ControlRequest::Pause { duration_secs } => {
+                self.pause(duration_secs.map(Duration::from_secs))
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                Ok(json!({ "paused": true }))
+            }
+            ControlRequest::Resume => {
+                self.resume().await;
+                Ok(json!({ "paused": false }))
+            }
+            ControlRequest::Toggle => {
+                if self.paused.load(Ordering::Relaxed) {
+                    self.resume().await;
+                } else {
+                    self.pause(None).map_err(|e| internal(&e))?;
+                }
+                Ok(json!({ "paused": self.paused.load(Ordering::Relaxed) }))
+            }
+            ControlRequest::Reload => {
+                self.config.reload().map_err(|e| internal(&e))?;
+                Ok(json!({ "reloaded": true }))
+            }
+        }
+    }
+
+    /// Hide every activity, indefinitely or for `duration`. Listeners keep
+    /// running so presences come back current on resume. A `duration` too
+    /// large to add to the clock is rejected and nothing changes.
+    fn pause(&mut self, duration: Option<Duration>) -> Result<(), control::ControlError> {
+        self.paused_until = duration
+            .map(|duration| control::hidden::snooze_deadline(SystemTime::now(), duration))
+            .transpose()?;
+        let was_paused = self.paused.swap(true, Ordering::Relaxed);
+        match duration {
+            Some(duration) => info!(
+                "Presence hidden for {}",
+                utils::format_duration(duration.as_secs())
+            ),
+            None if !was_paused => info!("Presence hidden"),
+            None => {}
+        }
+        for (key, presence) in &self.media_players {
+            if let Err(e) = presence
+                .hold_back_activity(&format!("Clearing Discord activity for {} (hidden)", key))
+            {
+                warn!("Failed to clear presence for {}: {}", key, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn resume(&mut self) {
+        self.paused_until = None;
+        control::hidden::remove_flag();
+        if !self.paused.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        info!("Presence visible again");
+        for (key, presence) in self.media_players.iter_mut() {
+            if let Err(e) = presence.refresh().await {
+                warn!("Failed to refresh presence for {}: {}", key, e);
+            }
+        }
+    }
+
+    /// Resolve a `ctl` player argument to a registry key: exact key, then
+    /// normalized identity, then bus name.
+    fn find_presence_key(&self, player: &str) -> Option<SmolStr> {
+        if self.media_players.contains_key(player) {
+            return Some(SmolStr::new(player));
+        }
+        let normalized = utils::normalize_player_identity(player);
+        self.media_players
+            .iter()
+            .find(|(_, presence)| {
+                let id = presence.player_id();
+                utils::normalize_player_identity(&id.identity) == normalized
+                    || id.player_bus_name.eq_ignore_ascii_case(player)
+                    || canonical_player_bus_name(&id.player_bus_name).eq_ignore_ascii_case(player)
+            })
+            .map(|(key, _)| key.clone())
+    }
+
     fn ensure_listeners(&mut self, tx: &mpsc::Sender<PlayerEvent>) {
         for (norm_id, presence) in self.media_players.iter_mut() {
             presence.ensure_listener(tx.clone(), norm_id.clone());
@@ -743,5 +973,7 @@ impl Mprisence {
             // if the player genuinely restarted it will be picked up cleanly.
             self.ensure_listeners(tx);
         }
+        // A status change or a removal can hand the activity to another player.
+        self.arbitrate().await;
     }
 }
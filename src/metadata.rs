@@ -1,6 +1,11 @@
 use std::time::Duration;
 
+use crate::config::schema::{
+    FilterAction, FilterRule, FilterSubject, PathMap, PathRewrite, PlayerConfig, RedactField,
+};
+use crate::config::ConfigManager;
 use crate::cover::sources::ArtSource;
+use crate::lyrics::Lyrics;
 use crate::utils::{
     format_audio_channels, format_bit_depth, format_bitrate, format_duration, format_sample_rate,
     format_track_number,
@@ -11,7 +16,7 @@ use lofty::{
     prelude::*,
     properties::FileProperties,
 };
-use log::{trace, warn};
+use log::{debug, trace, warn};
 use mpris::Metadata;
 use serde::Serialize;
 use url::Url;
@@ -183,10 +188,155 @@ pub struct MediaMetadata {
     pub movement_display: Option<String>, // "1/3" format like track_display
 }
 
+impl MediaMetadata {
+    /// The values `[[filter]]` rules match against. `url` is passed in since
+    /// the resolved track URL may differ from `xesam:url` (e.g. cmus).
+    pub fn filter_subject<'a>(&'a self, url: Option<&'a str>) -> FilterSubject<'a> {
+        FilterSubject {
+            title: self.title.as_deref(),
+            artists: &self.artists,
+            album: self.album.as_deref(),
+            album_artists: &self.album_artists,
+            genres: &self.genres,
+            url,
+        }
+    }
+
+    /// Overwrite `fields` with `replacement` (URLs are dropped instead) so
+    /// templates never see the original values. Redacting the title, artist
+    /// or album also drops every tag that would identify the track anyway.
+    pub fn redact(&mut self, fields: &[RedactField], replacement: &str) {
+        for field in fields {
+            match field {
+                RedactField::Title => {
+                    self.title = Some(replacement.to_string());
+                    self.clear_identifiers();
+                }
+                RedactField::Artist => {
+                    self.artists = vec![replacement.to_string()];
+                    self.artist_display = Some(replacement.to_string());
+                    self.clear_identifiers();
+                }
+                RedactField::Album => {
+                    self.album = Some(replacement.to_string());
+                    self.clear_identifiers();
+                }
+                RedactField::AlbumArtist => {
+                    self.album_artists = vec![replacement.to_string()];
+                    self.album_artist_display = Some(replacement.to_string());
+                    self.musicbrainz_album_artist_id = None;
+                }
+                RedactField::Genre => {
+                    self.genres = vec![replacement.to_string()];
+                    self.genre_display = Some(replacement.to_string());
+                }
+                RedactField::Url => self.url = None,
+                RedactField::Cover => {}
+            }
+        }
+    }
+
+    /// IDs, codes, credits and the position on the release: enough to look
+    /// the track up even with its title, artist and album replaced.
+    fn clear_identifiers(&mut self) {
+        self.musicbrainz_track_id = None;
+        self.musicbrainz_album_id = None;
+        self.musicbrainz_artist_id = None;
+        self.musicbrainz_album_artist_id = None;
+        self.musicbrainz_release_group_id = None;
+        self.isrc = None;
+        self.barcode = None;
+        self.catalog_number = None;
+        self.label = None;
+        self.publisher = None;
+        self.copyright = None;
+        self.composer = None;
+        self.lyricist = None;
+        self.conductor = None;
+        self.remixer = None;
+        self.comment = None;
+        self.track_number = None;
+        self.track_total = None;
+        self.track_display = None;
+        self.disc_number = None;
+        self.disc_total = None;
+        self.disc_display = None;
+        self.movement = None;
+        self.movement_number = None;
+        self.movement_total = None;
+        self.movement_display = None;
+    }
+
+    /// Apply the `[[filter]]` rule matching this track, if any: `redact`
+    /// overwrites fields here and `template` layers the rule's templates onto
+    /// `player_config`. The rule is returned so callers can act on `hide`
+    /// and cover redaction. Shared by the Discord activity and the bar.
+    pub fn apply_filter(
+        &mut self,
+        config: &ConfigManager,
+        url: Option<&str>,
+        player_config: &mut PlayerConfig,
+    ) -> Option<FilterRule> {
+        let rule = config.match_filter(&self.filter_subject(url))?;
+        match rule.action {
+            FilterAction::Hide => {}
+            FilterAction::Redact => {
+                debug!("Redacting track fields ([[filter]] rule)");
+                self.redact(rule.redact_fields(), rule.replacement());
+            }
+            FilterAction::Template => {
+                debug!("Using [[filter]] template");
+                rule.apply_template(player_config);
+            }
+        }
+        Some(rule)
+    }
+
+    /// Read the track from `source` as Discord shows it, with the player
+    /// config that applies to it. A title suffix that picked the config
+    /// (e.g. " | YouTube Music") is stripped, so the title and the
+    /// `[[filter]]` rules matched against it see only the track name.
+    /// Shared by the Discord activity, the bar and the scrobbler.
+    pub fn resolve(
+        source: &MetadataSource,
+        config: &ConfigManager,
+        identity: &str,
+        player_bus_name: &str,
+    ) -> ResolvedTrack {
+        let mut metadata = source.to_media_metadata();
+        let url = source.url();
+        let (player_config, title_suffix) = config.get_player_config_with_title_fallback(
+            identity,
+            player_bus_name,
+            url.as_deref(),
+            metadata.title.as_deref(),
+        );
+        if let (Some(suffix), Some(title)) = (title_suffix, metadata.title.as_mut()) {
+            if let Some(stripped) = title.strip_suffix(suffix.as_str()) {
+                *title = stripped.trim_end().to_string();
+            }
+        }
+        ResolvedTrack {
+            metadata,
+            url,
+            player_config,
+        }
+    }
+}
+
+/// A track read by [`MediaMetadata::resolve`].
+pub struct ResolvedTrack {
+    pub metadata: MediaMetadata,
+    pub url: Option<String>,
+    pub player_config: PlayerConfig,
+}
+
 pub struct MetadataSource {
     mpris_metadata: Option<Metadata>,
     tagged_file: Option<TaggedFile>,
     override_url: Option<String>,
+    /// `[[path_map]]` rule applied to the track URL, if any.
+    path_rewrite: Option<PathRewrite>,
     /// Memoized cover-cache key. Computed once via `generate_cache_key()`
     /// and reused across fast-path and slow-path lookups on the same track.
     cache_key: std::sync::OnceLock<String>,
@@ -198,20 +348,42 @@ impl MetadataSource {
             mpris_metadata,
             tagged_file: lofty_tagged_file,
             override_url: None,
+            path_rewrite: None,
             cache_key: std::sync::OnceLock::new(),
         }
     }
 
+    /// Without `[[path_map]]`; used by the integration test crate.
+    #[allow(dead_code)]
     pub fn from_mpris_with_override(metadata: Metadata, override_url: Option<String>) -> Self {
-        let override_tagged_file = override_url
-            .as_ref()
-            .and_then(|url| Self::lofty_tag_from_url(url).ok());
-        let tagged_file = metadata
-            .url()
-            .and_then(|url| Self::lofty_tag_from_url(url).ok());
-        let tagged_file = override_tagged_file.or(tagged_file);
-        let mut source = Self::new(Some(metadata), tagged_file);
+        Self::from_mpris_mapped(metadata, override_url, &PathMap::default())
+    }
+
+    /// Build from MPRIS metadata. `override_url` replaces `xesam:url` (e.g.
+    /// from a file resolver), then `path_map` rewrites the result before
+    /// tags are read from the file.
+    pub fn from_mpris_mapped(
+        metadata: Metadata,
+        override_url: Option<String>,
+        path_map: &PathMap,
+    ) -> Self {
+        let player_url = metadata.url().map(String::from);
+        let mut source = Self::new(Some(metadata), None);
         source.override_url = override_url;
+        source.path_rewrite = source.url().and_then(|url| path_map.rewrite(&url));
+        if let Some(rewrite) = &source.path_rewrite {
+            debug!("Path map: {}", rewrite);
+        }
+
+        let url = source.url();
+        source.tagged_file = url
+            .as_ref()
+            .and_then(|url| Self::lofty_tag_from_url(url).ok())
+            .or_else(|| {
+                player_url
+                    .filter(|player_url| Some(player_url) != url.as_ref())
+                    .and_then(|player_url| Self::lofty_tag_from_url(player_url).ok())
+            });
         source
     }
 
@@ -379,6 +551,17 @@ impl MetadataSource {
         select_art_source(art_url, embedded)
     }
 
+    /// Synced lyrics for the track, from an `.lrc` file next to it or its
+    /// tags.
+    pub fn lyrics(&self) -> Option<Lyrics> {
+        let path = self
+            .url()
+            .and_then(|url| Url::parse(&url).ok())
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok());
+        Lyrics::load(path.as_deref(), self.tagged_file.as_ref())
+    }
+
     pub fn mpris_metadata(&self) -> Option<&Metadata> {
         self.mpris_metadata.as_ref()
     }
@@ -390,6 +573,9 @@ impl MetadataSource {
     }
 
     pub fn url(&self) -> Option<String> {
+        if let Some(rewrite) = &self.path_rewrite {
+            return Some(rewrite.to.clone());
+        }
         self.override_url
             .as_ref()
             .filter(|url| !url.is_empty())
@@ -626,12 +812,94 @@ fn select_art_source(art_url: Option<&str>, embedded_bytes: Option<Vec<u8>>) ->
 
 #[cfg(test)]
 mod tests {
-    use super::select_art_source;
+    use super::{select_art_source, MediaMetadata};
+    use crate::config::schema::RedactField;
     use crate::cover::sources::ArtSource;
     use std::path::PathBuf;
 
     const PLASMA_FILE: &str = "file:///tmp/plasma-browser-integration_artwork_zmXyTR.jpg";
 
+    #[test]
+    fn redact_replaces_chosen_fields_only() {
+        let mut metadata = MediaMetadata {
+            title: Some("Real Title".to_string()),
+            artists: vec!["A".to_string(), "B".to_string()],
+            artist_display: Some("A, B".to_string()),
+            album: Some("Real Album".to_string()),
+            url: Some("https://example.com/private".to_string()),
+            ..Default::default()
+        };
+
+        metadata.redact(
+            &[RedactField::Title, RedactField::Artist, RedactField::Url],
+            "Private",
+        );
+
+        assert_eq!(metadata.title.as_deref(), Some("Private"));
+        assert_eq!(metadata.artists, vec!["Private".to_string()]);
+        assert_eq!(metadata.artist_display.as_deref(), Some("Private"));
+        assert_eq!(metadata.album.as_deref(), Some("Real Album"));
+        assert_eq!(metadata.url, None);
+    }
+
+    #[test]
+    fn apply_filter_redacts_or_swaps_templates() {
+        use crate::config::schema::{
+            Config, FilterAction, FilterRule, PlayerConfig, TemplateConfigLayer,
+        };
+        use crate::config::ConfigManager;
+
+        let config = ConfigManager::new_with_config(Config {
+            filter: vec![
+                FilterRule {
+                    title: Some("Hidden".into()),
+                    ..Default::default()
+                },
+                FilterRule {
+                    title: Some("Private".into()),
+                    action: FilterAction::Redact,
+                    redact: vec![RedactField::Title],
+                    ..Default::default()
+                },
+                FilterRule {
+                    title: Some("Lo-fi".into()),
+                    action: FilterAction::Template,
+                    template: Some(TemplateConfigLayer {
+                        details: Some("Lo-fi beats".into()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        let track = |title: &str| MediaMetadata {
+            title: Some(title.to_string()),
+            ..Default::default()
+        };
+
+        let mut player_config = PlayerConfig::default();
+        let mut hidden = track("Hidden");
+        let rule = hidden.apply_filter(&config, None, &mut player_config);
+        assert_eq!(rule.map(|rule| rule.action), Some(FilterAction::Hide));
+
+        let mut private = track("Private");
+        private.apply_filter(&config, None, &mut player_config);
+        assert_eq!(private.title.as_deref(), Some("Private"));
+        assert!(player_config.template.is_none());
+
+        let mut lofi = track("Lo-fi");
+        lofi.apply_filter(&config, None, &mut player_config);
+        assert_eq!(lofi.title.as_deref(), Some("Lo-fi"));
+        let details = player_config.template.and_then(|template| template.details);
+        assert_eq!(details.as_deref(), Some("Lo-fi beats"));
+
+        let mut other = track("Other");
+        assert!(other
+            .apply_filter(&config, None, &mut PlayerConfig::default())
+            .is_none());
+    }
+
     #[test]
     fn remote_http_art_url_wins() {
         let curated = "https://cdn.example.com/cover.png";
@@ -678,4 +946,32 @@ mod tests {
     fn all_inputs_empty_returns_none() {
         assert!(select_art_source(None, None).is_none());
     }
+
+    #[test]
+    fn resolve_strips_the_title_suffix_that_picked_the_config() {
+        use crate::config::schema::{Config, WebPlayerConfigLayer};
+        use crate::config::ConfigManager;
+        use crate::cover::providers::tests::metadata;
+
+        let mut config = Config::default();
+        config.user_web_player.insert(
+            "example".to_string(),
+            WebPlayerConfigLayer {
+                title_suffix: Some(" | Example".to_string()),
+                app_id: Some("123456789012345678".to_string()),
+                ..Default::default()
+            },
+        );
+        config.rebuild_merged_web_player();
+        let config = ConfigManager::new_with_config(config);
+
+        let source = metadata(&[("xesam:title", "Song | Example")]);
+        let resolved = MediaMetadata::resolve(&source, &config, "Firefox", "firefox");
+        assert_eq!(resolved.metadata.title.as_deref(), Some("Song"));
+        assert_eq!(resolved.player_config.app_id, "123456789012345678");
+
+        let source = metadata(&[("xesam:title", "Song | Elsewhere")]);
+        let resolved = MediaMetadata::resolve(&source, &config, "Firefox", "firefox");
+        assert_eq!(resolved.metadata.title.as_deref(), Some("Song | Elsewhere"));
+    }
 }
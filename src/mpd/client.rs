@@ -0,0 +1,376 @@
+//! Minimal async client for the MPD protocol: the few commands the backend
+//! needs, over TCP or a Unix socket.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use log::trace;
+use mpris::PlaybackStatus;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufStream},
+    net::{TcpStream, UnixStream},
+};
+
+use super::error::MpdError;
+use crate::utils::expand_home;
+
+pub const DEFAULT_PORT: u16 = 6600;
+
+/// Where MPD listens, from the `address` setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    /// `host:port`; the port defaults to 6600.
+    Tcp(String),
+    /// An absolute or `~/` path to MPD's socket.
+    Unix(PathBuf),
+}
+
+impl Address {
+    pub fn parse(address: &str) -> Self {
+        let address = address.trim();
+        if address.starts_with('/') || address.starts_with('~') {
+            return Address::Unix(expand_home(address));
+        }
+        let has_port = address
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+        if has_port {
+            Address::Tcp(address.to_string())
+        } else {
+            Address::Tcp(format!("{address}:{DEFAULT_PORT}"))
+        }
+    }
+}
+
+/// The parts of `status` the backend uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    pub state: PlaybackStatus,
+    pub song_id: Option<u32>,
+    /// 0-100; `None` when MPD has no mixer.
+    pub volume: Option<u8>,
+    pub elapsed: Option<Duration>,
+    pub duration: Option<Duration>,
+}
+
+/// The parts of `currentsong` the backend uses. Tags MPD reports more than
+/// once (e.g. several `Artist` lines) are kept in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Song {
+    /// Path relative to `music_directory`, or a stream URL.
+    pub file: String,
+    pub id: Option<u32>,
+    pub title: Option<String>,
+    /// Stream or station name, sent by radio streams instead of a title.
+    pub name: Option<String>,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    pub album_artists: Vec<String>,
+    pub track_number: Option<i32>,
+    pub disc_number: Option<i32>,
+    pub duration: Option<Duration>,
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+pub struct MpdClient {
+    stream: BufStream<Box<dyn Connection>>,
+    version: String,
+}
+
+impl MpdClient {
+    pub async fn connect(address: &Address) -> Result<Self, MpdError> {
+        let stream: Box<dyn Connection> = match address {
+            Address::Tcp(address) => Box::new(TcpStream::connect(address).await?),
+            Address::Unix(path) => Box::new(UnixStream::connect(path).await?),
+        };
+        let mut stream = BufStream::new(stream);
+
+        let greeting = read_line(&mut stream).await?;
+        let version = greeting
+            .strip_prefix("OK MPD ")
+            .ok_or_else(|| MpdError::Handshake(greeting.clone()))?
+            .to_string();
+        Ok(Self { stream, version })
+    }
+
+    /// Protocol version from the greeting.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Send one command line and collect the `key: value` pairs of the
+    /// response.
+    pub async fn command(&mut self, command: &str) -> Result<Vec<(String, String)>, MpdError> {
+        trace!("MPD <- {}", command);
+        self.stream.write_all(command.as_bytes()).await?;
+        self.stream.write_all(b"\n").await?;
+        self.stream.flush().await?;
+
+        let mut pairs = Vec::new();
+        loop {
+            let line = read_line(&mut self.stream).await?;
+            if line == "OK" {
+                return Ok(pairs);
+            }
+            if let Some(message) = line.strip_prefix("ACK ") {
+                return Err(MpdError::Ack(message.to_string()));
+            }
+            match line.split_once(": ") {
+                Some((key, value)) => pairs.push((key.to_string(), value.to_string())),
+                None => return Err(MpdError::Protocol(line)),
+            }
+        }
+    }
+
+    pub async fn password(&mut self, password: &str) -> Result<(), MpdError> {
+        self.command(&format!("password {}", quote(password)))
+            .await
+            .map(drop)
+    }
+
+    pub async fn status(&mut self) -> Result<Status, MpdError> {
+        Ok(parse_status(&self.command("status").await?))
+    }
+
+    /// `None` when the queue is empty or playback is stopped at its end.
+    pub async fn current_song(&mut self) -> Result<Option<Song>, MpdError> {
+        Ok(parse_song(&self.command("currentsong").await?))
+    }
+
+    /// Block until one of `subsystems` changes; returns the changed ones.
+    pub async fn idle(&mut self, subsystems: &[&str]) -> Result<Vec<String>, MpdError> {
+        let command = std::iter::once("idle")
+            .chain(subsystems.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(self
+            .command(&command)
+            .await?
+            .into_iter()
+            .filter(|(key, _)| key == "changed")
+            .map(|(_, value)| value)
+            .collect())
+    }
+
+    /// MPD's `music_directory`. MPD only answers `config` on Unix socket
+    /// connections and replies with an [`MpdError::Ack`] otherwise.
+    pub async fn music_directory(&mut self) -> Result<Option<PathBuf>, MpdError> {
+        Ok(self
+            .command("config")
+            .await?
+            .into_iter()
+            .find(|(key, _)| key == "music_directory")
+            .map(|(_, value)| PathBuf::from(value)))
+    }
+}
+
+async fn read_line(stream: &mut BufStream<Box<dyn Connection>>) -> Result<String, MpdError> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Err(MpdError::Closed);
+    }
+    let line = line.trim_end_matches(['\n', '\r']).to_string();
+    trace!("MPD -> {}", line);
+    Ok(line)
+}
+
+/// Quote a command argument.
+fn quote(argument: &str) -> String {
+    format!(
+        "\"{}\"",
+        argument.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+fn seconds(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+/// `3/12` and `3` both give 3.
+fn number(value: &str) -> Option<i32> {
+    value.split('/').next()?.trim().parse().ok()
+}
+
+fn parse_status(pairs: &[(String, String)]) -> Status {
+    let mut status = Status {
+        state: PlaybackStatus::Stopped,
+        song_id: None,
+        volume: None,
+        elapsed: None,
+        duration: None,
+    };
+    for (key, value) in pairs {
+        match key.as_str() {
+            "state" => {
+                status.state = match value.as_str() {
+                    "play" => PlaybackStatus::Playing,
+                    "pause" => PlaybackStatus::Paused,
+                    _ => PlaybackStatus::Stopped,
+                }
+            }
+            "songid" => status.song_id = value.parse().ok(),
+            // `-1` without a mixer.
+            "volume" => status.volume = value.parse().ok(),
+            "elapsed" => status.elapsed = seconds(value),
+            "duration" => status.duration = seconds(value),
+            _ => {}
+        }
+    }
+    status
+}
+
+fn parse_song(pairs: &[(String, String)]) -> Option<Song> {
+    let mut song = Song::default();
+    // Older servers only send the whole-second `Time`.
+    let mut time = None;
+    for (key, value) in pairs {
+        match key.as_str() {
+            "file" => song.file = value.clone(),
+            "Id" => song.id = value.parse().ok(),
+            "Title" => song.title = Some(value.clone()),
+            "Name" => song.name = Some(value.clone()),
+            "Artist" => song.artists.push(value.clone()),
+            "Album" => song.album = Some(value.clone()),
+            "AlbumArtist" => song.album_artists.push(value.clone()),
+            "Track" => song.track_number = number(value),
+            "Disc" => song.disc_number = number(value),
+            "duration" => song.duration = seconds(value),
+            "Time" => time = seconds(value),
+            _ => {}
+        }
+    }
+    song.duration = song.duration.or(time);
+    (!song.file.is_empty()).then_some(song)
+}
+
+/// Resolve a song's `file` to a URL: streams pass through, library files
+/// become `file://` URLs under `music_directory` when it is known.
+pub fn song_url(file: &str, music_directory: Option<&Path>) -> Option<String> {
+    if file.contains("://") {
+        return Some(file.to_string());
+    }
+    let path = Path::new(file);
+    let path = if path.is_absolute() {
+        // Files played by absolute path, outside the library.
+        path.to_path_buf()
+    } else {
+        music_directory?.join(path)
+    };
+    url::Url::from_file_path(path).ok().map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fake_mpd;
+
+    #[tokio::test]
+    async fn reads_status_song_and_idle_events_over_tcp() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            fake_mpd(
+                stream,
+                vec![
+                    (r#"password "p\"w""#, "OK\n"),
+                    (
+                        "status",
+                        "volume: 80\nstate: play\nsongid: 7\nelapsed: 12.500\nduration: 245.000\nOK\n",
+                    ),
+                    (
+                        "currentsong",
+                        "file: Daft Punk/RAM/08 Get Lucky.flac\nArtist: Daft Punk\n\
+                         Artist: Pharrell Williams\nTitle: Get Lucky\nAlbum: Random Access Memories\n\
+                         Track: 8/13\nTime: 248\nduration: 248.041\nId: 7\nOK\n",
+                    ),
+                    ("idle player", "changed: player\nOK\n"),
+                    ("config", "ACK [4@0] {config} Permission denied\n"),
+                ],
+            )
+            .await;
+        });
+
+        let mut client = MpdClient::connect(&Address::parse(&format!("127.0.0.1:{port}")))
+            .await
+            .unwrap();
+        assert_eq!(client.version(), "0.23.5");
+        client.password("p\"w").await.unwrap();
+
+        let status = client.status().await.unwrap();
+        assert_eq!(status.state, PlaybackStatus::Playing);
+        assert_eq!(status.song_id, Some(7));
+        assert_eq!(status.volume, Some(80));
+        assert_eq!(status.elapsed, Some(Duration::from_millis(12_500)));
+
+        let song = client.current_song().await.unwrap().unwrap();
+        assert_eq!(song.title.as_deref(), Some("Get Lucky"));
+        assert_eq!(song.artists, ["Daft Punk", "Pharrell Williams"]);
+        assert_eq!(song.track_number, Some(8));
+        assert_eq!(song.duration, Some(Duration::from_millis(248_041)));
+
+        assert_eq!(client.idle(&["player"]).await.unwrap(), ["player"]);
+        assert!(matches!(
+            client.music_directory().await,
+            Err(MpdError::Ack(message)) if message.contains("Permission denied")
+        ));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_music_directory_over_unix_socket() {
+        let dir = std::env::temp_dir().join(format!("mprisence-mpd-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("socket");
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            fake_mpd(
+                stream,
+                vec![
+                    ("config", "music_directory: /srv/music\nOK\n"),
+                    ("currentsong", "OK\n"),
+                ],
+            )
+            .await;
+        });
+
+        let address = Address::parse(socket.to_str().unwrap());
+        assert_eq!(address, Address::Unix(socket.clone()));
+        let mut client = MpdClient::connect(&address).await.unwrap();
+        assert_eq!(
+            client.music_directory().await.unwrap(),
+            Some(PathBuf::from("/srv/music"))
+        );
+        assert_eq!(client.current_song().await.unwrap(), None);
+        server.await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resolves_song_urls() {
+        let music = Path::new("/srv/music");
+        assert_eq!(
+            song_url("Daft Punk/RAM/08 Get Lucky.flac", Some(music)).as_deref(),
+            Some("file:///srv/music/Daft%20Punk/RAM/08%20Get%20Lucky.flac")
+        );
+        assert_eq!(song_url("Daft Punk/RAM/08 Get Lucky.flac", None), None);
+        assert_eq!(
+            song_url("http://radio.example/stream", Some(music)).as_deref(),
+            Some("http://radio.example/stream")
+        );
+        assert_eq!(
+            Address::parse("music-box"),
+            Address::Tcp("music-box:6600".into())
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MpdError {
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    /// The server did not greet with `OK MPD <version>`.
+    #[error("Not an MPD server: {0}")]
+    Handshake(String),
+
+    /// MPD rejected a command, e.g. `[4@0] {config} Permission denied`.
+    #[error("MPD error: {0}")]
+    Ack(String),
+
+    #[error("Unexpected response line: {0}")]
+    Protocol(String),
+
+    #[error("Connection closed by MPD")]
+    Closed,
+}
@@ -0,0 +1,549 @@
+//! Native MPD backend. Talks to MPD over its own protocol, waits for changes
+//! with `idle` and keeps the latest state in an [`MpdPlayer`]. Discovery
+//! hands that player out next to the MPRIS ones, so presence, templates,
+//! covers, `mprisence bar` and the scrobbler read MPD without D-Bus. This
+//! covers setups without mpDris2 or with a broken MPRIS bridge; nothing is
+//! published on the session bus.
+//!
+//! Library files are reported as `file://` URLs under MPD's
+//! `music_directory`, which lets tag reading and local cover search work
+//! the same as for players that expose file URLs themselves.
+
+pub mod client;
+pub mod error;
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, trace, warn};
+use mpris::{DBusError, Metadata, MetadataValue, PlaybackStatus};
+use parking_lot::Mutex;
+use tokio::sync::broadcast;
+
+use self::client::{song_url, Address, MpdClient, Song, Status};
+use self::error::MpdError;
+use crate::config::{schema::MpdConfig, ConfigChange, ConfigManager};
+use crate::utils::expand_home;
+
+/// Stands in for the bus name. A trailing `instance` segment is the MPRIS
+/// convention for another instance of a player; discovery strips it, so
+/// `[player.mpd]` applies and an mpDris2 player for the same MPD is
+/// deduplicated against this one.
+const BUS_NAME: &str = "mpd.instance_mprisence";
+const UNIQUE_NAME: &str = "mprisence:mpd";
+const IDENTITY: &str = "Music Player Daemon";
+
+const IDLE_SUBSYSTEMS: &[&str] = &["player", "mixer"];
+
+/// A position this far from the extrapolated one is reported as a seek.
+const SEEK_THRESHOLD: Duration = Duration::from_secs(2);
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(2);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// The backend's player, shared by everything in the process that lists
+/// players.
+static PLAYER: OnceLock<MpdPlayer> = OnceLock::new();
+
+fn shared_player() -> &'static MpdPlayer {
+    PLAYER.get_or_init(MpdPlayer::new)
+}
+
+/// The MPD player while the backend is connected, `None` otherwise.
+pub fn player() -> Option<MpdPlayer> {
+    let player = shared_player();
+    player.is_connected().then(|| player.clone())
+}
+
+/// Start the backend on the current runtime. Follows config reloads:
+/// enabling, disabling or changing `[mpd]` takes effect without a restart.
+pub fn spawn(config: Arc<ConfigManager>) {
+    tokio::spawn(run(config));
+}
+
+/// A change worth telling listeners about, like the MPRIS signals a player
+/// would emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MpdEvent {
+    TrackChanged,
+    Playing,
+    Paused,
+    Stopped,
+    Seeked(Duration),
+    /// The connection is gone; the player is no longer listed.
+    Disconnected,
+}
+
+/// MPD as a player: the last `status` and `currentsong`, read the way an
+/// MPRIS player is. Cheap to clone; clones share the state.
+#[derive(Clone)]
+pub struct MpdPlayer {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<Option<Snapshot>>,
+    events: broadcast::Sender<MpdEvent>,
+}
+
+struct Snapshot {
+    status: Status,
+    song: Option<Song>,
+    music_directory: Option<PathBuf>,
+    sampled_at: Instant,
+}
+
+impl Snapshot {
+    /// Where playback should be now, extrapolated from the last sample.
+    fn position(&self) -> Option<Duration> {
+        let mut position = self.status.elapsed?;
+        if self.status.state == PlaybackStatus::Playing {
+            position += self.sampled_at.elapsed();
+        }
+        Some(self.status.duration.map_or(position, |d| position.min(d)))
+    }
+}
+
+impl MpdPlayer {
+    fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(None),
+                events: broadcast::channel(16).0,
+            }),
+        }
+    }
+
+    pub fn identity(&self) -> &str {
+        IDENTITY
+    }
+
+    pub fn bus_name(&self) -> &str {
+        BUS_NAME
+    }
+
+    pub fn unique_name(&self) -> &str {
+        UNIQUE_NAME
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.shared.state.lock().is_some()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MpdEvent> {
+        self.shared.events.subscribe()
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&Snapshot) -> Option<T>) -> Result<T, DBusError> {
+        let state = self.shared.state.lock();
+        let snapshot = state
+            .as_ref()
+            .ok_or_else(|| DBusError::Miscellaneous("not connected to MPD".to_string()))?;
+        f(snapshot).ok_or_else(|| DBusError::Miscellaneous("not reported by MPD".to_string()))
+    }
+
+    pub fn get_playback_status(&self) -> Result<PlaybackStatus, DBusError> {
+        self.read(|snapshot| Some(snapshot.status.state))
+    }
+
+    pub fn get_metadata(&self) -> Result<Metadata, DBusError> {
+        self.read(|snapshot| {
+            Some(
+                snapshot
+                    .song
+                    .as_ref()
+                    .map(|song| build_metadata(song, snapshot.music_directory.as_deref()))
+                    .unwrap_or_default(),
+            )
+        })
+    }
+
+    pub fn get_position(&self) -> Result<Duration, DBusError> {
+        self.read(Snapshot::position)
+    }
+
+    /// Volume from 0.0 to 1.0. MPD without a mixer has none.
+    pub fn get_volume(&self) -> Result<f64, DBusError> {
+        self.read(|snapshot| snapshot.status.volume)
+            .map(|volume| f64::from(volume) / 100.0)
+    }
+
+    /// Record a new sample and tell listeners what changed.
+    fn update(&self, status: Status, song: Option<Song>, music_directory: Option<&Path>) {
+        let mut events = Vec::new();
+        {
+            let mut state = self.shared.state.lock();
+            let previous = state.as_ref();
+            let same_song = previous.is_some_and(|previous| previous.song == song);
+            if !same_song && song.is_some() {
+                events.push(MpdEvent::TrackChanged);
+            }
+            if previous.map(|previous| previous.status.state) != Some(status.state) {
+                events.push(match status.state {
+                    PlaybackStatus::Playing => MpdEvent::Playing,
+                    PlaybackStatus::Paused => MpdEvent::Paused,
+                    PlaybackStatus::Stopped => MpdEvent::Stopped,
+                });
+            }
+            let position = status.elapsed.unwrap_or_default();
+            let seeked = same_song
+                && song.is_some()
+                && previous
+                    .and_then(Snapshot::position)
+                    .is_some_and(|expected| expected.abs_diff(position) > SEEK_THRESHOLD);
+            if seeked {
+                events.push(MpdEvent::Seeked(position));
+            }
+            *state = Some(Snapshot {
+                status,
+                song,
+                music_directory: music_directory.map(Path::to_path_buf),
+                sampled_at: Instant::now(),
+            });
+        }
+        for event in events {
+            // Nobody listening is fine: readers poll the state instead.
+            let _ = self.shared.events.send(event);
+        }
+    }
+
+    fn disconnect(&self) {
+        if self.shared.state.lock().take().is_some() {
+            let _ = self.shared.events.send(MpdEvent::Disconnected);
+        }
+    }
+}
+
+async fn run(config: Arc<ConfigManager>) {
+    let player = shared_player();
+    let mut changes = config.subscribe();
+    let mut retry_delay = INITIAL_RETRY_DELAY;
+    let mut failures = 0u32;
+
+    loop {
+        let mpd_config = config.mpd_config();
+        if mpd_config.enabled {
+            match connect(&mpd_config).await {
+                Ok(session) => {
+                    info!(
+                        "Connected to MPD {} at {}",
+                        session.client.version(),
+                        mpd_config.address
+                    );
+                    retry_delay = INITIAL_RETRY_DELAY;
+                    failures = 0;
+                    let reconfigured = reconfigured(&config, &mpd_config, &mut changes);
+                    let served = session.serve(player, reconfigured).await;
+                    player.disconnect();
+                    match served {
+                        // `[mpd]` changed: reconnect with the new settings.
+                        Ok(()) => continue,
+                        Err(e) => warn!("Lost connection to MPD: {}", e),
+                    }
+                }
+                Err(e) => {
+                    // MPD being down is routine; only the first failure is loud.
+                    if failures == 0 {
+                        warn!("Failed to connect to MPD at {}: {}", mpd_config.address, e);
+                    } else {
+                        debug!("Failed to connect to MPD at {}: {}", mpd_config.address, e);
+                    }
+                    failures += 1;
+                }
+            }
+        }
+
+        let retry = async {
+            if mpd_config.enabled {
+                tokio::time::sleep(retry_delay).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        };
+        tokio::select! {
+            _ = retry => {
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+            },
+            change = changes.recv() => match change {
+                Ok(ConfigChange::Reloaded) => retry_delay = INITIAL_RETRY_DELAY,
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+        }
+    }
+}
+
+/// Resolves once a reload changes `[mpd]` away from `current`.
+async fn reconfigured(
+    config: &ConfigManager,
+    current: &MpdConfig,
+    changes: &mut broadcast::Receiver<ConfigChange>,
+) {
+    loop {
+        match changes.recv().await {
+            Ok(ConfigChange::Reloaded) if config.mpd_config() != *current => return,
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => std::future::pending::<()>().await,
+        }
+    }
+}
+
+struct Session {
+    client: MpdClient,
+    music_directory: Option<PathBuf>,
+}
+
+async fn connect(config: &MpdConfig) -> Result<Session, MpdError> {
+    let mut client = MpdClient::connect(&Address::parse(&config.address)).await?;
+    if let Some(password) = config.password.as_deref().filter(|p| !p.is_empty()) {
+        client.password(password).await?;
+    }
+
+    let music_directory = match &config.music_directory {
+        Some(dir) => Some(expand_home(&dir.to_string_lossy())),
+        None => match client.music_directory().await {
+            Ok(dir) => dir,
+            Err(MpdError::Ack(message)) => {
+                debug!(
+                    "MPD did not share its music_directory ({}); set [mpd] music_directory to resolve local files",
+                    message
+                );
+                None
+            }
+            Err(e) => return Err(e),
+        },
+    };
+    debug!("MPD music directory: {:?}", music_directory);
+
+    Ok(Session {
+        client,
+        music_directory,
+    })
+}
+
+impl Session {
+    /// Keep `player` current until the connection fails (`Err`) or
+    /// `stop` resolves (`Ok`).
+    async fn serve(
+        mut self,
+        player: &MpdPlayer,
+        stop: impl Future<Output = ()>,
+    ) -> Result<(), MpdError> {
+        tokio::pin!(stop);
+        loop {
+            let status = self.client.status().await?;
+            let song = self.client.current_song().await?;
+            player.update(status, song, self.music_directory.as_deref());
+
+            tokio::select! {
+                changed = self.client.idle(IDLE_SUBSYSTEMS) => {
+                    trace!("MPD changed: {:?}", changed?);
+                },
+                _ = &mut stop => return Ok(()),
+            }
+        }
+    }
+}
+
+/// MPRIS-style metadata for an MPD song, shaped like what mpDris2 publishes.
+fn build_metadata(song: &Song, music_directory: Option<&Path>) -> Metadata {
+    let mut values = HashMap::new();
+    let mut insert = |key: &str, value: MetadataValue| {
+        values.insert(key.to_string(), value);
+    };
+    let strings = |items: &[String]| {
+        MetadataValue::Array(items.iter().cloned().map(MetadataValue::String).collect())
+    };
+
+    if let Some(id) = song.id {
+        insert(
+            "mpris:trackid",
+            MetadataValue::String(format!("/org/mprisence/mpd/track/{id}")),
+        );
+    }
+    let file_stem = Path::new(&song.file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned());
+    if let Some(title) = song.title.clone().or(song.name.clone()).or(file_stem) {
+        insert("xesam:title", MetadataValue::String(title));
+    }
+    if !song.artists.is_empty() {
+        insert("xesam:artist", strings(&song.artists));
+    }
+    if let Some(album) = &song.album {
+        insert("xesam:album", MetadataValue::String(album.clone()));
+    }
+    if !song.album_artists.is_empty() {
+        insert("xesam:albumArtist", strings(&song.album_artists));
+    }
+    if let Some(track_number) = song.track_number {
+        insert("xesam:trackNumber", MetadataValue::I32(track_number));
+    }
+    if let Some(disc_number) = song.disc_number {
+        insert("xesam:discNumber", MetadataValue::I32(disc_number));
+    }
+    if let Some(duration) = song.duration.filter(|d| !d.is_zero()) {
+        let micros = duration.as_micros().min(i64::MAX as u128) as i64;
+        insert("mpris:length", MetadataValue::I64(micros));
+    }
+    if let Some(url) = song_url(&song.file, music_directory) {
+        insert("xesam:url", MetadataValue::String(url));
+    }
+    Metadata::from(values)
+}
+
+#[cfg(test)]
+impl MpdPlayer {
+    /// A player with no connection behind it that reports `state` for
+    /// `song`, as if MPD had sent them.
+    pub(crate) fn reporting(state: PlaybackStatus, song: Song) -> Self {
+        let player = Self::new();
+        player.report(state, song);
+        player
+    }
+
+    pub(crate) fn report(&self, state: PlaybackStatus, song: Song) {
+        let status = Status {
+            state,
+            song_id: song.id,
+            volume: None,
+            elapsed: Some(Duration::ZERO),
+            duration: song.duration,
+        };
+        self.update(status, Some(song), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::fake_mpd;
+
+    #[test]
+    fn builds_metadata_with_library_file_urls() {
+        let song = Song {
+            file: "Daft Punk/RAM/08 Get Lucky.flac".to_string(),
+            id: Some(7),
+            title: Some("Get Lucky".to_string()),
+            artists: vec!["Daft Punk".to_string(), "Pharrell Williams".to_string()],
+            album: Some("Random Access Memories".to_string()),
+            track_number: Some(8),
+            duration: Some(Duration::from_secs(248)),
+            ..Song::default()
+        };
+        let metadata = build_metadata(&song, Some(Path::new("/srv/music")));
+        assert_eq!(metadata.title(), Some("Get Lucky"));
+        assert_eq!(
+            metadata.artists(),
+            Some(vec!["Daft Punk", "Pharrell Williams"])
+        );
+        assert_eq!(metadata.track_number(), Some(8));
+        assert_eq!(metadata.length(), Some(Duration::from_secs(248)));
+        assert_eq!(
+            metadata.url(),
+            Some("file:///srv/music/Daft%20Punk/RAM/08%20Get%20Lucky.flac")
+        );
+        assert_eq!(
+            metadata.track_id().map(|id| id.to_string()).as_deref(),
+            Some("/org/mprisence/mpd/track/7")
+        );
+
+        // Radio streams: the station name stands in for the title.
+        let stream = Song {
+            file: "http://radio.example/stream".to_string(),
+            name: Some("Radio Example".to_string()),
+            ..Song::default()
+        };
+        let metadata = build_metadata(&stream, None);
+        assert_eq!(metadata.title(), Some("Radio Example"));
+        assert_eq!(metadata.url(), Some("http://radio.example/stream"));
+    }
+
+    #[tokio::test]
+    async fn follows_a_fake_mpd_without_dbus() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            fake_mpd(
+                stream,
+                vec![
+                    (
+                        "config",
+                        "music_directory: /srv/music\nOK\n",
+                    ),
+                    (
+                        "status",
+                        "volume: 80\nstate: play\nsongid: 7\nelapsed: 12.000\nduration: 248.000\nOK\n",
+                    ),
+                    (
+                        "currentsong",
+                        "file: Daft Punk/RAM/08 Get Lucky.flac\nArtist: Daft Punk\n\
+                         Title: Get Lucky\nduration: 248.000\nId: 7\nOK\n",
+                    ),
+                    ("idle player mixer", "changed: player\nOK\n"),
+                    (
+                        "status",
+                        "volume: 80\nstate: pause\nsongid: 7\nelapsed: 100.000\nduration: 248.000\nOK\n",
+                    ),
+                    (
+                        "currentsong",
+                        "file: Daft Punk/RAM/08 Get Lucky.flac\nArtist: Daft Punk\n\
+                         Title: Get Lucky\nduration: 248.000\nId: 7\nOK\n",
+                    ),
+                    // Hang up during the next `idle`.
+                    ("idle player mixer", ""),
+                ],
+            )
+            .await;
+        });
+
+        let player = MpdPlayer::new();
+        let mut events = player.subscribe();
+        let config = MpdConfig {
+            enabled: true,
+            address,
+            ..MpdConfig::default()
+        };
+        let session = connect(&config).await.unwrap();
+        assert!(matches!(
+            session.serve(&player, std::future::pending()).await,
+            Err(MpdError::Closed)
+        ));
+        server.await.unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(
+            received,
+            [
+                MpdEvent::TrackChanged,
+                MpdEvent::Playing,
+                MpdEvent::Paused,
+                MpdEvent::Seeked(Duration::from_secs(100)),
+            ]
+        );
+        assert_eq!(
+            player.get_playback_status().unwrap(),
+            PlaybackStatus::Paused
+        );
+        assert_eq!(player.get_position().unwrap(), Duration::from_secs(100));
+        assert_eq!(player.get_volume().unwrap(), 0.8);
+        assert_eq!(
+            player.get_metadata().unwrap().url(),
+            Some("file:///srv/music/Daft%20Punk/RAM/08%20Get%20Lucky.flac")
+        );
+
+        player.disconnect();
+        assert!(!player.is_connected());
+        assert_eq!(events.try_recv().unwrap(), MpdEvent::Disconnected);
+        assert!(player.get_metadata().is_err());
+    }
+}
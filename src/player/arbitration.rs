@@ -0,0 +1,325 @@
+//! Presence arbitration: when several players are active, decide which one
+//! owns the activity. Discord shows a single activity, and without a
+//! decision whichever player pushed last would win.
+
+use std::{cmp::Ordering, collections::HashMap, fmt::Display};
+
+use mpris::PlaybackStatus;
+use smol_str::SmolStr;
+
+use crate::{
+    config::schema::{ActivityType, ArbitrationConfig, ArbitrationRule},
+    utils::normalize_player_identity,
+};
+
+/// What arbitration knows about one tracked player.
+#[derive(Debug, Clone)]
+pub struct Contender {
+    /// Registry key in `Mprisence::media_players`.
+    pub key: SmolStr,
+    pub identity: SmolStr,
+    pub bus_name: SmolStr,
+    pub status: Option<PlaybackStatus>,
+    /// Activity type of the last render, if any.
+    pub activity_type: Option<ActivityType>,
+    /// Whether the last update produced an activity. A hidden, blocked or
+    /// failed track does not, even while the player reports `Playing`.
+    pub publishes: bool,
+}
+
+/// The outcome for one player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Standing {
+    Owner,
+    Outranked {
+        by: SmolStr,
+        /// The criterion that decided, or `None` when the owner only kept
+        /// its place on a tie.
+        rule: Option<ArbitrationRule>,
+    },
+    /// The player has nothing to publish, so it cannot own the activity.
+    Silent {
+        by: SmolStr,
+    },
+}
+
+impl Display for Standing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Standing::Owner => write!(f, "owns the activity"),
+            Standing::Outranked { by, rule } => {
+                let reason = match rule {
+                    Some(ArbitrationRule::Playing) => "playing over paused",
+                    Some(ArbitrationRule::Priority) => "priority list",
+                    Some(ArbitrationRule::Music) => "music over video",
+                    Some(ArbitrationRule::Recent) => "started playing more recently",
+                    None => "already shown",
+                };
+                write!(f, "outranked by {} ({})", by, reason)
+            }
+            Standing::Silent { by } => write!(f, "publishes nothing, {} owns the activity", by),
+        }
+    }
+}
+
+/// Remembers the owner and play start order between decisions.
+#[derive(Debug, Default)]
+pub struct Arbiter {
+    owner: Option<SmolStr>,
+    /// Ticket per playing player; a higher ticket started playing later.
+    playing_since: HashMap<SmolStr, u64>,
+    next_ticket: u64,
+}
+
+impl Arbiter {
+    /// The player that got the activity in the last decision.
+    pub fn owner(&self) -> Option<&SmolStr> {
+        self.owner.as_ref()
+    }
+
+    /// Decide who owns the activity. Returns a standing for every
+    /// contender, or an empty map when arbitration is disabled.
+    pub fn arbitrate(
+        &mut self,
+        config: &ArbitrationConfig,
+        contenders: &[Contender],
+    ) -> HashMap<SmolStr, Standing> {
+        self.playing_since.retain(|key, _| {
+            contenders
+                .iter()
+                .any(|c| c.key == *key && c.status == Some(PlaybackStatus::Playing))
+        });
+        for contender in contenders {
+            if contender.status == Some(PlaybackStatus::Playing)
+                && !self.playing_since.contains_key(&contender.key)
+            {
+                self.next_ticket += 1;
+                self.playing_since
+                    .insert(contender.key.clone(), self.next_ticket);
+            }
+        }
+
+        if !config.enabled || contenders.is_empty() {
+            self.owner = None;
+            return HashMap::new();
+        }
+
+        let owner = contenders
+            .iter()
+            .min_by(|a, b| self.compare(config, a, b).0)
+            .expect("contenders is not empty");
+        self.owner = Some(owner.key.clone());
+
+        contenders
+            .iter()
+            .map(|contender| {
+                let standing = if contender.key == owner.key {
+                    Standing::Owner
+                } else if !contender.publishes {
+                    Standing::Silent {
+                        by: owner.key.clone(),
+                    }
+                } else {
+                    Standing::Outranked {
+                        by: owner.key.clone(),
+                        rule: self.compare(config, owner, contender).1,
+                    }
+                };
+                (contender.key.clone(), standing)
+            })
+            .collect()
+    }
+
+    /// `Less` when `a` should own the activity rather than `b`, with the
+    /// rule that decided.
+    fn compare(
+        &self,
+        config: &ArbitrationConfig,
+        a: &Contender,
+        b: &Contender,
+    ) -> (Ordering, Option<ArbitrationRule>) {
+        // A player with nothing to show must not clear one that has.
+        let ordering = b.publishes.cmp(&a.publishes);
+        if ordering != Ordering::Equal {
+            return (ordering, None);
+        }
+        for rule in &config.order {
+            let ordering = match rule {
+                ArbitrationRule::Playing => status_rank(a.status).cmp(&status_rank(b.status)),
+                ArbitrationRule::Priority => {
+                    priority_rank(config, a).cmp(&priority_rank(config, b))
+                }
+                ArbitrationRule::Music => {
+                    content_rank(a.activity_type).cmp(&content_rank(b.activity_type))
+                }
+                ArbitrationRule::Recent => {
+                    let since = |c: &Contender| self.playing_since.get(&c.key).copied();
+                    since(b).cmp(&since(a))
+                }
+            };
+            if ordering != Ordering::Equal {
+                return (ordering, Some(*rule));
+            }
+        }
+        // Ties keep the current owner so equal players do not take turns.
+        let is_owner = |c: &Contender| self.owner.as_ref() == Some(&c.key);
+        let ordering = is_owner(b)
+            .cmp(&is_owner(a))
+            .then_with(|| a.key.cmp(&b.key));
+        (ordering, None)
+    }
+}
+
+fn status_rank(status: Option<PlaybackStatus>) -> u8 {
+    match status {
+        Some(PlaybackStatus::Playing) => 0,
+        Some(PlaybackStatus::Paused) => 1,
+        _ => 2,
+    }
+}
+
+fn priority_rank(config: &ArbitrationConfig, contender: &Contender) -> usize {
+    let identity = normalize_player_identity(&contender.identity);
+    config
+        .priority
+        .iter()
+        .position(|entry| {
+            normalize_player_identity(entry) == identity
+                || entry.eq_ignore_ascii_case(&contender.bus_name)
+        })
+        .unwrap_or(config.priority.len())
+}
+
+fn content_rank(activity_type: Option<ActivityType>) -> u8 {
+    match activity_type {
+        Some(ActivityType::Listening) => 0,
+        Some(ActivityType::Watching) => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::LoserAction;
+
+    fn config(order: Vec<ArbitrationRule>, priority: &[&str]) -> ArbitrationConfig {
+        ArbitrationConfig {
+            enabled: true,
+            order,
+            priority: priority.iter().map(|p| p.to_string()).collect(),
+            losers: LoserAction::Clear,
+        }
+    }
+
+    fn contender(key: &str, status: PlaybackStatus, activity_type: ActivityType) -> Contender {
+        Contender {
+            key: key.into(),
+            identity: key.into(),
+            bus_name: key.into(),
+            status: Some(status),
+            activity_type: Some(activity_type),
+            publishes: true,
+        }
+    }
+
+    fn owner(standings: &HashMap<SmolStr, Standing>) -> &str {
+        standings
+            .iter()
+            .find(|(_, standing)| **standing == Standing::Owner)
+            .map(|(key, _)| key.as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn disabled_arbitration_decides_nothing() {
+        let mut arbiter = Arbiter::default();
+        let players = [contender(
+            "vlc",
+            PlaybackStatus::Playing,
+            ActivityType::Watching,
+        )];
+        assert!(arbiter
+            .arbitrate(&ArbitrationConfig::default(), &players)
+            .is_empty());
+    }
+
+    #[test]
+    fn rules_apply_in_configured_order() {
+        let players = [
+            contender("spotify", PlaybackStatus::Paused, ActivityType::Listening),
+            contender("vlc", PlaybackStatus::Playing, ActivityType::Watching),
+            contender("mpd", PlaybackStatus::Playing, ActivityType::Listening),
+        ];
+        let mut arbiter = Arbiter::default();
+
+        let standings = arbiter.arbitrate(&config(default_order(), &[]), &players);
+        assert_eq!(owner(&standings), "mpd");
+        assert_eq!(
+            standings["vlc"],
+            Standing::Outranked {
+                by: "mpd".into(),
+                rule: Some(ArbitrationRule::Music)
+            }
+        );
+        assert_eq!(
+            standings["spotify"].to_string(),
+            "outranked by mpd (playing over paused)"
+        );
+
+        let priority_first = vec![ArbitrationRule::Priority, ArbitrationRule::Playing];
+        let standings = arbiter.arbitrate(&config(priority_first, &["Spotify", "vlc"]), &players);
+        assert_eq!(owner(&standings), "spotify");
+    }
+
+    #[test]
+    fn most_recent_player_wins_and_ties_keep_the_owner() {
+        let mut arbiter = Arbiter::default();
+        let recent = config(vec![ArbitrationRule::Recent], &[]);
+        let mut players = vec![
+            contender("mpd", PlaybackStatus::Playing, ActivityType::Listening),
+            contender("vlc", PlaybackStatus::Paused, ActivityType::Listening),
+        ];
+        assert_eq!(owner(&arbiter.arbitrate(&recent, &players)), "mpd");
+
+        players[1].status = Some(PlaybackStatus::Playing);
+        assert_eq!(owner(&arbiter.arbitrate(&recent, &players)), "vlc");
+
+        // Nothing tells them apart: the owner keeps the activity.
+        let playing = config(vec![ArbitrationRule::Playing], &[]);
+        let standings = arbiter.arbitrate(&playing, &players);
+        assert_eq!(owner(&standings), "vlc");
+        assert_eq!(
+            standings["mpd"].to_string(),
+            "outranked by vlc (already shown)"
+        );
+    }
+
+    #[test]
+    fn players_that_publish_nothing_never_take_the_activity() {
+        let mut arbiter = Arbiter::default();
+        let recent = config(vec![ArbitrationRule::Recent], &[]);
+        let mut players = vec![contender(
+            "mpd",
+            PlaybackStatus::Playing,
+            ActivityType::Listening,
+        )];
+        assert_eq!(owner(&arbiter.arbitrate(&recent, &players)), "mpd");
+
+        // Started later, but its track is hidden by a [[filter]].
+        let mut hidden = contender("vlc", PlaybackStatus::Playing, ActivityType::Listening);
+        hidden.publishes = false;
+        players.push(hidden);
+        let standings = arbiter.arbitrate(&recent, &players);
+        assert_eq!(owner(&standings), "mpd");
+        assert_eq!(standings["vlc"], Standing::Silent { by: "mpd".into() });
+
+        // Once it has something to show, the recent rule applies again.
+        players[1].publishes = true;
+        assert_eq!(owner(&arbiter.arbitrate(&recent, &players)), "vlc");
+    }
+
+    fn default_order() -> Vec<ArbitrationRule> {
+        ArbitrationConfig::default().order
+    }
+}
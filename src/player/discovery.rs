@@ -0,0 +1,173 @@
+//! Player discovery shared by the daemon and `mprisence bar`: every allowed,
+//! non-ignored MPRIS player, plus MPD when the native backend is connected,
+//! grouped into logical players.
+
+use std::collections::HashMap;
+
+use log::{debug, trace};
+use mpris::{Player, PlayerFinder};
+use smol_str::SmolStr;
+
+use super::{
+    bridge_browser, canonical_player_bus_name, is_mprisence_web_bridge_bus,
+    is_playerctld_no_active_error, merge_url_duplicates, select_richest_player, select_winner_idx,
+    should_suppress_native, MediaPlayer, PlayerIdentifier,
+};
+use crate::{config::ConfigManager, error::MprisenceError, mpd, utils};
+
+/// Every player on the session bus.
+fn mpris_players() -> Result<Vec<Player>, MprisenceError> {
+    let mut player_finder = PlayerFinder::new()?;
+    player_finder.set_player_timeout_ms(5000);
+
+    let mut players = Vec::new();
+    for player in player_finder.iter_players()? {
+        match player {
+            Ok(player) => players.push(player),
+            Err(err) if is_playerctld_no_active_error(&err) => {
+                debug!("Skipping playerctld proxy without an active player during discovery");
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(players)
+}
+
+/// The MPRIS players and the native MPD player. Without a session bus MPD
+/// is still listed; the bus error only surfaces when there is nothing else.
+fn all_players() -> Result<Vec<MediaPlayer>, MprisenceError> {
+    let mpd = mpd::player().map(MediaPlayer::Mpd);
+    let mut players: Vec<MediaPlayer> = match mpris_players() {
+        Ok(players) => players.into_iter().map(MediaPlayer::Mpris).collect(),
+        Err(e) if mpd.is_some() => {
+            debug!(
+                "Listing MPRIS players failed, continuing with MPD only: {}",
+                e
+            );
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+    players.extend(mpd);
+    Ok(players)
+}
+
+/// Scan for players and group the ones worth presenting by logical
+/// player. A group holds more than one player only when several bus names
+/// expose the same player; callers pick one with `select_richest_player` /
+/// `select_winner_idx`.
+pub fn find_candidates(
+    config: &ConfigManager,
+) -> Result<HashMap<SmolStr, Vec<MediaPlayer>>, MprisenceError> {
+    // Phase 1: collect all allowed, non-ignored players and group them by
+    // normalised identity so that multiple bus names for the same underlying
+    // player (e.g. `mpd` and `playerctld`) are treated as one logical player.
+    let mut candidates: HashMap<SmolStr, Vec<MediaPlayer>> = HashMap::new();
+
+    for player in all_players()? {
+        let id = PlayerIdentifier::from(&player);
+
+        if !config.is_player_allowed(&id.identity, &id.player_bus_name) {
+            trace!("Skipping disallowed player: {}", id.identity);
+            continue;
+        }
+
+        let metadata = player.get_metadata().ok();
+        let url = metadata
+            .as_ref()
+            .and_then(|m| m.url().map(|s| s.to_string()));
+        let title = metadata
+            .as_ref()
+            .and_then(|m| m.title().map(|s| s.to_string()));
+        let (player_config, _) = config.get_player_config_with_title_fallback(
+            &id.identity,
+            &id.player_bus_name,
+            url.as_deref(),
+            title.as_deref(),
+        );
+        if player_config.ignore {
+            trace!(
+                "Skipping ignored player: {} ({})",
+                id.identity,
+                url.as_deref().unwrap_or("no URL")
+            );
+            continue;
+        }
+
+        // Bridge players are one-per-tab: their canonical bus name
+        // (`mprisence_web.<site>.<hash>`) is unique per tab, so key by it.
+        // Native players are keyed by normalized identity so multiple bus
+        // names for the same player still collapse to one.
+        let norm_id = if is_mprisence_web_bridge_bus(&id.player_bus_name) {
+            id.player_bus_name.clone()
+        } else {
+            SmolStr::new(utils::normalize_player_identity(&id.identity))
+        };
+        candidates.entry(norm_id).or_default().push(player);
+    }
+
+    // Phase 1.5: merge identity groups that share the same xesam:url, so
+    // that e.g. plasma-browser-integration and the native browser MPRIS
+    // endpoint exposing the same tab are tracked as one logical player.
+    let candidates = merge_url_duplicates(candidates);
+
+    // Phase 1.6a: when a native browser MPRIS endpoint exposes the same
+    // tab as a live bridge player, suppress the native endpoint so the tab
+    // is not double-counted. Keep unrelated native browser entries (e.g.
+    // SoundCloud in the browser when only YouTube tabs are bridged).
+    let bridged_sources: Vec<(String, String)> = candidates
+        .values()
+        .flatten()
+        .filter(|p| is_mprisence_web_bridge_bus(p.bus_name()))
+        // A D-Bus metadata error silently skips the player for this scan cycle.
+        .filter_map(|p| {
+            let metadata = p.get_metadata().ok()?;
+            let browser = bridge_browser(&metadata)?;
+            let url = metadata.url().map(|s| s.to_string())?;
+            Some((browser, url))
+        })
+        .collect();
+    Ok(if bridged_sources.is_empty() {
+        candidates
+    } else {
+        candidates
+            .into_iter()
+            // A post-merge candidate group holds players of a single kind
+            // (bridge groups are keyed per-tab and never URL-merged), so a
+            // group is dropped only when every player in it is a native
+            // browser bus already represented by a bridge player.
+            .filter(|(_, players)| {
+                !players.iter().all(|p| {
+                    let canon = canonical_player_bus_name(p.bus_name());
+                    let native_url = p
+                        .get_metadata()
+                        .ok()
+                        .and_then(|m| m.url().map(|s| s.to_string()));
+                    should_suppress_native(&canon, native_url.as_deref(), &bridged_sources)
+                })
+            })
+            .collect()
+    })
+}
+
+/// [`find_candidates`] with one player picked per logical player, the way
+/// the daemon picks them. `current_bus` maps a key to the bus name picked
+/// last time, which keeps winning ties so the pick does not flip between
+/// equally rich buses.
+pub fn find_players(
+    config: &ConfigManager,
+    current_bus: &HashMap<SmolStr, SmolStr>,
+) -> Result<HashMap<SmolStr, MediaPlayer>, MprisenceError> {
+    let mut winners = HashMap::new();
+    for (key, mut group) in find_candidates(config)? {
+        let current = current_bus.get(&key).map(SmolStr::as_str);
+        let winner_idx = if group.len() > 1 {
+            select_richest_player(&group, current)
+        } else {
+            let ids: Vec<PlayerIdentifier> = group.iter().map(PlayerIdentifier::from).collect();
+            select_winner_idx(&ids, current)
+        };
+        winners.insert(key, group.remove(winner_idx));
+    }
+    Ok(winners)
+}
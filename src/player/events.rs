@@ -7,7 +7,9 @@ use std::thread::JoinHandle;
 use log::{debug, trace, warn};
 use mpris::{Event as MprisEvent, PlayerFinder};
 use smol_str::SmolStr;
-use tokio::sync::{mpsc, Notify};
+use tokio::sync::{broadcast, mpsc, Notify};
+
+use crate::mpd::{MpdEvent, MpdPlayer};
 
 /// Event emitted by a per-player listener thread, forwarded to the async event loop.
 #[derive(Debug)]
@@ -143,6 +145,66 @@ fn run_listener(
     });
 }
 
+/// Forward the native MPD backend's changes as the MPRIS events a bus
+/// player would emit. Runs as a task: the backend's channel is async.
+pub fn spawn_mpd_listener(
+    player: MpdPlayer,
+    norm_id: SmolStr,
+    tx: mpsc::Sender<PlayerEvent>,
+    cancel: Arc<AtomicBool>,
+    update_generation: Arc<std::sync::atomic::AtomicU64>,
+    update_notify: Arc<Notify>,
+) {
+    let mut events = player.subscribe();
+    tokio::spawn(async move {
+        debug!("listener spawn for {} (mpd)", norm_id);
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    trace!("listener for {} skipped {} MPD events", norm_id, skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if cancel.load(Ordering::Relaxed) {
+                debug!("listener for {} cancelled", norm_id);
+                return;
+            }
+            trace!("event from {}: {:?}", norm_id, event);
+            let event = match event {
+                MpdEvent::TrackChanged => {
+                    update_generation.fetch_add(1, Ordering::Relaxed);
+                    update_notify.notify_waiters();
+                    MprisEvent::TrackChanged(player.get_metadata().unwrap_or_default())
+                }
+                MpdEvent::Playing => MprisEvent::Playing,
+                MpdEvent::Paused => MprisEvent::Paused,
+                MpdEvent::Stopped => MprisEvent::Stopped,
+                MpdEvent::Seeked(position) => MprisEvent::Seeked {
+                    position_in_us: position.as_micros().min(u64::MAX as u128) as u64,
+                },
+                MpdEvent::Disconnected => MprisEvent::PlayerShutDown,
+            };
+            let msg = PlayerEvent {
+                norm_id: norm_id.clone(),
+                kind: PlayerEventKind::Mpris(event),
+            };
+            if tx.send(msg).await.is_err() {
+                debug!("listener for {} exiting: receiver dropped", norm_id);
+                return;
+            }
+        }
+        debug!("listener for {} exited", norm_id);
+        let _ = tx
+            .send(PlayerEvent {
+                norm_id,
+                kind: PlayerEventKind::ListenerExited,
+            })
+            .await;
+    });
+}
+
 fn find_player_by_bus_name(bus_name: &str) -> Result<mpris::Player, String> {
     let mut finder = PlayerFinder::new().map_err(|e| e.to_string())?;
     finder.set_player_timeout_ms(5000);
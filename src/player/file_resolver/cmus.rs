@@ -0,0 +1,64 @@
+//! cmus: its MPRIS interface has no `xesam:url`, so ask `cmus-remote`.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use async_trait::async_trait;
+
+use super::{run_command, LocalFileResolver, ResolveError, Track};
+
+pub struct CmusResolver;
+
+#[async_trait]
+impl LocalFileResolver for CmusResolver {
+    fn name(&self) -> &'static str {
+        "cmus"
+    }
+
+    async fn resolve(&self, _track: &Track<'_>) -> Result<Option<PathBuf>, ResolveError> {
+        let output = run_command("cmus-remote", &["-Q".to_string()], &HashMap::new()).await?;
+        Ok(parse_track_path(&output))
+    }
+}
+
+fn parse_track_path(output: &str) -> Option<PathBuf> {
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("file ") {
+            let path_str = rest.trim();
+            if path_str.is_empty() {
+                continue;
+            }
+            let path = PathBuf::from(path_str);
+            if path.is_absolute() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_track_path;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parses_file_line() {
+        let output = "status playing\nfile /music/test.flac\ntag artist Foo";
+        assert_eq!(
+            parse_track_path(output),
+            Some(PathBuf::from("/music/test.flac"))
+        );
+    }
+
+    #[test]
+    fn ignores_non_file_lines() {
+        let output = "status playing\nstream https://example.com/stream";
+        assert_eq!(parse_track_path(output), None);
+    }
+
+    #[test]
+    fn ignores_relative_paths() {
+        let output = "file relative/path.mp3";
+        assert_eq!(parse_track_path(output), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Run a user command that prints the playing file, for players with their
+//! own query tool (e.g. `mocp -Q %file`, `deadbeef --nowplaying-tf %path%`).
+//! The track is passed in `MPRISENCE_*` environment variables.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use async_trait::async_trait;
+use url::Url;
+
+use super::{run_command, LocalFileResolver, ResolveError, Track};
+
+pub struct CommandResolver {
+    /// Program and arguments; not run through a shell.
+    command: Vec<String>,
+}
+
+impl CommandResolver {
+    pub fn new(command: Vec<String>) -> Self {
+        Self { command }
+    }
+}
+
+#[async_trait]
+impl LocalFileResolver for CommandResolver {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    async fn resolve(&self, track: &Track<'_>) -> Result<Option<PathBuf>, ResolveError> {
+        let Some((program, args)) = self.command.split_first() else {
+            return Ok(None);
+        };
+        let envs = HashMap::from([
+            ("MPRISENCE_PLAYER", track.identity.to_string()),
+            ("MPRISENCE_BUS_NAME", track.bus_name.to_string()),
+            (
+                "MPRISENCE_TRACK_ID",
+                track.track_id.unwrap_or_default().to_string(),
+            ),
+            (
+                "MPRISENCE_TITLE",
+                track.title.unwrap_or_default().to_string(),
+            ),
+            ("MPRISENCE_URL", track.url.unwrap_or_default().to_string()),
+        ]);
+        let output = run_command(program, args, &envs).await?;
+        Ok(parse_output(&output))
+    }
+}
+
+/// The first non-empty line, as a path or a `file://` URL. Other URLs are
+/// streams and have no local file.
+fn parse_output(output: &str) -> Option<PathBuf> {
+    let line = output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    if line.contains("://") {
+        return Url::parse(line).ok()?.to_file_path().ok();
+    }
+    Some(PathBuf::from(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track() -> Track<'static> {
+        Track {
+            identity: "DeaDBeeF",
+            bus_name: "deadbeef",
+            track_id: None,
+            title: Some("Get Lucky"),
+            url: None,
+        }
+    }
+
+    #[test]
+    fn parses_paths_and_file_urls() {
+        assert_eq!(
+            parse_output("\n/music/a b.flac\nextra"),
+            Some(PathBuf::from("/music/a b.flac"))
+        );
+        assert_eq!(
+            parse_output("file:///music/a%20b.flac"),
+            Some(PathBuf::from("/music/a b.flac"))
+        );
+        assert_eq!(parse_output("https://radio.example/stream"), None);
+        assert_eq!(parse_output("  \n"), None);
+    }
+
+    #[tokio::test]
+    async fn runs_the_command_with_track_variables() {
+        let resolver = CommandResolver::new(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "echo \"/music/$MPRISENCE_TITLE.flac\"".to_string(),
+        ]);
+        assert_eq!(
+            resolver.resolve(&track()).await.unwrap(),
+            Some(PathBuf::from("/music/Get Lucky.flac"))
+        );
+
+        let failing = CommandResolver::new(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "exit 3".to_string(),
+        ]);
+        assert!(matches!(
+            failing.resolve(&track()).await,
+            Err(ResolveError::NonZeroExit {
+                status: Some(3),
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,290 @@
+//! Local file resolvers: per-player ways to find the file behind the
+//! playing track when the player's `xesam:url` is missing or unusable, so
+//! tag reading and local cover search still work. Selected with
+//! `file_resolver` in `[player.*]`.
+
+pub mod cmus;
+pub mod command;
+pub mod prefix;
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::Output,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use mpris::Metadata;
+use parking_lot::Mutex;
+use smol_str::SmolStr;
+use thiserror::Error;
+use tokio::{process::Command, runtime::Handle, time::timeout};
+use url::Url;
+
+use crate::config::{schema::FileResolverConfig, ConfigManager};
+use crate::player::{canonical_player_bus_name, MediaPlayer};
+
+use self::cmus::CmusResolver;
+use self::command::CommandResolver;
+use self::prefix::PrefixResolver;
+
+/// Longest a resolver command may run before it is abandoned.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("{program} timed out after {timeout:?}")]
+    Timeout { program: String, timeout: Duration },
+    #[error("{program} failed to launch: {source}")]
+    Launch {
+        program: String,
+        source: std::io::Error,
+    },
+    #[error("{program} exited with status {status:?}: {stderr}")]
+    NonZeroExit {
+        program: String,
+        status: Option<i32>,
+        stderr: String,
+    },
+}
+
+/// What a resolver knows about the playing track.
+#[derive(Debug, Clone, Copy)]
+pub struct Track<'a> {
+    pub identity: &'a str,
+    pub bus_name: &'a str,
+    pub track_id: Option<&'a str>,
+    pub title: Option<&'a str>,
+    /// The player's own `xesam:url`, if any.
+    pub url: Option<&'a str>,
+}
+
+#[async_trait]
+pub trait LocalFileResolver: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Absolute path of the playing file, or `None` when there is none
+    /// (e.g. a stream, or nothing playing yet).
+    async fn resolve(&self, track: &Track<'_>) -> Result<Option<PathBuf>, ResolveError>;
+}
+
+pub fn create_resolver(config: &FileResolverConfig) -> Arc<dyn LocalFileResolver> {
+    match config {
+        FileResolverConfig::Cmus => Arc::new(CmusResolver),
+        FileResolverConfig::Command { command } => Arc::new(CommandResolver::new(command.clone())),
+        FileResolverConfig::Prefix { from, to } => {
+            Arc::new(PrefixResolver::new(from.clone(), to.clone()))
+        }
+    }
+}
+
+/// The resolver for one player connection and its result for the current
+/// track. Resolvers may be slow (cmus and command resolvers spawn a
+/// process), so the path is kept until the track changes.
+pub struct FileResolution {
+    resolver: Mutex<Option<(FileResolverConfig, Arc<dyn LocalFileResolver>)>>,
+    track_token: Mutex<Option<Box<str>>>,
+    path: Mutex<Option<PathBuf>>,
+    /// Errors are logged once per track, not on every update.
+    error_logged: AtomicBool,
+}
+
+impl FileResolution {
+    pub fn new() -> Self {
+        Self {
+            resolver: Mutex::new(None),
+            track_token: Mutex::new(None),
+            path: Mutex::new(None),
+            error_logged: AtomicBool::new(false),
+        }
+    }
+
+    pub fn reset(&self) {
+        *self.track_token.lock() = None;
+        *self.path.lock() = None;
+        self.error_logged.store(false, Ordering::Relaxed);
+    }
+
+    /// A `file://` URL for the playing track from the configured resolver,
+    /// or `None` when the player has none or it found nothing.
+    pub async fn resolve_url(
+        &self,
+        config: Option<&FileResolverConfig>,
+        identity: &str,
+        bus_name: &str,
+        metadata: &Metadata,
+    ) -> Option<String> {
+        let resolver = self.resolver_for(config)?;
+
+        let track_id = metadata.track_id().map(|id| id.to_string());
+        let track_token = track_id
+            .clone()
+            .or_else(|| metadata.url().map(|url| url.to_string()))
+            .or_else(|| metadata.title().map(|title| title.to_string()));
+        let track_changed = track_token.as_deref() != self.track_token.lock().as_deref();
+        if track_changed {
+            *self.track_token.lock() = track_token.map(|token| token.into_boxed_str());
+            *self.path.lock() = None;
+            self.error_logged.store(false, Ordering::Relaxed);
+        }
+
+        if self.path.lock().is_none() {
+            let track = Track {
+                identity,
+                bus_name,
+                track_id: track_id.as_deref(),
+                title: metadata.title(),
+                url: metadata.url(),
+            };
+            match resolver.resolve(&track).await {
+                Ok(Some(path)) => {
+                    debug!("{} resolver: {:?}", resolver.name(), path);
+                    *self.path.lock() = Some(path);
+                }
+                Ok(None) => {}
+                Err(e) => self.log_once(|| warn!("{} resolver failed: {}", resolver.name(), e)),
+            }
+        }
+
+        let path = self.path.lock().clone()?;
+        match Url::from_file_path(&path) {
+            Ok(url) => Some(url.to_string()),
+            Err(_) => {
+                self.log_once(|| {
+                    warn!(
+                        "{} resolver returned a non-absolute path: {:?}",
+                        resolver.name(),
+                        path
+                    )
+                });
+                None
+            }
+        }
+    }
+
+    /// The resolver for `config`, rebuilt when the config changed.
+    fn resolver_for(
+        &self,
+        config: Option<&FileResolverConfig>,
+    ) -> Option<Arc<dyn LocalFileResolver>> {
+        let mut current = self.resolver.lock();
+        let Some(config) = config else {
+            *current = None;
+            return None;
+        };
+        let changed = current.as_ref().map(|(c, _)| c) != Some(config);
+        if changed {
+            *current = Some((config.clone(), create_resolver(config)));
+        }
+        let resolver = current.as_ref().map(|(_, resolver)| Arc::clone(resolver));
+        drop(current);
+        if changed {
+            self.reset();
+        }
+        resolver
+    }
+
+    fn log_once(&self, log: impl FnOnce()) {
+        if !self.error_logged.swap(true, Ordering::Relaxed) {
+            log();
+        }
+    }
+}
+
+impl Default for FileResolution {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`FileResolution`] per player for code that samples every player on
+/// each tick instead of holding a connection (`mprisence bar`, the
+/// scrobbler), keyed by discovery key.
+#[derive(Default)]
+pub struct FileResolutions(Mutex<HashMap<SmolStr, Arc<FileResolution>>>);
+
+impl FileResolutions {
+    /// [`FileResolution::resolve_url`] with `player`'s configured resolver,
+    /// for blocking sampling code running inside the tokio runtime.
+    pub fn resolve_url_blocking(
+        &self,
+        key: &SmolStr,
+        config: &ConfigManager,
+        player: &MediaPlayer,
+        metadata: &Metadata,
+    ) -> Option<String> {
+        let bus_name = canonical_player_bus_name(player.bus_name());
+        let resolver = config
+            .get_player_config(player.identity(), &bus_name)
+            .file_resolver?;
+        let resolution = Arc::clone(self.0.lock().entry(key.clone()).or_default());
+        tokio::task::block_in_place(|| {
+            Handle::current().block_on(resolution.resolve_url(
+                Some(&resolver),
+                player.identity(),
+                &bus_name,
+                metadata,
+            ))
+        })
+    }
+
+    /// Forget players for which `keep` is false, e.g. ones that went away.
+    pub fn retain(&self, keep: impl Fn(&SmolStr) -> bool) {
+        self.0.lock().retain(|key, _| keep(key));
+    }
+}
+
+/// Run `program` with a timeout and return its stdout.
+pub(crate) async fn run_command(
+    program: &str,
+    args: &[String],
+    envs: &HashMap<&str, String>,
+) -> Result<String, ResolveError> {
+    let output: Output = match timeout(
+        COMMAND_TIMEOUT,
+        Command::new(program)
+            .args(args)
+            .envs(envs)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    {
+        Ok(Ok(output)) => output,
+        Ok(Err(source)) => {
+            return Err(ResolveError::Launch {
+                program: program.to_string(),
+                source,
+            })
+        }
+        Err(_) => {
+            return Err(ResolveError::Timeout {
+                program: program.to_string(),
+                timeout: COMMAND_TIMEOUT,
+            })
+        }
+    };
+
+    if !output.status.success() {
+        let mut stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if stderr.is_empty() {
+            stderr = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        }
+        if stderr.is_empty() {
+            stderr = "no output".to_string();
+        }
+        return Err(ResolveError::NonZeroExit {
+            program: program.to_string(),
+            status: output.status.code(),
+            stderr,
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
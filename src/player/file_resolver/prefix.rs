@@ -0,0 +1,72 @@
+//! Rewrite the start of the path the player reports, for players that
+//! emit relative paths or run in a container that mounts the library
+//! elsewhere.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use url::Url;
+
+use super::{LocalFileResolver, ResolveError, Track};
+use crate::utils::{expand_home, replace_path_prefix};
+
+pub struct PrefixResolver {
+    /// Prefix to replace; empty matches every relative path.
+    from: PathBuf,
+    to: PathBuf,
+}
+
+impl PrefixResolver {
+    pub fn new(from: String, to: String) -> Self {
+        Self {
+            from: PathBuf::from(from),
+            to: expand_home(&to),
+        }
+    }
+
+    fn rewrite(&self, url: &str) -> Option<PathBuf> {
+        let path = if url.contains("://") {
+            Url::parse(url).ok()?.to_file_path().ok()?
+        } else {
+            PathBuf::from(url)
+        };
+        if self.from.as_os_str().is_empty() && path.is_absolute() {
+            return None;
+        }
+        replace_path_prefix(&path, &self.from, &self.to)
+    }
+}
+
+#[async_trait]
+impl LocalFileResolver for PrefixResolver {
+    fn name(&self) -> &'static str {
+        "prefix"
+    }
+
+    async fn resolve(&self, track: &Track<'_>) -> Result<Option<PathBuf>, ResolveError> {
+        Ok(track.url.and_then(|url| self.rewrite(url)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_matching_prefixes_only() {
+        let container = PrefixResolver::new("/data/music".to_string(), "/srv/music".to_string());
+        assert_eq!(
+            container.rewrite("file:///data/music/Daft%20Punk/01.flac"),
+            Some(PathBuf::from("/srv/music/Daft Punk/01.flac"))
+        );
+        assert_eq!(container.rewrite("/data/musical/01.flac"), None);
+        assert_eq!(container.rewrite("https://example.com/01.flac"), None);
+
+        let relative = PrefixResolver::new(String::new(), "/srv/music".to_string());
+        assert_eq!(
+            relative.rewrite("Daft Punk/01.flac"),
+            Some(PathBuf::from("/srv/music/Daft Punk/01.flac"))
+        );
+        assert_eq!(relative.rewrite("/elsewhere/01.flac"), None);
+    }
+}
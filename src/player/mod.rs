@@ -1,15 +1,20 @@
 use std::{collections::HashMap, fmt::Display, time::Duration};
 
 use log::{debug, info, trace};
-use mpris::{DBusError, PlaybackStatus, Player};
+use mpris::{DBusError, PlaybackStatus};
 use smol_str::SmolStr;
 use url::Url;
 
 use crate::utils;
 
-pub mod cmus;
+pub mod arbitration;
+pub mod discovery;
 pub mod events;
+pub mod file_resolver;
 pub mod health;
+pub mod source;
+
+pub use source::MediaPlayer;
 
 const MPRIS_BUS_PREFIX: &str = "org.mpris.MediaPlayer2.";
 const PLAYERCTLD_NO_ACTIVE_PLAYER_ERROR: &str = "com.github.altdesktop.playerctld.NoActivePlayer";
@@ -32,8 +37,8 @@ impl Display for PlayerIdentifier {
     }
 }
 
-impl From<&Player> for PlayerIdentifier {
-    fn from(player: &Player) -> Self {
+impl From<&MediaPlayer> for PlayerIdentifier {
+    fn from(player: &MediaPlayer) -> Self {
         let player_bus_name = canonical_player_bus_name(player.bus_name());
 
         Self {
@@ -74,8 +79,8 @@ impl Display for PlaybackState {
     }
 }
 
-impl From<&Player> for PlaybackState {
-    fn from(player: &Player) -> Self {
+impl From<&MediaPlayer> for PlaybackState {
+    fn from(player: &MediaPlayer) -> Self {
         let metadata = player.get_metadata().ok();
         let playback_status = player.get_playback_status().ok();
         Self::from_parts(player, playback_status, metadata.as_ref())
@@ -86,7 +91,7 @@ impl PlaybackState {
     /// Construct `PlaybackState` from an already-fetched status and metadata.
     /// Avoids redundant D-Bus calls when the caller already has both values.
     pub fn from_with_status(
-        player: &Player,
+        player: &MediaPlayer,
         playback_status: PlaybackStatus,
         metadata: &mpris::Metadata,
     ) -> Self {
@@ -94,7 +99,7 @@ impl PlaybackState {
     }
 
     fn from_parts(
-        player: &Player,
+        player: &MediaPlayer,
         playback_status: Option<PlaybackStatus>,
         metadata: Option<&mpris::Metadata>,
     ) -> Self {
@@ -265,7 +270,7 @@ pub const BRIDGE_CONFIG_KEY: &str = "mprisence_web";
 
 /// Score a player's metadata richness (higher = richer).
 /// Used to break ties when multiple bus names expose the same content.
-pub(crate) fn metadata_richness(player: &Player) -> u8 {
+pub(crate) fn metadata_richness(player: &MediaPlayer) -> u8 {
     let mut score: u8 = 0;
     if let Ok(meta) = player.get_metadata() {
         if meta.title().is_some() {
@@ -293,7 +298,7 @@ pub(crate) fn metadata_richness(player: &Player) -> u8 {
 /// Given a group of `Player`s that represent the same content (merged by URL),
 /// returns the index of the one with the richest metadata.
 /// Falls back to `select_winner_idx` tie-breaking if scores are equal.
-pub fn select_richest_player(players: &[Player], current_bus: Option<&str>) -> usize {
+pub fn select_richest_player(players: &[MediaPlayer], current_bus: Option<&str>) -> usize {
     if players.len() <= 1 {
         return 0;
     }
@@ -565,8 +570,8 @@ fn compute_url_merges(groups: &[GroupSnapshot]) -> Vec<(SmolStr, SmolStr)> {
 /// both exposing the same tab. Bridge players are one-per-tab and are never
 /// merged — they pass through untouched.
 pub fn merge_url_duplicates(
-    mut candidates: HashMap<SmolStr, Vec<Player>>,
-) -> HashMap<SmolStr, Vec<Player>> {
+    mut candidates: HashMap<SmolStr, Vec<MediaPlayer>>,
+) -> HashMap<SmolStr, Vec<MediaPlayer>> {
     if candidates.len() < 2 {
         return candidates;
     }
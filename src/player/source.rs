@@ -0,0 +1,74 @@
+//! Where a tracked player's state comes from: an MPRIS player on the
+//! session bus, or MPD read over its own protocol. Both answer the same
+//! questions, so discovery, presence, `mprisence bar` and the scrobbler
+//! treat them alike.
+
+use std::time::Duration;
+
+use mpris::{DBusError, Metadata, PlaybackStatus, Player};
+
+use crate::mpd::MpdPlayer;
+
+pub enum MediaPlayer {
+    Mpris(Player),
+    Mpd(MpdPlayer),
+}
+
+impl MediaPlayer {
+    pub fn identity(&self) -> &str {
+        match self {
+            MediaPlayer::Mpris(player) => player.identity(),
+            MediaPlayer::Mpd(player) => player.identity(),
+        }
+    }
+
+    /// The MPRIS bus name, or a stand-in that canonicalizes to the
+    /// player's config key for players that are not on the bus.
+    pub fn bus_name(&self) -> &str {
+        match self {
+            MediaPlayer::Mpris(player) => player.bus_name(),
+            MediaPlayer::Mpd(player) => player.bus_name(),
+        }
+    }
+
+    pub fn unique_name(&self) -> &str {
+        match self {
+            MediaPlayer::Mpris(player) => player.unique_name(),
+            MediaPlayer::Mpd(player) => player.unique_name(),
+        }
+    }
+
+    pub fn get_playback_status(&self) -> Result<PlaybackStatus, DBusError> {
+        match self {
+            MediaPlayer::Mpris(player) => player.get_playback_status(),
+            MediaPlayer::Mpd(player) => player.get_playback_status(),
+        }
+    }
+
+    pub fn get_metadata(&self) -> Result<Metadata, DBusError> {
+        match self {
+            MediaPlayer::Mpris(player) => player.get_metadata(),
+            MediaPlayer::Mpd(player) => player.get_metadata(),
+        }
+    }
+
+    pub fn get_position(&self) -> Result<Duration, DBusError> {
+        match self {
+            MediaPlayer::Mpris(player) => player.get_position(),
+            MediaPlayer::Mpd(player) => player.get_position(),
+        }
+    }
+
+    pub fn get_volume(&self) -> Result<f64, DBusError> {
+        match self {
+            MediaPlayer::Mpris(player) => player.get_volume(),
+            MediaPlayer::Mpd(player) => player.get_volume(),
+        }
+    }
+
+    /// Whether the player lives on the session bus, where a fresh
+    /// connection can re-check it.
+    pub fn is_mpris(&self) -> bool {
+        matches!(self, MediaPlayer::Mpris(_))
+    }
+}
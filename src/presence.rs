@@ -6,13 +6,10 @@ use std::{
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use discord_rich_presence::{
-    activity::{Activity, Assets, Timestamps},
-    DiscordIpc, DiscordIpcClient,
-};
+use discord_rich_presence::DiscordIpc;
 use log::{debug, error, info, trace, warn};
 use mime_guess::mime;
-use mpris::{Event as MprisEvent, Metadata as MprisMetadata, PlaybackStatus, Player};
+use mpris::{Event as MprisEvent, Metadata as MprisMetadata, PlaybackStatus};
 use parking_lot::Mutex;
 use smol_str::SmolStr;
 use tokio::sync::{mpsc, Notify};
@@ -21,36 +18,34 @@ use tokio_util::sync::CancellationToken;
 use crate::{
     config::{
         schema::{
-            ActivityType, ActivityTypesConfig, PlayerConfig, StatusDisplayType,
-            DEFAULT_PLAYER_APP_ID,
+            ActivityType, ActivityTypesConfig, FilterAction, LoserAction, PlayerConfig,
+            RedactField, StatusDisplayType, DEFAULT_PLAYER_APP_ID,
         },
         ConfigManager,
     },
-    cover::CoverManager,
+    cover::{sources::ArtSource, CoverManager},
     error::DiscordError,
+    lyrics::Lyrics,
     metadata::{self, MediaMetadata},
     player::{
-        canonical_player_bus_name, cmus,
+        arbitration::Standing,
+        canonical_player_bus_name,
         events::{self, EventOutcome, PlayerEvent, PlayerEventKind},
-        health, PlaybackState, PlayerIdentifier,
+        file_resolver::FileResolution,
+        health, MediaPlayer, PlaybackState, PlayerIdentifier,
+    },
+    sink::{
+        discord::DiscordSink, PresenceSink, SinkActivity, SinkManager, SinkPlayer, SinkTimestamps,
     },
-    template::{ActivityTexts, TemplateManager},
+    template::{ActivityTexts, RenderContext, TemplateManager},
     utils,
 };
 
 use health::TrackFingerprint;
 
-/// Bundled inputs to `Presence::build_and_push_activity`. Borrows everywhere
-/// except the small `Copy` enums to avoid clones on the hot path.
-struct ActivityFraming<'a> {
-    texts: &'a crate::template::ActivityTexts,
-    /// `(start_s, Option<end_s>)`; absent when no timestamp should be sent.
-    timing: Option<(u64, Option<u64>)>,
-    cover_art_url: Option<&'a str>,
-    player_config: &'a PlayerConfig,
-    activity_type: ActivityType,
-    status_display_type: StatusDisplayType,
-}
+/// Floor for `[lyrics] min_interval` and `[progress] refresh_interval`:
+/// Discord accepts five activity updates per 20 seconds.
+const MIN_TICK_PUSH_INTERVAL: Duration = Duration::from_secs(4);
 
 #[derive(Debug, Clone, PartialEq)]
 struct UpdateSnapshot {
@@ -129,20 +124,21 @@ fn summarize_metadata_for_log(metadata: &MprisMetadata) -> String {
 }
 
 pub struct Presence {
-    player: Player,
+    player: MediaPlayer,
     /// Cached identifier for the currently active player connection.
     /// Updated whenever the underlying bus name or unique connection changes.
     player_id: PlayerIdentifier,
     template_manager: Arc<TemplateManager>,
     cover_manager: Arc<CoverManager>,
     last_player_state: Option<PlaybackState>,
-    cmus: cmus::CmusState,
-    discord_client: Option<Arc<Mutex<DiscordIpcClient>>>,
-    /// The Discord application id the current `discord_client` was opened with.
-    /// Each cycle re-resolves the effective app id (player + web_player overlay).
-    /// A mismatch triggers IPC client recycling so the new app's icon/name
-    /// takes effect.
-    last_effective_app_id: Mutex<Option<String>>,
+    file_resolution: FileResolution,
+    /// Discord IPC connection, opened with the player's effective app id.
+    /// Each cycle re-resolves the app id (player + web_player overlay); a
+    /// mismatch with `DiscordSink::app_id` recycles the client so the new
+    /// app's icon/name takes effect.
+    discord: Option<Arc<DiscordSink>>,
+    /// `[[sink]]` outputs, shared by every player.
+    sinks: Arc<SinkManager>,
     needs_initial_connection: AtomicBool,
     needs_reconnection: AtomicBool,
     error_logged: AtomicBool,
@@ -185,10 +181,20 @@ pub struct Presence {
     /// Cached activity texts from the last template render. Reused when
     /// `last_rendered_snapshot` matches the current state.
     last_activity_texts: Option<crate::template::ActivityTexts>,
-    /// Tracks whether a Discord activity (push) is currently displayed.
+    /// Template variables behind `last_activity_texts`, handed to the sinks.
+    last_render_context: Option<RenderContext>,
+    /// Lyric line at the time of the last template render.
+    last_rendered_lyric_line: Option<String>,
+    /// Synced lyrics of the current track with the update generation they
+    /// were loaded for; the inner `None` means the track has none.
+    lyrics: Option<(u64, Option<Lyrics>)>,
+    /// When the activity was last published, to space out pushes that
+    /// only advance the lyric line.
+    last_published_at: Option<Instant>,
+    /// Tracks whether an activity (push) is currently displayed.
     /// Set `true` after a successful push, `false` after a clear.
     /// Prevents redundant Clear→Clear log spam from duplicate players.
-    discord_activity_is_set: Arc<AtomicBool>,
+    activity_is_set: Arc<AtomicBool>,
     /// False until the first Discord push completes. When false, the health
     /// tracker is bypassed so initial discovery always pushes at least once.
     /// Prevents `track_ended` from swallowing a freshly-discovered player.
@@ -202,14 +208,31 @@ pub struct Presence {
     listener_cancel: Option<Arc<AtomicBool>>,
     /// The MPRIS bus name the active listener is bound to (used to detect winner-bus handoff).
     listener_bus: Option<SmolStr>,
+    /// Daemon-wide pause flag toggled over the control socket. While set,
+    /// updates still run (so state stays current) but nothing is pushed.
+    paused: Arc<AtomicBool>,
+    /// Set while `[arbitration]` gives the activity to another player.
+    /// Shared with background cover tasks so a late cover is not pushed.
+    outranked: Arc<AtomicBool>,
+    /// Last arbitration outcome, `None` while arbitration is disabled.
+    standing: Option<Standing>,
+    /// Activity type of the last update, for arbitration.
+    last_activity_type: Option<ActivityType>,
+    /// Whether the last update produced an activity, even one held back by
+    /// pause or arbitration. Hidden, blocked, stopped, failed and cleared
+    /// updates publish nothing, so arbitration must not hand them the
+    /// activity.
+    publishes: AtomicBool,
 }
 
 impl Presence {
     pub fn new(
-        player: Player,
+        player: MediaPlayer,
         template_manager: Arc<TemplateManager>,
         cover_manager: Arc<CoverManager>,
+        sinks: Arc<SinkManager>,
         config: Arc<ConfigManager>,
+        paused: Arc<AtomicBool>,
     ) -> Self {
         let player_bus_name = canonical_player_bus_name(player.bus_name());
         let player_config = config.get_player_config(player.identity(), &player_bus_name);
@@ -229,9 +252,9 @@ impl Presence {
             template_manager,
             cover_manager,
             last_player_state: None,
-            cmus: cmus::CmusState::new(),
-            discord_client: None,
-            last_effective_app_id: Mutex::new(None),
+            file_resolution: FileResolution::new(),
+            discord: None,
+            sinks,
             needs_initial_connection: AtomicBool::new(true),
             needs_reconnection: AtomicBool::new(false),
             error_logged: AtomicBool::new(false),
@@ -247,15 +270,79 @@ impl Presence {
             last_rendered_snapshot: None,
             last_rendered_volume: None,
             last_activity_texts: None,
-            discord_activity_is_set: Arc::new(AtomicBool::new(false)),
+            last_render_context: None,
+            last_rendered_lyric_line: None,
+            lyrics: None,
+            last_published_at: None,
+            activity_is_set: Arc::new(AtomicBool::new(false)),
             first_update_done: AtomicBool::new(false),
             config,
             cover_cancel_token: Arc::new(parking_lot::Mutex::new(CancellationToken::new())),
             listener_cancel: None,
             listener_bus: None,
+            paused,
+            outranked: Arc::new(AtomicBool::new(false)),
+            standing: None,
+            last_activity_type: None,
+            publishes: AtomicBool::new(false),
         }
     }
 
+    /// Activity texts from the last template render, if any.
+    pub fn activity_texts(&self) -> Option<&crate::template::ActivityTexts> {
+        self.last_activity_texts.as_ref()
+    }
+
+    /// Whether this presence currently has an activity shown in Discord or
+    /// the `[[sink]]` outputs.
+    pub fn activity_is_set(&self) -> bool {
+        self.activity_is_set.load(Ordering::Relaxed)
+    }
+
+    /// Whether anything consumes this player's activity: a Discord client,
+    /// or at least one `[[sink]]`.
+    fn has_output(&self) -> bool {
+        self.discord.is_some() || !self.sinks.is_empty()
+    }
+
+    pub fn activity_type(&self) -> Option<ActivityType> {
+        self.last_activity_type
+    }
+
+    pub fn publishes(&self) -> bool {
+        self.publishes.load(Ordering::Relaxed)
+    }
+
+    pub fn standing(&self) -> Option<&Standing> {
+        self.standing.as_ref()
+    }
+
+    /// Record the arbitration outcome. Returns `Some(outranked)` when it
+    /// changes whether this presence may publish. A silent player stays held
+    /// back too, so it cannot push over the owner before the next decision.
+    pub fn set_standing(&mut self, standing: Option<Standing>) -> Option<bool> {
+        let outranked = matches!(
+            standing,
+            Some(Standing::Outranked { .. } | Standing::Silent { .. })
+        );
+        self.standing = standing;
+        let was_outranked = self.outranked.swap(outranked, Ordering::Relaxed);
+        (was_outranked != outranked).then_some(outranked)
+    }
+
+    fn sink_player(&self) -> SinkPlayer {
+        SinkPlayer {
+            identity: self.player.identity().to_string(),
+            bus_name: canonical_player_bus_name(self.player.bus_name()),
+        }
+    }
+
+    pub fn playback_status(&self) -> Option<PlaybackStatus> {
+        self.last_player_state
+            .as_ref()
+            .and_then(|state| state.playback_status)
+    }
+
     /// Determine if this player is a browser-based source (e.g. Plasma Browser
     /// Integration, Firefox, Chromium). Browser sources get tighter stall
     /// thresholds and a confirming state that blocks the first push until
@@ -296,7 +383,7 @@ impl Presence {
     }
 
     pub fn initialize_discord_client(&mut self) -> Result<(), DiscordError> {
-        if self.discord_client.is_some() {
+        if self.discord.is_some() {
             return Ok(());
         }
         // Resolve the app id with URL/title context so a tab covered by a
@@ -316,21 +403,19 @@ impl Presence {
     }
 
     fn initialize_discord_client_with_app_id(&mut self, app_id: &str) -> Result<(), DiscordError> {
-        let client = DiscordIpcClient::new(app_id);
-        self.discord_client = Some(Arc::new(Mutex::new(client)));
+        self.discord = Some(Arc::new(DiscordSink::new(app_id)));
         self.needs_initial_connection.store(true, Ordering::Relaxed);
-        *self.last_effective_app_id.lock() = Some(app_id.to_string());
         Ok(())
     }
 
     pub fn destroy_discord_client(&mut self) -> Result<(), DiscordError> {
-        if let Some(client) = &self.discord_client {
+        if let Some(discord) = &self.discord {
             debug!(
                 "Closing Discord connection for player: {}",
                 self.player.identity()
             );
             {
-                let mut discord_client = client.lock();
+                let mut discord_client = discord.client();
                 if let Err(err) = discord_client.clear_activity() {
                     debug!(
                         "Failed to clear Discord activity before closing connection: {}",
@@ -344,13 +429,12 @@ impl Presence {
                 })?;
             }
             trace!("Discord connection closed successfully");
-            self.discord_client = None;
-            *self.last_effective_app_id.lock() = None;
+            self.discord = None;
         }
         Ok(())
     }
 
-    pub async fn update(&mut self, player: Player) -> Result<(), DiscordError> {
+    pub async fn update(&mut self, player: MediaPlayer) -> Result<(), DiscordError> {
         trace!("Updating presence for player: {}", player.identity());
 
         // The caller (main.rs discovery loop) keys presences by normalized
@@ -379,7 +463,8 @@ impl Presence {
             self.player_id = new_id;
             self.player = player;
             self.last_player_state = None;
-            self.cmus.reset();
+            self.file_resolution.reset();
+            self.lyrics = None;
             *self.last_pushed_track_id.lock() = None;
             *self.last_pushed_track_url.lock() = None;
             *self.last_pushed_art_url.lock() = None;
@@ -396,9 +481,9 @@ impl Presence {
             return Ok(());
         }
 
-        let Some(_discord_client) = &self.discord_client else {
+        if !self.has_output() {
             return Ok(());
-        };
+        }
 
         self.ensure_connection()?;
 
@@ -411,8 +496,8 @@ impl Presence {
             error!("Failed to get playback status: {}", err);
             DiscordError::ActivityError(format!("Failed to get playback status: {}", err))
         })?;
-        let playback_status = if raw_status == PlaybackStatus::Playing {
-            PlaybackStatus::Playing
+        let playback_status = if raw_status == PlaybackStatus::Playing || !self.player.is_mpris() {
+            raw_status
         } else {
             // Re-check with a fresh connection to rule out stale state.
             recheck_playback_status(&self.player.bus_name())
@@ -517,7 +602,7 @@ impl Presence {
                 }
             }
             health::TransitionOutcome::Clear => {
-                self.clear_discord_activity_with_reason(&format!(
+                self.clear_activity_with_reason(&format!(
                     "Clearing Discord activity - player {} is stalled/stopped/paused",
                     self.player.identity()
                 ))?;
@@ -537,9 +622,9 @@ impl Presence {
     /// Used on initial discovery in event-driven mode — signals only fire on changes,
     /// so the current state must be pushed once when a new player is first seen.
     pub async fn update_from_current_state(&mut self) -> Result<(), DiscordError> {
-        let Some(_) = &self.discord_client else {
+        if !self.has_output() {
             return Ok(());
-        };
+        }
         self.ensure_connection()?;
         // Seed `last_player_state` so the next polling tick's diff sees no change
         // and skips re-pushing (and re-fetching cover art) for the same track.
@@ -548,16 +633,129 @@ impl Presence {
             .await
     }
 
+    /// Forget the cached render and push the current state again, re-rendering
+    /// templates against the current config.
+    pub async fn refresh(&mut self) -> Result<(), DiscordError> {
+        self.last_rendered_snapshot = None;
+        self.last_rendered_volume = None;
+        self.last_activity_texts = None;
+        self.last_render_context = None;
+        self.last_rendered_lyric_line = None;
+        self.update_from_current_state().await
+    }
+
+    /// Re-render between updates while playing: every `[progress]
+    /// refresh_interval`, and when the synced lyric line moved on but at
+    /// most once per `[lyrics] min_interval`. Lines that go by faster are
+    /// skipped.
+    pub async fn tick(&mut self) -> Result<(), DiscordError> {
+        if !self.activity_is_set()
+            || self.paused.load(Ordering::Relaxed)
+            || self.outranked.load(Ordering::Relaxed)
+        {
+            return Ok(());
+        }
+        let waited = |interval: u64| {
+            let interval = Duration::from_millis(interval).max(MIN_TICK_PUSH_INTERVAL);
+            self.last_published_at
+                .is_none_or(|published_at| published_at.elapsed() >= interval)
+        };
+        let refresh_interval = self.config.progress_config().refresh_interval;
+        let refresh_due = refresh_interval > 0 && waited(refresh_interval);
+        let generation = self.update_generation.load(Ordering::Relaxed);
+        let lyrics = match &self.lyrics {
+            Some((loaded_for, Some(lyrics))) if *loaded_for == generation => Some(lyrics),
+            _ => None,
+        };
+        let lyrics_due =
+            !refresh_due && lyrics.is_some() && waited(self.config.lyrics_config().min_interval);
+        if !refresh_due && !lyrics_due {
+            return Ok(());
+        }
+        if self.player.get_playback_status().ok() != Some(PlaybackStatus::Playing) {
+            return Ok(());
+        }
+
+        if let Some(lyrics) = lyrics.filter(|_| lyrics_due) {
+            let Ok(position) = self.player.get_position() else {
+                return Ok(());
+            };
+            if lyrics.line_at(position) == self.last_rendered_lyric_line.as_deref() {
+                return Ok(());
+            }
+            trace!("Lyric line changed for {}", self.player.identity());
+        } else {
+            trace!("Periodic re-render for {}", self.player.identity());
+        }
+        self.update_activity(
+            None,
+            health::ArtDecision::default(),
+            Some(PlaybackStatus::Playing),
+        )
+        .await
+    }
+
+    /// The synced lyric line at the player's position, loading the track's
+    /// lyrics on first use. `None` while `[lyrics]` is disabled.
+    fn lyric_line(&mut self, metadata_source: &metadata::MetadataSource) -> Option<String> {
+        if !self.config.lyrics_config().enabled {
+            self.lyrics = None;
+            return None;
+        }
+        let generation = self.update_generation.load(Ordering::Relaxed);
+        if self.lyrics.as_ref().map(|(loaded_for, _)| *loaded_for) != Some(generation) {
+            self.lyrics = Some((generation, metadata_source.lyrics()));
+        }
+        let lyrics = self.lyrics.as_ref()?.1.as_ref()?;
+        let position = self.player.get_position().ok()?;
+        lyrics.line_at(position).map(str::to_string)
+    }
+
+    /// The cover URL the activity on Discord currently shows.
+    pub fn shown_cover(&self) -> Option<String> {
+        if !self.activity_is_set.load(Ordering::Relaxed) {
+            return None;
+        }
+        let generation = self.update_generation.load(Ordering::Relaxed);
+        match self.last_resolved_cover_art.lock().as_ref() {
+            Some((cover_generation, url)) if *cover_generation == generation => Some(url.clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether the activity on Discord currently shows `url` as its cover.
+    pub fn shows_cover(&self, url: &str) -> bool {
+        self.shown_cover().as_deref() == Some(url)
+    }
+
+    /// Re-push after the shown cover URL was replaced or dropped, so the
+    /// cache (or a fresh fetch) decides the cover instead of the stale URL.
+    pub async fn refresh_cover(&mut self) -> Result<(), DiscordError> {
+        *self.last_resolved_cover_art.lock() = None;
+        self.refresh().await
+    }
+
+    /// Clear this player's activity until its next update.
+    pub fn clear_activity(&self, reason: &str) -> Result<(), DiscordError> {
+        self.clear_activity_with_reason(reason)
+    }
+
+    /// Clear an activity that pause or arbitration holds back. The player
+    /// still has it to show, so it keeps its place in arbitration.
+    pub fn hold_back_activity(&self, reason: &str) -> Result<(), DiscordError> {
+        self.clear_shown_activity(reason)
+    }
+
     fn ensure_connection(&mut self) -> Result<(), DiscordError> {
         const MIN_RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
 
-        let Some(discord_client) = &self.discord_client else {
+        let Some(discord) = &self.discord else {
             return Ok(());
         };
 
         if self.needs_initial_connection.load(Ordering::Relaxed) {
             debug!("Establishing initial Discord connection");
-            discord_client.lock().connect().map_err(|err| {
+            discord.client().connect().map_err(|err| {
                 if !self.error_logged.load(Ordering::Relaxed) {
                     error!("Failed to establish Discord connection: {}", err);
                     self.error_logged.store(true, Ordering::Relaxed);
@@ -585,7 +783,7 @@ impl Presence {
 
             *self.last_reconnect_attempt.lock() = now;
 
-            discord_client.lock().reconnect().map_err(|err| {
+            discord.client().reconnect().map_err(|err| {
                 if !self.error_logged.load(Ordering::Relaxed) {
                     error!("Failed to reconnect to Discord: {}", err);
                     self.error_logged.store(true, Ordering::Relaxed);
@@ -650,7 +848,7 @@ impl Presence {
 
     fn update_snapshot_matches_current(
         &self,
-        player: &Player,
+        player: &MediaPlayer,
         expected: &UpdateSnapshot,
         checkpoint: &str,
     ) -> bool {
@@ -702,7 +900,7 @@ impl Presence {
 
     fn should_discard_stale_update(
         &self,
-        player: &Player,
+        player: &MediaPlayer,
         generation: Option<u64>,
         snapshot: &UpdateSnapshot,
         checkpoint: &str,
@@ -718,27 +916,42 @@ impl Presence {
         !self.update_snapshot_matches_current(player, snapshot, checkpoint)
     }
 
-    fn clear_discord_activity_with_reason(&self, reason: &str) -> Result<(), DiscordError> {
+    /// Clear the activity because there is nothing to show. Every such path
+    /// runs through here, so arbitration never ranks a cleared player as one
+    /// that publishes.
+    fn clear_activity_with_reason(&self, reason: &str) -> Result<(), DiscordError> {
+        self.publishes.store(false, Ordering::Relaxed);
+        self.clear_shown_activity(reason)
+    }
+
+    fn clear_shown_activity(&self, reason: &str) -> Result<(), DiscordError> {
         // Skip redundant clears — prevents log spam from duplicate MPRIS
         // players (e.g. firefox + plasma-browser-integration) both emitting
         // frozen-position events for the same stopped track.
-        if !self.discord_activity_is_set.load(Ordering::Relaxed) {
+        if !self.activity_is_set.load(Ordering::Relaxed) {
             trace!("Skipping redundant clear: {}", reason);
             return Ok(());
         }
         if !self.error_logged.load(Ordering::Relaxed) {
             info!("{}", reason);
         }
-        if let Some(discord_client) = self.discord_client.clone() {
-            discord_client.lock().clear_activity().map_err(|err| {
+        self.clear_outputs()
+    }
+
+    /// Clear the activity from Discord and every `[[sink]]`.
+    fn clear_outputs(&self) -> Result<(), DiscordError> {
+        let player = self.sink_player();
+        self.sinks.clear(&player);
+        if let Some(discord) = &self.discord {
+            discord.clear(&player).map_err(|err| {
                 if !self.error_logged.load(Ordering::Relaxed) {
                     error!("Failed to clear Discord activity: {}", err);
                     self.error_logged.store(true, Ordering::Relaxed);
                 }
                 DiscordError::ActivityError(err.to_string())
             })?;
-            self.discord_activity_is_set.store(false, Ordering::Relaxed);
         }
+        self.activity_is_set.store(false, Ordering::Relaxed);
         Ok(())
     }
 
@@ -748,7 +961,7 @@ impl Presence {
         art_decision: health::ArtDecision,
         playback_status: Option<PlaybackStatus>,
     ) -> Result<(), DiscordError> {
-        if self.discord_client.is_none() {
+        if !self.has_output() {
             return Ok(());
         }
 
@@ -761,7 +974,7 @@ impl Presence {
         };
 
         if playback_status == PlaybackStatus::Stopped || playback_status == PlaybackStatus::Paused {
-            self.clear_discord_activity_with_reason(&format!(
+            self.clear_activity_with_reason(&format!(
                 "Clearing Discord activity - player {} is {}",
                 self.player.identity(),
                 if playback_status == PlaybackStatus::Stopped {
@@ -882,20 +1095,25 @@ impl Presence {
         }
 
         let player_bus_name = canonical_player_bus_name(self.player.bus_name());
-        let is_cmus =
-            player_bus_name == "cmus" || self.player.identity().eq_ignore_ascii_case("cmus");
-        let cmus_override_url = if is_cmus {
-            self.cmus.resolve_url(&metadata).await
-        } else {
-            None
-        };
+        let resolver_config = self
+            .config
+            .get_player_config(self.player.identity(), &player_bus_name)
+            .file_resolver;
+        let resolved_url = self
+            .file_resolution
+            .resolve_url(
+                resolver_config.as_ref(),
+                self.player.identity(),
+                &player_bus_name,
+                &metadata,
+            )
+            .await;
 
-        let metadata_source = match cmus_override_url {
-            Some(url) => {
-                metadata::MetadataSource::from_mpris_with_override(metadata.clone(), Some(url))
-            }
-            None => metadata::MetadataSource::from_mpris_with_override(metadata.clone(), None),
-        };
+        let metadata_source = metadata::MetadataSource::from_mpris_mapped(
+            metadata.clone(),
+            resolved_url,
+            &self.config.path_map(),
+        );
 
         debug!("--- Raw MPRIS Metadata Start ---");
         if let Some(mpris_meta) = metadata_source.mpris_metadata() {
@@ -918,29 +1136,49 @@ impl Presence {
         // --- Snapshot check: skip template rendering if nothing relevant changed ---
         // The snapshot captures playback_status + TrackFingerprint.  Volume is
         // checked separately because it's part of the template context but not
-        // included in UpdateSnapshot.  Position-only updates don't affect templates.
+        // included in UpdateSnapshot.  Position only affects templates through
+        // the lyric line, which is checked the same way, unless `[progress]`
+        // re-renders keep position variables fresh.
         let volume = self.player.get_volume().ok();
-
-        let mut media_metadata = metadata_source.to_media_metadata();
-        let track_url: Option<String> = metadata_source.url();
-        let track_url_ref = track_url.as_deref();
-
-        let (player_config, title_suffix) = self.config.get_player_config_with_title_fallback(
+        let position_changes_texts = self.config.progress_config().refresh_interval > 0;
+
+        let metadata::ResolvedTrack {
+            metadata: mut media_metadata,
+            url: track_url,
+            mut player_config,
+        } = MediaMetadata::resolve(
+            &metadata_source,
+            &self.config,
             self.player.identity(),
             &player_bus_name,
-            track_url_ref,
-            media_metadata.title.as_deref(),
         );
+        let track_url_ref = track_url.as_deref();
 
-        // Strip the matched title suffix (e.g. " | YouTube Music") from the
-        // displayed title so Discord shows only the track name.
-        if let Some(ref suffix) = title_suffix {
-            if let Some(ref mut title) = media_metadata.title {
-                if let Some(stripped) = title.strip_suffix(suffix.as_str()) {
-                    *title = stripped.trim_end().to_string();
-                }
-            }
+        // [[filter]] rules see the stripped title, i.e. what Discord would show.
+        let filter = media_metadata.apply_filter(&self.config, track_url_ref, &mut player_config);
+        // A hidden or redacted track's lyrics would give it away. Dropping
+        // the loaded lyrics also stops lyric ticks from re-rendering it.
+        let lyric_line = if filter
+            .as_ref()
+            .is_some_and(|rule| rule.action != FilterAction::Template)
+        {
+            self.lyrics = None;
+            None
+        } else {
+            self.lyric_line(&metadata_source)
+        };
+        if filter
+            .as_ref()
+            .is_some_and(|rule| rule.action == FilterAction::Hide)
+        {
+            return self.clear_activity_with_reason(&format!(
+                "Hiding Discord activity for {} - track matched a [[filter]] rule",
+                self.player.identity()
+            ));
         }
+        let redact_cover = filter
+            .as_ref()
+            .is_some_and(|rule| rule.redacts(RedactField::Cover));
 
         let snapshot_matches = self
             .last_rendered_snapshot
@@ -949,15 +1187,21 @@ impl Presence {
 
         debug!("Resolved MediaMetadata: {:?}", media_metadata);
 
-        let activity_texts = if snapshot_matches && volume == self.last_rendered_volume {
+        let (activity_texts, render_context) = if snapshot_matches
+            && !position_changes_texts
+            && volume == self.last_rendered_volume
+            && lyric_line == self.last_rendered_lyric_line
+        {
             // Fast path: nothing that affects template output has changed.
             // Reuse the previously rendered texts — saves 4 Handlebars renders.
-            if let Some(ref cached) = self.last_activity_texts {
+            if let (Some(texts), Some(context)) =
+                (&self.last_activity_texts, &self.last_render_context)
+            {
                 trace!(
                     "Skipping template rendering — snapshot and volume unchanged for {}",
                     self.player.identity()
                 );
-                cached.clone()
+                (texts.clone(), context.clone())
             } else {
                 // Cache was populated by a previous push for this snapshot/volume
                 // (first tick on this track). Fall through to render below.
@@ -967,6 +1211,7 @@ impl Presence {
                     &player_config,
                     &update_snapshot,
                     volume,
+                    lyric_line,
                 )?
             }
         } else {
@@ -976,6 +1221,7 @@ impl Presence {
                 &player_config,
                 &update_snapshot,
                 volume,
+                lyric_line,
             )?
         };
 
@@ -984,10 +1230,10 @@ impl Presence {
         // Reconcile the Discord IPC client when the resolved app id changes
         // (e.g. the active [web_player.*] overlay matched a different service).
         let new_app_id = player_config.app_id.clone();
-        let needs_app_swap = {
-            let last = self.last_effective_app_id.lock();
-            last.as_deref() != Some(new_app_id.as_str())
-        };
+        let needs_app_swap = self
+            .discord
+            .as_ref()
+            .is_some_and(|discord| discord.app_id() != new_app_id);
         if needs_app_swap {
             debug!(
                 "Effective Discord app id changed to {} (recycling IPC client)",
@@ -998,24 +1244,15 @@ impl Presence {
             self.ensure_connection()?;
         }
 
-        let Some(discord_client) = self.discord_client.clone() else {
-            trace!("No Discord client available, skipping activity push");
-            return Ok(());
-        };
+        let discord = self.discord.clone();
 
         if !player_config.allow_streaming && track_url_ref.is_some_and(utils::is_streaming_url) {
             info!(
                 "Skipping Discord activity - streaming source blocked for player {}",
                 self.player.identity()
             );
-            discord_client.lock().clear_activity().map_err(|err| {
-                if !self.error_logged.load(Ordering::Relaxed) {
-                    error!("Failed to clear Discord activity: {}", err);
-                    self.error_logged.store(true, Ordering::Relaxed);
-                }
-                DiscordError::ActivityError(err.to_string())
-            })?;
-            return Ok(());
+            self.publishes.store(false, Ordering::Relaxed);
+            return self.clear_outputs();
         }
         let as_elapsed = self.config.time_config().as_elapsed;
 
@@ -1061,6 +1298,7 @@ impl Presence {
             track_url_ref,
         );
         let status_display_type = resolve_status_display_type(&player_config);
+        self.last_activity_type = Some(activity_type);
 
         // Fast path: try a sync, in-process cache lookup so a cached cover
         // attaches to the very first push. Cache miss → push immediately with
@@ -1113,13 +1351,19 @@ impl Presence {
                 current_generation, cover_url
             );
         }
-        let cover_for_push = cached_cover
-            .as_deref()
-            .or(direct_cover.as_deref())
-            .or(remembered_cover.as_deref());
+        let cover_for_push = if redact_cover {
+            None
+        } else {
+            cached_cover
+                .as_deref()
+                .or(direct_cover)
+                .or(remembered_cover.as_deref())
+        };
         debug!(
             "Artwork source for push: {}",
-            if cover_for_push.is_none() {
+            if redact_cover {
+                "none (redacted by filter)"
+            } else if cover_for_push.is_none() {
                 "none (placeholder)"
             } else if cached_cover.is_some() {
                 "cached_cover"
@@ -1141,6 +1385,28 @@ impl Presence {
             return Ok(());
         }
 
+        self.publishes.store(true, Ordering::Relaxed);
+        if self.paused.load(Ordering::Relaxed) {
+            return self.hold_back_activity(&format!(
+                "Presence paused - not publishing activity for {}",
+                self.player.identity()
+            ));
+        }
+
+        if self.outranked.load(Ordering::Relaxed) {
+            if self.config.arbitration_config().losers == LoserAction::Clear {
+                return self.hold_back_activity(&format!(
+                    "Clearing Discord activity for {} - outranked by another player",
+                    self.player.identity()
+                ));
+            }
+            trace!(
+                "Holding back activity for {} - outranked by another player",
+                self.player.identity()
+            );
+            return Ok(());
+        }
+
         if !self.error_logged.load(Ordering::Relaxed) {
             debug!("Updating Discord activity");
             debug!(
@@ -1153,25 +1419,39 @@ impl Presence {
                 activity_type,
             );
         }
-        Self::build_and_push_activity(
-            &discord_client,
-            &ActivityFraming {
-                texts: &activity_texts,
-                timing: start_s.map(|s| (s, end_s)),
-                cover_art_url: cover_for_push,
-                player_config: &player_config,
-                activity_type,
-                status_display_type,
+        let sink_activity = SinkActivity {
+            player: self.sink_player(),
+            activity_type,
+            status_display_type,
+            details: activity_texts.details.clone(),
+            state: activity_texts.state.clone(),
+            large_text: activity_texts.large_text.clone(),
+            small_text: activity_texts.small_text.clone(),
+            buttons: activity_texts.buttons.clone(),
+            cover_url: cover_for_push.map(str::to_string),
+            icon: player_config.icon.clone(),
+            show_icon: player_config.show_icon,
+            timestamps: start_s.map(|start| SinkTimestamps { start, end: end_s }),
+            context: render_context,
+            local_cover: if redact_cover || !self.sinks.wants_local_cover() {
+                None
+            } else {
+                metadata_source
+                    .art_source_with_options(art_decision.source_options)
+                    .filter(|source| !matches!(source, ArtSource::Url(_)))
+                    .map(Arc::new)
             },
-        )
-        .map_err(|err| {
+        };
+        Self::publish_activity(discord.as_deref(), &self.sinks, &sink_activity).map_err(|err| {
             if !self.error_logged.load(Ordering::Relaxed) {
                 error!("Failed to set Discord activity: {}", err);
                 self.error_logged.store(true, Ordering::Relaxed);
             }
+            self.publishes.store(false, Ordering::Relaxed);
             err
         })?;
-        self.discord_activity_is_set.store(true, Ordering::Relaxed);
+        self.activity_is_set.store(true, Ordering::Relaxed);
+        self.last_published_at = Some(Instant::now());
         if !self.error_logged.load(Ordering::Relaxed) {
             info!(
                 "Updated Discord activity for {} - {} ({:?})",
@@ -1187,7 +1467,7 @@ impl Presence {
         // equal generation.  Uses generation-based gating so rapid track skips
         // allow the newest track to preempt older in-flight fetches.
         let spawn_gen = current_generation;
-        if cover_for_push.is_none() && {
+        if cover_for_push.is_none() && !redact_cover && {
             let mut spawned = false;
             let mut skip_reason: Option<(u64, u64)> = None;
             loop {
@@ -1221,21 +1501,24 @@ impl Presence {
             spawned
         } {
             let cover_manager = Arc::clone(&self.cover_manager);
-            let discord_client_for_task = Arc::clone(&discord_client);
+            let discord_for_task = discord.clone();
+            let sinks_for_task = Arc::clone(&self.sinks);
             let update_generation = Arc::clone(&self.update_generation);
             let cancel_token = {
                 let token = self.cover_cancel_token.lock();
                 token.clone()
             };
             let last_resolved_cover_art_for_task = Arc::clone(&self.last_resolved_cover_art);
-            let texts_for_task = activity_texts.clone();
+            let mut activity_for_task = sink_activity;
             let player_config_for_task = player_config.clone();
             let identity_for_task = self.player.identity().to_string();
             let metadata_source_for_task = metadata_source;
             let art_source_options_for_task = art_decision.source_options;
             let read_cache_for_task = art_decision.read_cache;
             let cover_fetch_gen = Arc::clone(&self.cover_fetch_generation);
-            let discord_activity_is_set_for_task = Arc::clone(&self.discord_activity_is_set);
+            let activity_is_set_for_task = Arc::clone(&self.activity_is_set);
+            let paused_for_task = Arc::clone(&self.paused);
+            let outranked_for_task = Arc::clone(&self.outranked);
             // Always use the freshly-loaded generation (post-bump) so this task
             // self-cancels on any subsequent track change in either run mode.
             let fetch_gen = spawn_gen;
@@ -1266,6 +1549,7 @@ impl Presence {
                         art_source.clone(),
                         &metadata_source_for_task,
                         read_cache_for_task,
+                        player_config_for_task.cover.as_ref(),
                         &cancel_token,
                     ) => result,
                     _ = cancel_token.cancelled() => {
@@ -1303,22 +1587,33 @@ impl Presence {
                     return;
                 }
 
+                if paused_for_task.load(Ordering::Relaxed) {
+                    trace!(
+                        "background cover result discarded: presence paused for {}",
+                        identity_for_task
+                    );
+                    return;
+                }
+
+                if outranked_for_task.load(Ordering::Relaxed) {
+                    trace!(
+                        "background cover result discarded: {} is outranked",
+                        identity_for_task
+                    );
+                    return;
+                }
+
                 trace!("Found cover art URL for Discord presence: {}", cover_url);
                 debug!(
                     "Artwork source for push: background_fetch generation={} url={}",
                     spawn_gen, cover_url
                 );
                 *last_resolved_cover_art_for_task.lock() = Some((spawn_gen, cover_url.clone()));
-                if let Err(err) = Self::build_and_push_activity(
-                    &discord_client_for_task,
-                    &ActivityFraming {
-                        texts: &texts_for_task,
-                        timing: start_s.map(|s| (s, end_s)),
-                        cover_art_url: Some(cover_url.as_str()),
-                        player_config: &player_config_for_task,
-                        activity_type,
-                        status_display_type,
-                    },
+                activity_for_task.cover_url = Some(cover_url);
+                if let Err(err) = Self::publish_activity(
+                    discord_for_task.as_deref(),
+                    &sinks_for_task,
+                    &activity_for_task,
                 ) {
                     warn!(
                         "Failed to push cover art update for {}: {}",
@@ -1327,9 +1622,9 @@ impl Presence {
                 } else {
                     info!(
                         "Updated Discord cover art for {} - {}",
-                        identity_for_task, texts_for_task.details
+                        identity_for_task, activity_for_task.details
                     );
-                    discord_activity_is_set_for_task.store(true, Ordering::Relaxed);
+                    activity_is_set_for_task.store(true, Ordering::Relaxed);
                 }
             });
         }
@@ -1346,77 +1641,45 @@ impl Presence {
         player_config: &PlayerConfig,
         snapshot: &UpdateSnapshot,
         volume: Option<f64>,
-    ) -> Result<ActivityTexts, DiscordError> {
+        lyric_line: Option<String>,
+    ) -> Result<(ActivityTexts, RenderContext), DiscordError> {
         trace!(
             "Rendering templates — snapshot or volume changed for {}",
             self.player.identity()
         );
-        let texts = self.template_manager.render_activity_texts(
+        let mut context = RenderContext::new(
             &self.player,
             playback_status,
             metadata.clone(),
             player_config.name.as_deref(),
-        )?;
+        );
+        context.lyric_line = lyric_line.clone();
+        let texts = self
+            .template_manager
+            .render_activity_texts(&context, player_config)?;
         self.last_rendered_snapshot = Some(snapshot.clone());
         self.last_rendered_volume = volume;
+        self.last_rendered_lyric_line = lyric_line;
         self.last_activity_texts = Some(texts.clone());
-        Ok(texts)
+        self.last_render_context = Some(context.clone());
+        Ok((texts, context))
     }
 
-    /// Build a Discord `Activity` and push it. Callable from both the fast
-    /// path (event handler) and the slow path (background cover fetch task)
-    /// because it captures no `&self` state — all inputs are owned or
-    /// `Arc`-shared.
-    fn build_and_push_activity(
-        discord_client: &Arc<Mutex<DiscordIpcClient>>,
-        framing: &ActivityFraming<'_>,
+    /// Push `activity` to every `[[sink]]` and Discord. Callable from both
+    /// the fast path (event handler) and the slow path (background cover
+    /// fetch task) because it captures no `&self` state. Only a Discord
+    /// failure is returned, since it drives reconnection; sinks log their own.
+    fn publish_activity(
+        discord: Option<&DiscordSink>,
+        sinks: &SinkManager,
+        activity: &SinkActivity,
     ) -> Result<(), DiscordError> {
-        let mut activity = Activity::default()
-            .activity_type(framing.activity_type.into())
-            .status_display_type(framing.status_display_type.into());
-
-        if !framing.texts.details.is_empty() {
-            activity = activity.details(&framing.texts.details);
-        }
-        if !framing.texts.state.is_empty() {
-            activity = activity.state(&framing.texts.state);
-        }
-
-        if let Some((start, end)) = framing.timing {
-            activity = activity.timestamps({
-                let ts = Timestamps::default().start(start as i64);
-                if let Some(end) = end {
-                    ts.end(end as i64)
-                } else {
-                    ts
-                }
-            });
-        }
-
-        let mut assets = Assets::default();
-        if let Some(img_url) = framing.cover_art_url {
-            assets = assets.large_image(img_url);
-            if !framing.texts.large_text.is_empty() {
-                assets = assets.large_text(&framing.texts.large_text);
-            }
-            if framing.player_config.show_icon {
-                assets = assets.small_image(framing.player_config.icon.as_str());
-                if !framing.texts.small_text.is_empty() {
-                    assets = assets.small_text(&framing.texts.small_text);
-                }
-            }
-        } else {
-            assets = assets.large_image(framing.player_config.icon.as_str());
-            if !framing.texts.large_text.is_empty() {
-                assets = assets.large_text(&framing.texts.large_text);
-            }
+        sinks.publish(activity);
+        if let Some(discord) = discord {
+            discord
+                .publish(activity)
+                .map_err(|err| DiscordError::ActivityError(err.to_string()))?;
         }
-        activity = activity.assets(assets);
-
-        discord_client
-            .lock()
-            .set_activity(activity)
-            .map_err(|err| DiscordError::ActivityError(err.to_string()))?;
         Ok(())
     }
 
@@ -1424,6 +1687,7 @@ impl Presence {
         &mut self,
         template_manager: Arc<TemplateManager>,
         cover_manager: Arc<CoverManager>,
+        sinks: Arc<SinkManager>,
         config: Arc<ConfigManager>,
     ) {
         trace!(
@@ -1432,11 +1696,13 @@ impl Presence {
         );
         self.template_manager = template_manager;
         self.cover_manager = cover_manager;
+        self.sinks = sinks;
         self.config = config;
         trace!("Presence managers updated successfully");
 
         self.last_player_state = None;
-        self.cmus.reset();
+        self.file_resolution.reset();
+        self.lyrics = None;
         *self.last_pushed_track_id.lock() = None;
         *self.last_pushed_track_url.lock() = None;
         *self.last_pushed_art_url.lock() = None;
@@ -1510,9 +1776,9 @@ impl Presence {
             self.cover_fetch_generation.store(0, Ordering::Release);
         }
 
-        let Some(_discord_client) = &self.discord_client else {
+        if !self.has_output() {
             return Ok(EventOutcome::Continue);
-        };
+        }
 
         self.ensure_connection()?;
 
@@ -1562,7 +1828,7 @@ impl Presence {
                     art_decision = ad;
                 }
                 health::TransitionOutcome::Clear => {
-                    self.clear_discord_activity_with_reason(&format!(
+                    self.clear_activity_with_reason(&format!(
                         "Clearing Discord activity - player {} is stalled/stopped/paused (event)",
                         self.player.identity()
                     ))?;
@@ -1658,17 +1924,29 @@ impl Presence {
             self.stop_listener();
         }
         let cancel = Arc::new(AtomicBool::new(false));
-        // The JoinHandle is intentionally dropped — the listener thread blocks
-        // on a D-Bus call we can't interrupt, so it's detached and exits on its
-        // own once `cancel` flips or the player disappears.
-        let _ = events::spawn_listener(
-            current_bus.clone(),
-            norm_id,
-            tx,
-            cancel.clone(),
-            self.update_generation.clone(),
-            self.update_notify.clone(),
-        );
+        match &self.player {
+            // The JoinHandle is intentionally dropped — the listener thread blocks
+            // on a D-Bus call we can't interrupt, so it's detached and exits on its
+            // own once `cancel` flips or the player disappears.
+            MediaPlayer::Mpris(_) => {
+                let _ = events::spawn_listener(
+                    current_bus.clone(),
+                    norm_id,
+                    tx,
+                    cancel.clone(),
+                    self.update_generation.clone(),
+                    self.update_notify.clone(),
+                );
+            }
+            MediaPlayer::Mpd(player) => events::spawn_mpd_listener(
+                player.clone(),
+                norm_id,
+                tx,
+                cancel.clone(),
+                self.update_generation.clone(),
+                self.update_notify.clone(),
+            ),
+        }
         self.listener_cancel = Some(cancel);
         self.listener_bus = Some(current_bus);
     }
@@ -1689,6 +1967,10 @@ impl Presence {
 impl Drop for Presence {
     fn drop(&mut self) {
         self.stop_listener();
+        // Discord drops the activity with the connection; sinks have to be told.
+        if self.activity_is_set.load(Ordering::Relaxed) {
+            self.sinks.clear(&self.sink_player());
+        }
     }
 }
 
@@ -1765,4 +2047,86 @@ mod tests {
         assert!(summary.len() < data_url.len());
         assert!(!summary.contains(&"A".repeat(300)));
     }
+
+    #[tokio::test]
+    async fn a_player_cleared_by_an_event_loses_the_activity_to_a_playing_one() {
+        use crate::config::schema::{ArbitrationConfig, ArbitrationRule};
+        use crate::mpd::{client::Song, MpdPlayer};
+        use crate::player::arbitration::{Arbiter, Contender};
+
+        let dir = std::env::temp_dir().join(format!("mprisence-cleared-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let toml = format!(
+            "[[sink]]\ntype = \"jsonl\"\npath = {:?}\n",
+            dir.join("presence.jsonl")
+        );
+        let config = Arc::new(ConfigManager::new_with_config(
+            crate::config::parse_config_str(&toml).unwrap(),
+        ));
+
+        let song = Song {
+            file: "song.flac".to_string(),
+            id: Some(1),
+            title: Some("Song".to_string()),
+            ..Song::default()
+        };
+        let mpd = MpdPlayer::reporting(PlaybackStatus::Playing, song.clone());
+        let mut presence = Presence::new(
+            MediaPlayer::Mpd(mpd.clone()),
+            Arc::new(TemplateManager::new_raw("", "", "", "").unwrap()),
+            Arc::new(CoverManager::in_dir(&config, dir.join("covers"))),
+            Arc::new(SinkManager::new(&config)),
+            config.clone(),
+            Arc::new(AtomicBool::new(false)),
+        );
+        // As after a published update.
+        presence.last_player_state = Some(PlaybackState::from(&presence.player));
+        presence.publishes.store(true, Ordering::Relaxed);
+        presence.activity_is_set.store(true, Ordering::Relaxed);
+
+        let contenders = |presence: &Presence| {
+            [
+                Contender {
+                    key: "mpd".into(),
+                    identity: presence.player_id().identity.clone(),
+                    bus_name: presence.player_id().player_bus_name.clone(),
+                    status: presence.playback_status(),
+                    activity_type: presence.activity_type(),
+                    publishes: presence.publishes(),
+                },
+                Contender {
+                    key: "vlc".into(),
+                    identity: "VLC media player".into(),
+                    bus_name: "org.mpris.MediaPlayer2.vlc".into(),
+                    status: Some(PlaybackStatus::Playing),
+                    activity_type: None,
+                    publishes: true,
+                },
+            ]
+        };
+        // MPD is preferred, so only having nothing to show can cost it the
+        // activity.
+        let arbitration = ArbitrationConfig {
+            enabled: true,
+            order: vec![ArbitrationRule::Priority],
+            priority: vec![mpd.identity().to_string()],
+            losers: LoserAction::Clear,
+        };
+        let mut arbiter = Arbiter::default();
+        let standings = arbiter.arbitrate(&arbitration, &contenders(&presence));
+        assert_eq!(standings["mpd"], Standing::Owner);
+
+        mpd.report(PlaybackStatus::Stopped, song);
+        let track_changed = MprisEvent::TrackChanged(mpd.get_metadata().unwrap());
+        presence
+            .handle_event(PlayerEventKind::Mpris(track_changed))
+            .await
+            .unwrap();
+        assert!(!presence.publishes());
+
+        let standings = arbiter.arbitrate(&arbitration, &contenders(&presence));
+        assert_eq!(standings["vlc"], Standing::Owner);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,43 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ScrobbleError {
+    #[error("Network error: {0}")]
+    Network(String),
+
+    /// The service is down or rate limiting; the same request may succeed
+    /// later.
+    #[error("{service} temporarily unavailable: {message}")]
+    Unavailable {
+        service: &'static str,
+        message: String,
+    },
+
+    /// Missing or revoked credentials. Queued scrobbles are kept until the
+    /// configuration is fixed.
+    #[error("{service} rejected the credentials: {message}")]
+    Auth {
+        service: &'static str,
+        message: String,
+    },
+
+    /// The request itself was refused and retrying it will not help.
+    #[error("{service} rejected the request: {message}")]
+    Rejected {
+        service: &'static str,
+        message: String,
+    },
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+}
+
+impl ScrobbleError {
+    /// Whether the request should stay queued for another attempt.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Self::Rejected { .. })
+    }
+}
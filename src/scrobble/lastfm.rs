@@ -0,0 +1,364 @@
+//! Last.fm 2.0 API client. Libre.fm and other Audioscrobbler-compatible
+//! services work by pointing `api_url` at them.
+
+use async_trait::async_trait;
+use log::{debug, info};
+use md5::{Digest, Md5};
+use reqwest::{header, Client};
+use serde_json::Value;
+
+use super::{create_client, error::ScrobbleError, Scrobble, ScrobbleService};
+use crate::config::schema::LastfmConfig;
+
+const SERVICE: &str = "lastfm";
+
+/// `track.scrobble` takes at most 50 tracks per request.
+const MAX_BATCH: usize = 50;
+
+pub struct LastfmClient {
+    client: Client,
+    api_url: String,
+    api_key: String,
+    api_secret: String,
+    session_key: Option<String>,
+}
+
+impl LastfmClient {
+    /// Needs `api_key` and `api_secret`; `session_key` is only required for
+    /// submissions, not for `auth`.
+    pub fn from_config(config: &LastfmConfig) -> Result<Self, ScrobbleError> {
+        let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.trim().is_empty());
+        let (Some(api_key), Some(api_secret)) =
+            (non_empty(&config.api_key), non_empty(&config.api_secret))
+        else {
+            return Err(ScrobbleError::Auth {
+                service: SERVICE,
+                message: "api_key and api_secret must be set".to_string(),
+            });
+        };
+        Ok(Self {
+            client: create_client(),
+            api_url: config.api_url.clone(),
+            api_key,
+            api_secret,
+            session_key: non_empty(&config.session_key),
+        })
+    }
+
+    /// First step of desktop auth: a token the user approves in the browser.
+    pub async fn request_token(&self) -> Result<String, ScrobbleError> {
+        let response = self.call("auth.getToken", Vec::new()).await?;
+        response["token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| self.rejected("auth.getToken returned no token"))
+    }
+
+    pub fn approval_url(&self, auth_url: &str, token: &str) -> String {
+        format!("{auth_url}?api_key={}&token={token}", self.api_key)
+    }
+
+    /// Exchange an approved token for `(username, session key)`.
+    pub async fn fetch_session(&self, token: &str) -> Result<(String, String), ScrobbleError> {
+        let response = self
+            .call("auth.getSession", vec![("token".into(), token.into())])
+            .await?;
+        let session = &response["session"];
+        match (session["name"].as_str(), session["key"].as_str()) {
+            (Some(name), Some(key)) => Ok((name.to_string(), key.to_string())),
+            _ => Err(self.rejected("auth.getSession returned no session")),
+        }
+    }
+
+    fn session_key(&self) -> Result<&str, ScrobbleError> {
+        self.session_key
+            .as_deref()
+            .ok_or_else(|| ScrobbleError::Auth {
+                service: SERVICE,
+                message: "no session_key configured; run `mprisence scrobble auth`".to_string(),
+            })
+    }
+
+    fn rejected(&self, message: &str) -> ScrobbleError {
+        ScrobbleError::Rejected {
+            service: SERVICE,
+            message: message.to_string(),
+        }
+    }
+
+    /// POST a signed method call and return the decoded response.
+    async fn call(
+        &self,
+        method: &str,
+        mut params: Vec<(String, String)>,
+    ) -> Result<Value, ScrobbleError> {
+        params.push(("method".into(), method.into()));
+        params.push(("api_key".into(), self.api_key.clone()));
+        let signature = sign(&params, &self.api_secret);
+        params.push(("api_sig".into(), signature));
+        params.push(("format".into(), "json".into()));
+
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&params)
+            .finish();
+        debug!("Calling {} at {}", method, self.api_url);
+        let response = self
+            .client
+            .post(&self.api_url)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ScrobbleError::Network(format!("{SERVICE}: {e}")))?;
+        let status = response.status();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| ScrobbleError::Network(format!("{SERVICE}: {e}")))?;
+
+        match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) if value.get("error").is_some() => Err(api_error(&value)),
+            Ok(value) if status.is_success() => Ok(value),
+            _ if status.is_server_error() || status.as_u16() == 429 => {
+                Err(ScrobbleError::Unavailable {
+                    service: SERVICE,
+                    message: format!("{method} failed with {status}"),
+                })
+            }
+            Ok(_) => Err(self.rejected(&format!("{method} failed with {status}"))),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// `api_sig`: md5 over the parameters sorted by name, each name directly
+/// followed by its value, then the shared secret.
+fn sign(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<&(String, String)> = params.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut hasher = Md5::new();
+    for (key, value) in sorted {
+        hasher.update(key.as_bytes());
+        hasher.update(value.as_bytes());
+    }
+    hasher.update(secret.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Map documented error codes onto retry behaviour.
+fn api_error(value: &Value) -> ScrobbleError {
+    let code = value["error"].as_u64().unwrap_or(0);
+    let message = format!(
+        "error {code}: {}",
+        value["message"].as_str().unwrap_or("unknown error")
+    );
+    match code {
+        // Service offline, temporarily unavailable, rate limit exceeded.
+        11 | 16 | 29 => ScrobbleError::Unavailable {
+            service: SERVICE,
+            message,
+        },
+        // Authentication failed, invalid session, invalid or suspended key,
+        // unauthorized or expired token.
+        4 | 9 | 10 | 14 | 15 | 26 => ScrobbleError::Auth {
+            service: SERVICE,
+            message,
+        },
+        _ => ScrobbleError::Rejected {
+            service: SERVICE,
+            message,
+        },
+    }
+}
+
+/// Track fields, suffixed with `[index]` for batched scrobbles.
+fn track_params(track: &Scrobble, index: Option<usize>) -> Vec<(String, String)> {
+    let key = |name: &str| match index {
+        Some(i) => format!("{name}[{i}]"),
+        None => name.to_string(),
+    };
+    let mut params = vec![
+        (key("artist"), track.artist.clone()),
+        (key("track"), track.track.clone()),
+    ];
+    let optional = [
+        ("album", track.album.clone()),
+        ("albumArtist", track.album_artist.clone()),
+        ("trackNumber", track.track_number.map(|n| n.to_string())),
+        ("duration", track.duration_secs.map(|d| d.to_string())),
+        ("mbid", track.musicbrainz_track_id.clone()),
+    ];
+    params.extend(
+        optional
+            .into_iter()
+            .filter_map(|(name, value)| Some((key(name), value?))),
+    );
+    params
+}
+
+#[async_trait]
+impl ScrobbleService for LastfmClient {
+    fn name(&self) -> &'static str {
+        SERVICE
+    }
+
+    fn max_batch(&self) -> usize {
+        MAX_BATCH
+    }
+
+    async fn now_playing(&self, track: &Scrobble) -> Result<(), ScrobbleError> {
+        let mut params = track_params(track, None);
+        params.push(("sk".into(), self.session_key()?.to_string()));
+        self.call("track.updateNowPlaying", params).await?;
+        debug!(
+            "Sent now playing to Last.fm: {} - {}",
+            track.artist, track.track
+        );
+        Ok(())
+    }
+
+    async fn scrobble(&self, batch: &[Scrobble]) -> Result<(), ScrobbleError> {
+        let mut params = vec![("sk".to_string(), self.session_key()?.to_string())];
+        for (i, track) in batch.iter().enumerate() {
+            params.extend(track_params(track, Some(i)));
+            params.push((format!("timestamp[{i}]"), track.timestamp.to_string()));
+        }
+        let response = self.call("track.scrobble", params).await?;
+        let attr = &response["scrobbles"]["@attr"];
+        info!(
+            "Last.fm accepted {} of {} scrobbles ({} ignored)",
+            attr["accepted"],
+            batch.len(),
+            attr["ignored"]
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::spawn_server;
+    use std::collections::HashMap;
+
+    fn client(api_url: &str, session_key: Option<&str>) -> LastfmClient {
+        LastfmClient::from_config(&LastfmConfig {
+            enabled: true,
+            api_url: api_url.to_string(),
+            api_key: Some("key".to_string()),
+            api_secret: Some("secret".to_string()),
+            session_key: session_key.map(str::to_string),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn form(body: &str) -> HashMap<String, String> {
+        url::form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect()
+    }
+
+    fn track(timestamp: u64) -> Scrobble {
+        Scrobble {
+            artist: "Daft Punk".to_string(),
+            track: "Get Lucky".to_string(),
+            album: Some("Random Access Memories".to_string()),
+            duration_secs: Some(369),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn signs_sorted_parameters_with_secret() {
+        let params = vec![
+            ("method".to_string(), "auth.getToken".to_string()),
+            ("api_key".to_string(), "key".to_string()),
+        ];
+        // md5("api_keykeymethodauth.getTokensecret")
+        assert_eq!(sign(&params, "secret"), "b4705499705a550b07ca058a15bde9b0");
+    }
+
+    #[tokio::test]
+    async fn submits_batched_scrobbles_with_signature() {
+        let (api, rx) = spawn_server(vec![(
+            200,
+            r#"{"scrobbles":{"@attr":{"accepted":2,"ignored":0}}}"#,
+        )]);
+        client(&api, Some("session"))
+            .scrobble(&[track(1_700_000_000), track(1_700_000_400)])
+            .await
+            .unwrap();
+
+        let form = form(&rx.recv().unwrap().body_text());
+        assert_eq!(form["method"], "track.scrobble");
+        assert_eq!(form["sk"], "session");
+        assert_eq!(form["artist[1]"], "Daft Punk");
+        assert_eq!(form["timestamp[1]"], "1700000400");
+        assert_eq!(form["duration[0]"], "369");
+        assert_eq!(form["format"], "json");
+
+        let mut params: Vec<(String, String)> = form
+            .iter()
+            .filter(|(k, _)| !matches!(k.as_str(), "api_sig" | "format"))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        params.sort();
+        assert_eq!(form["api_sig"], sign(&params, "secret"));
+    }
+
+    #[tokio::test]
+    async fn classifies_api_errors() {
+        let (api, _rx) = spawn_server(vec![
+            (200, r#"{"error":9,"message":"Invalid session key"}"#),
+            (503, r#"{"error":16,"message":"Temporarily unavailable"}"#),
+            (400, r#"{"error":6,"message":"Invalid parameters"}"#),
+            (502, "<html>Bad Gateway</html>"),
+        ]);
+        let lastfm = client(&api, Some("session"));
+        let errors = [
+            lastfm.now_playing(&track(0)).await.unwrap_err(),
+            lastfm.now_playing(&track(0)).await.unwrap_err(),
+            lastfm.now_playing(&track(0)).await.unwrap_err(),
+            lastfm.now_playing(&track(0)).await.unwrap_err(),
+        ];
+        assert!(matches!(errors[0], ScrobbleError::Auth { .. }));
+        assert!(matches!(errors[1], ScrobbleError::Unavailable { .. }));
+        assert!(matches!(errors[2], ScrobbleError::Rejected { .. }));
+        assert!(matches!(errors[3], ScrobbleError::Unavailable { .. }));
+
+        let unauthenticated = client(&api, None);
+        assert!(matches!(
+            unauthenticated.now_playing(&track(0)).await,
+            Err(ScrobbleError::Auth { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn exchanges_token_for_session() {
+        let (api, rx) = spawn_server(vec![
+            (200, r#"{"token":"tok"}"#),
+            (
+                200,
+                r#"{"session":{"name":"someone","key":"sk-123","subscriber":0}}"#,
+            ),
+        ]);
+        let lastfm = client(&api, None);
+        let token = lastfm.request_token().await.unwrap();
+        assert_eq!(
+            lastfm.approval_url("https://www.last.fm/api/auth/", &token),
+            "https://www.last.fm/api/auth/?api_key=key&token=tok"
+        );
+        assert_eq!(
+            lastfm.fetch_session(&token).await.unwrap(),
+            ("someone".to_string(), "sk-123".to_string())
+        );
+        rx.recv().unwrap();
+        assert_eq!(form(&rx.recv().unwrap().body_text())["token"], "tok");
+    }
+}
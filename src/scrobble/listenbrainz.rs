@@ -0,0 +1,302 @@
+//! ListenBrainz `submit-listens` client.
+
+use async_trait::async_trait;
+use log::{debug, info};
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Map, Value};
+
+use super::{create_client, error::ScrobbleError, Scrobble, ScrobbleService};
+use crate::config::schema::ListenbrainzConfig;
+
+const SERVICE: &str = "listenbrainz";
+
+/// The API allows 1000 listens per request; smaller batches keep request
+/// bodies well under its size limit.
+const MAX_BATCH: usize = 100;
+
+pub struct ListenbrainzClient {
+    client: Client,
+    api_url: String,
+    token: String,
+}
+
+impl ListenbrainzClient {
+    pub fn from_config(config: &ListenbrainzConfig) -> Result<Self, ScrobbleError> {
+        let Some(token) = config.token.clone().filter(|t| !t.trim().is_empty()) else {
+            return Err(ScrobbleError::Auth {
+                service: SERVICE,
+                message: "token must be set".to_string(),
+            });
+        };
+        Ok(Self {
+            client: create_client(),
+            api_url: config.api_url.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+
+    async fn submit(&self, listen_type: &str, payload: Vec<Value>) -> Result<(), ScrobbleError> {
+        let url = format!("{}/1/submit-listens", self.api_url);
+        debug!(
+            "Submitting {} {} listens to {}",
+            payload.len(),
+            listen_type,
+            url
+        );
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Token {}", self.token))
+            .json(&json!({ "listen_type": listen_type, "payload": payload }))
+            .send()
+            .await
+            .map_err(|e| ScrobbleError::Network(format!("{SERVICE}: {e}")))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|value| value["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| format!("submit-listens failed with {status}"));
+        Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ScrobbleError::Auth {
+                service: SERVICE,
+                message,
+            },
+            StatusCode::TOO_MANY_REQUESTS => ScrobbleError::Unavailable {
+                service: SERVICE,
+                message,
+            },
+            status if status.is_server_error() => ScrobbleError::Unavailable {
+                service: SERVICE,
+                message,
+            },
+            _ => ScrobbleError::Rejected {
+                service: SERVICE,
+                message,
+            },
+        })
+    }
+}
+
+/// MusicBrainz IDs are UUIDs. The API rejects a whole listen over one
+/// malformed ID, so anything else found in tags is left out.
+fn is_mbid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// `track_metadata` for one listen. Unknown fields are left out rather than
+/// sent empty.
+fn track_metadata(track: &Scrobble) -> Value {
+    let mbid = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| is_mbid(id))
+            .map(Value::from)
+    };
+    let artist_mbids: Vec<&str> = track
+        .musicbrainz_artist_ids
+        .iter()
+        .map(String::as_str)
+        .filter(|id| is_mbid(id))
+        .collect();
+
+    let mut info = Map::new();
+    let mut insert = |key: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            info.insert(key.to_string(), value);
+        }
+    };
+    insert("recording_mbid", mbid(&track.musicbrainz_track_id));
+    insert("release_mbid", mbid(&track.musicbrainz_album_id));
+    insert(
+        "release_group_mbid",
+        mbid(&track.musicbrainz_release_group_id),
+    );
+    insert(
+        "artist_mbids",
+        (!artist_mbids.is_empty()).then(|| Value::from(artist_mbids)),
+    );
+    insert("isrc", track.isrc.clone().map(Value::from));
+    insert(
+        "duration_ms",
+        track.duration_secs.map(|secs| Value::from(secs * 1000)),
+    );
+    insert("tracknumber", track.track_number.map(Value::from));
+    insert(
+        "media_player",
+        (!track.player.is_empty()).then(|| Value::from(track.player.clone())),
+    );
+    insert("submission_client", Some(Value::from("mprisence")));
+    insert(
+        "submission_client_version",
+        Some(Value::from(env!("CARGO_PKG_VERSION"))),
+    );
+
+    let mut metadata = json!({
+        "artist_name": track.artist,
+        "track_name": track.track,
+        "additional_info": info,
+    });
+    if let Some(album) = &track.album {
+        metadata["release_name"] = Value::from(album.clone());
+    }
+    metadata
+}
+
+#[async_trait]
+impl ScrobbleService for ListenbrainzClient {
+    fn name(&self) -> &'static str {
+        SERVICE
+    }
+
+    fn max_batch(&self) -> usize {
+        MAX_BATCH
+    }
+
+    async fn now_playing(&self, track: &Scrobble) -> Result<(), ScrobbleError> {
+        self.submit(
+            "playing_now",
+            vec![json!({ "track_metadata": track_metadata(track) })],
+        )
+        .await?;
+        debug!(
+            "Sent playing now to ListenBrainz: {} - {}",
+            track.artist, track.track
+        );
+        Ok(())
+    }
+
+    async fn scrobble(&self, batch: &[Scrobble]) -> Result<(), ScrobbleError> {
+        let payload = batch
+            .iter()
+            .map(|track| {
+                json!({
+                    "listened_at": track.timestamp,
+                    "track_metadata": track_metadata(track),
+                })
+            })
+            .collect();
+        // `single` must carry exactly one listen; backlogs go as `import`.
+        let listen_type = if batch.len() == 1 { "single" } else { "import" };
+        self.submit(listen_type, payload).await?;
+        info!("ListenBrainz accepted {} listens", batch.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::spawn_server;
+
+    fn client(api_url: &str) -> ListenbrainzClient {
+        ListenbrainzClient::from_config(&ListenbrainzConfig {
+            enabled: true,
+            api_url: api_url.to_string(),
+            token: Some("user-token".to_string()),
+        })
+        .unwrap()
+    }
+
+    fn track(timestamp: u64) -> Scrobble {
+        Scrobble {
+            artist: "Daft Punk".to_string(),
+            track: "Get Lucky".to_string(),
+            album: Some("Random Access Memories".to_string()),
+            duration_secs: Some(369),
+            musicbrainz_track_id: Some("8c5e6e0c-4e4d-4a4b-9c6e-1d8c5c7a9f01".to_string()),
+            musicbrainz_artist_ids: vec![
+                "056e4f3e-d505-4dad-8ec1-d04f521cbb56".to_string(),
+                "149f91ef-1287-46fb-9ee4-ebf5c8e2d0d8".to_string(),
+            ],
+            musicbrainz_album_id: Some("not-an-mbid".to_string()),
+            isrc: Some("USQX91300108".to_string()),
+            player: "Elisa".to_string(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_single_and_playing_now_listens_with_mbids() {
+        let (api, rx) = spawn_server(vec![
+            (200, r#"{"status":"ok"}"#),
+            (200, r#"{"status":"ok"}"#),
+        ]);
+        let listenbrainz = client(&api);
+        listenbrainz.now_playing(&track(0)).await.unwrap();
+        listenbrainz
+            .scrobble(&[track(1_700_000_000)])
+            .await
+            .unwrap();
+
+        let request = rx.recv().unwrap();
+        assert_eq!(request.url, "/1/submit-listens");
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["listen_type"], "playing_now");
+        assert!(body["payload"][0].get("listened_at").is_none());
+
+        let body: Value = serde_json::from_slice(&rx.recv().unwrap().body).unwrap();
+        assert_eq!(body["listen_type"], "single");
+        let listen = &body["payload"][0];
+        assert_eq!(listen["listened_at"], 1_700_000_000);
+        let metadata = &listen["track_metadata"];
+        assert_eq!(metadata["artist_name"], "Daft Punk");
+        assert_eq!(metadata["release_name"], "Random Access Memories");
+        let info = &metadata["additional_info"];
+        assert_eq!(
+            info["recording_mbid"],
+            "8c5e6e0c-4e4d-4a4b-9c6e-1d8c5c7a9f01"
+        );
+        assert_eq!(info["artist_mbids"].as_array().unwrap().len(), 2);
+        assert_eq!(info["isrc"], "USQX91300108");
+        assert_eq!(info["duration_ms"], 369_000);
+        assert_eq!(info["media_player"], "Elisa");
+        assert!(info.get("release_mbid").is_none());
+    }
+
+    #[tokio::test]
+    async fn imports_backlogs_and_classifies_errors() {
+        let (api, rx) = spawn_server(vec![
+            (200, r#"{"status":"ok"}"#),
+            (
+                401,
+                r#"{"code":401,"error":"Invalid authorization token."}"#,
+            ),
+            (
+                400,
+                r#"{"code":400,"error":"JSON document may only contain listen_type"}"#,
+            ),
+            (503, "Service Unavailable"),
+        ]);
+        let listenbrainz = client(&api);
+        listenbrainz.scrobble(&[track(1), track(2)]).await.unwrap();
+        let body: Value = serde_json::from_slice(&rx.recv().unwrap().body).unwrap();
+        assert_eq!(body["listen_type"], "import");
+        assert_eq!(body["payload"].as_array().unwrap().len(), 2);
+
+        assert!(matches!(
+            listenbrainz.scrobble(&[track(3)]).await,
+            Err(ScrobbleError::Auth { .. })
+        ));
+        match listenbrainz.scrobble(&[track(3)]).await {
+            Err(ScrobbleError::Rejected { message, .. }) => {
+                assert!(message.contains("listen_type"))
+            }
+            other => panic!("expected rejection, got {other:?}"),
+        }
+        assert!(matches!(
+            listenbrainz.scrobble(&[track(3)]).await,
+            Err(ScrobbleError::Unavailable { .. })
+        ));
+    }
+}
@@ -0,0 +1,861 @@
+//! Listening history submission. A background task samples the allowed,
+//! non-ignored MPRIS players, feeds each into a [`PlayTracker`] and hands
+//! the resulting "now playing" updates and scrobbles to every enabled
+//! service. Scrobbles go through a persistent [`ScrobbleQueue`] first so
+//! they are retried after network or service failures. Nothing is tracked
+//! while presence is hidden or for tracks a `[[filter]]` rule hides or
+//! redacts.
+
+pub mod error;
+pub mod lastfm;
+pub mod listenbrainz;
+pub mod queue;
+pub mod tracker;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use log::{debug, info, trace, warn};
+use mpris::PlaybackStatus;
+use parking_lot::Mutex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::{
+    schema::{FilterAction, ScrobbleConfig},
+    ConfigChange, ConfigManager,
+};
+use crate::metadata::{MediaMetadata, MetadataSource};
+use crate::player::{
+    canonical_player_bus_name, discovery, file_resolver::FileResolutions, health::TrackFingerprint,
+    is_proxy_bus_name, PlayerIdentifier,
+};
+
+use self::error::ScrobbleError;
+use self::lastfm::LastfmClient;
+use self::listenbrainz::ListenbrainzClient;
+use self::queue::ScrobbleQueue;
+use self::tracker::{PlayEvent, PlayTracker};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30 * 60);
+
+const USER_AGENT: &str = concat!(
+    "mprisence/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/lazykern/mprisence)"
+);
+
+/// One play of a track, as submitted and as stored in the retry queue.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scrobble {
+    pub artist: String,
+    pub track: String,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub duration_secs: Option<u64>,
+    pub musicbrainz_track_id: Option<String>,
+    pub musicbrainz_album_id: Option<String>,
+    pub musicbrainz_artist_ids: Vec<String>,
+    pub musicbrainz_release_group_id: Option<String>,
+    pub isrc: Option<String>,
+    /// Unix time the play started.
+    pub timestamp: u64,
+    /// Identity of the player the track was heard on.
+    pub player: String,
+}
+
+impl Scrobble {
+    /// `None` when the metadata lacks a title or artist; such tracks are
+    /// not submitted.
+    pub fn from_metadata(metadata: &MediaMetadata, player: &str) -> Option<Self> {
+        Some(Self {
+            artist: metadata.artist_display.clone()?,
+            track: metadata.title.clone().filter(|t| !t.trim().is_empty())?,
+            album: metadata.album.clone(),
+            album_artist: metadata.album_artist_display.clone(),
+            track_number: metadata.track_number,
+            duration_secs: metadata.duration_secs,
+            musicbrainz_track_id: metadata.musicbrainz_track_id.clone(),
+            musicbrainz_album_id: metadata.musicbrainz_album_id.clone(),
+            // Multi-artist tags hold several IDs in one value.
+            musicbrainz_artist_ids: metadata
+                .musicbrainz_artist_id
+                .as_deref()
+                .unwrap_or_default()
+                .split(|c: char| c == ';' || c == '/' || c == ',' || c.is_whitespace())
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect(),
+            musicbrainz_release_group_id: metadata.musicbrainz_release_group_id.clone(),
+            isrc: metadata.isrc.clone(),
+            timestamp: 0,
+            player: player.to_string(),
+        })
+    }
+}
+
+/// A listening-history service.
+#[async_trait]
+pub trait ScrobbleService: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Largest number of scrobbles accepted in one `scrobble` call.
+    fn max_batch(&self) -> usize;
+
+    async fn now_playing(&self, track: &Scrobble) -> Result<(), ScrobbleError>;
+
+    async fn scrobble(&self, batch: &[Scrobble]) -> Result<(), ScrobbleError>;
+}
+
+pub(crate) fn create_client() -> Client {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(15))
+        .connect_timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// One player's state at a sampling tick.
+pub struct PlayerSample {
+    pub fingerprint: TrackFingerprint,
+    /// Only resolved when the fingerprint differs from the tracked one, so
+    /// tags are not re-read every tick.
+    pub metadata: Option<MediaMetadata>,
+    /// A `[[filter]]` rule keeps the track out of listening history.
+    pub filtered: bool,
+    pub status: PlaybackStatus,
+    pub position: Option<Duration>,
+    pub identity: String,
+}
+
+struct ServiceQueue {
+    service: Box<dyn ScrobbleService>,
+    queue: ScrobbleQueue,
+    retry_at: Option<Instant>,
+    retry_delay: Duration,
+}
+
+impl ServiceQueue {
+    fn new(service: Box<dyn ScrobbleService>, queue: ScrobbleQueue) -> Self {
+        Self {
+            service,
+            queue,
+            retry_at: None,
+            retry_delay: INITIAL_RETRY_DELAY,
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = self.queue.save() {
+            warn!(
+                "Failed to save {} scrobble queue: {}",
+                self.service.name(),
+                e
+            );
+        }
+    }
+
+    /// Submit queued scrobbles oldest first until the queue is empty or the
+    /// service fails.
+    async fn flush(&mut self, now: Instant) {
+        if self.retry_at.is_some_and(|at| now < at) {
+            return;
+        }
+        while !self.queue.is_empty() {
+            let batch = self.queue.front(self.service.max_batch());
+            match self.service.scrobble(&batch).await {
+                Ok(()) => {
+                    self.queue.remove_front(batch.len());
+                    self.retry_at = None;
+                    self.retry_delay = INITIAL_RETRY_DELAY;
+                }
+                Err(e) if e.is_retryable() => {
+                    warn!(
+                        "{} scrobbles kept for retry in {:?}: {}",
+                        self.queue.len(),
+                        self.retry_delay,
+                        e
+                    );
+                    self.retry_at = Some(now + self.retry_delay);
+                    self.retry_delay = (self.retry_delay * 2).min(MAX_RETRY_DELAY);
+                    break;
+                }
+                Err(e) => {
+                    warn!("Dropping {} scrobbles: {}", batch.len(), e);
+                    self.queue.remove_front(batch.len());
+                }
+            }
+            self.save();
+        }
+    }
+}
+
+pub struct Scrobbler {
+    services: Vec<ServiceQueue>,
+    trackers: HashMap<SmolStr, PlayTracker>,
+    /// Re-resolve tracked plays on the next sample, so `[[filter]]` rules
+    /// added by a config reload also stop plays already in progress.
+    recheck: bool,
+}
+
+impl Scrobbler {
+    pub fn new(config: &ScrobbleConfig) -> Self {
+        let mut services: Vec<Box<dyn ScrobbleService>> = Vec::new();
+        if config.lastfm.enabled {
+            match LastfmClient::from_config(&config.lastfm) {
+                Ok(client) => services.push(Box::new(client)),
+                Err(e) => warn!("Last.fm scrobbling disabled: {}", e),
+            }
+        }
+        if config.listenbrainz.enabled {
+            match ListenbrainzClient::from_config(&config.listenbrainz) {
+                Ok(client) => services.push(Box::new(client)),
+                Err(e) => warn!("ListenBrainz submission disabled: {}", e),
+            }
+        }
+
+        let services = services
+            .into_iter()
+            .filter_map(|service| {
+                let Some(path) = ScrobbleQueue::default_path(service.name()) else {
+                    warn!(
+                        "No data directory for the {} scrobble queue",
+                        service.name()
+                    );
+                    return None;
+                };
+                info!("Scrobbling to {}", service.name());
+                let queue = ScrobbleQueue::load(path, config.max_queue);
+                Some(ServiceQueue::new(service, queue))
+            })
+            .collect();
+        Self {
+            services,
+            trackers: HashMap::new(),
+            recheck: false,
+        }
+    }
+
+    /// Swap in services for a reloaded config, keeping in-progress plays.
+    pub fn reconfigure(&mut self, config: &ScrobbleConfig) {
+        let trackers = std::mem::take(&mut self.trackers);
+        *self = Self::new(config);
+        self.trackers = trackers;
+        self.recheck = true;
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.services.is_empty()
+    }
+
+    /// Fingerprints of tracked plays, whose metadata need not be resolved
+    /// again. Empty once after a reload so every play is re-filtered.
+    fn known_tracks(&mut self) -> HashMap<SmolStr, TrackFingerprint> {
+        if std::mem::take(&mut self.recheck) {
+            return HashMap::new();
+        }
+        self.trackers
+            .iter()
+            .map(|(key, tracker)| (key.clone(), tracker.fingerprint().clone()))
+            .collect()
+    }
+
+    /// Advance every player's tracker and act on what it reports. Players
+    /// missing from `samples` have gone away and are forgotten.
+    pub async fn observe(
+        &mut self,
+        samples: HashMap<SmolStr, PlayerSample>,
+        now: Instant,
+        unix_time: u64,
+    ) {
+        self.trackers.retain(|key, _| samples.contains_key(key));
+
+        let mut events = Vec::new();
+        for (key, sample) in samples {
+            if sample.filtered {
+                if self.trackers.remove(&key).is_some() {
+                    debug!("Stopped tracking {}: track matched a [[filter]] rule", key);
+                }
+                continue;
+            }
+            let tracked = self
+                .trackers
+                .get(&key)
+                .is_some_and(|t| *t.fingerprint() == sample.fingerprint);
+            if !tracked {
+                self.trackers.remove(&key);
+                let track = sample
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| Scrobble::from_metadata(m, &sample.identity));
+                match track {
+                    Some(track) => {
+                        trace!("Tracking '{}' on {}", track.track, key);
+                        self.trackers
+                            .insert(key.clone(), PlayTracker::new(sample.fingerprint, track));
+                    }
+                    None => continue,
+                }
+            }
+            if let Some(tracker) = self.trackers.get_mut(&key) {
+                events.extend(tracker.observe(sample.status, sample.position, now, unix_time));
+            }
+        }
+
+        for event in events {
+            match event {
+                PlayEvent::NowPlaying(track) => {
+                    for entry in &self.services {
+                        if let Err(e) = entry.service.now_playing(&track).await {
+                            debug!("{} now playing update failed: {}", entry.service.name(), e);
+                        }
+                    }
+                }
+                PlayEvent::Scrobble(track) => {
+                    info!("Scrobbling {} - {}", track.artist, track.track);
+                    for entry in &mut self.services {
+                        entry.queue.push(track.clone());
+                        entry.save();
+                    }
+                }
+            }
+        }
+        self.flush(now).await;
+    }
+
+    /// Forget in-progress plays while presence is hidden or snoozed, so
+    /// nothing heard meanwhile is announced or scrobbled. Scrobbles queued
+    /// before that are still submitted.
+    pub async fn hide(&mut self, now: Instant) {
+        self.trackers.clear();
+        self.flush(now).await;
+    }
+
+    pub async fn flush(&mut self, now: Instant) {
+        for entry in &mut self.services {
+            entry.flush(now).await;
+        }
+    }
+}
+
+/// Whether a `[[filter]]` rule keeps the track out of listening history.
+/// Redacted tracks are dropped as well as hidden ones: a redacted scrobble
+/// is useless and would still carry the track's MBIDs and ISRC.
+fn is_filtered(config: &ConfigManager, metadata: &MediaMetadata, url: Option<&str>) -> bool {
+    config
+        .match_filter(&metadata.filter_subject(url))
+        .is_some_and(|rule| rule.action != FilterAction::Template)
+}
+
+/// Reads the players to track on each tick. Players are discovered and
+/// deduplicated the same way as for Discord, so one play heard through
+/// several buses (e.g. a browser's own MPRIS bus and the web bridge) is
+/// counted once, from the bus the daemon shows.
+#[derive(Default)]
+struct PlayerSampler {
+    /// Bus picked per player last time, kept on ties.
+    buses: Mutex<HashMap<SmolStr, SmolStr>>,
+    resolutions: FileResolutions,
+}
+
+impl PlayerSampler {
+    /// Read every allowed, non-ignored player. Blocking: every step is a
+    /// D-Bus call.
+    fn sample(
+        &self,
+        config: &ConfigManager,
+        known: &HashMap<SmolStr, TrackFingerprint>,
+    ) -> HashMap<SmolStr, PlayerSample> {
+        let mut samples = HashMap::new();
+        let players = match discovery::find_players(config, &self.buses.lock()) {
+            Ok(players) => players,
+            Err(e) => {
+                debug!("Scrobbler could not list players: {}", e);
+                return samples;
+            }
+        };
+        *self.buses.lock() = players
+            .iter()
+            .map(|(key, player)| {
+                let bus_name = canonical_player_bus_name(player.bus_name());
+                (key.clone(), SmolStr::new(bus_name))
+            })
+            .collect();
+        self.resolutions.retain(|key| players.contains_key(key));
+        let path_map = config.path_map();
+
+        for (key, player) in players {
+            let id = PlayerIdentifier::from(&player);
+            // A proxy only wins when it is all there is; what it proxies
+            // is sampled on its own bus.
+            if is_proxy_bus_name(&id.player_bus_name) {
+                continue;
+            }
+            let Ok(metadata) = player.get_metadata() else {
+                continue;
+            };
+            let Ok(status) = player.get_playback_status() else {
+                continue;
+            };
+
+            let fingerprint = TrackFingerprint::from_mpris(&metadata);
+            let mut filtered = false;
+            let metadata = (known.get(&key) != Some(&fingerprint)).then(|| {
+                let resolved_url = self
+                    .resolutions
+                    .resolve_url_blocking(&key, config, &player, &metadata);
+                let source = MetadataSource::from_mpris_mapped(metadata, resolved_url, &path_map);
+                let resolved =
+                    MediaMetadata::resolve(&source, config, &id.identity, &id.player_bus_name);
+                filtered = is_filtered(config, &resolved.metadata, resolved.url.as_deref());
+                resolved.metadata
+            });
+            samples.insert(
+                key,
+                PlayerSample {
+                    fingerprint,
+                    metadata: metadata.filter(|_| !filtered),
+                    filtered,
+                    status,
+                    position: player.get_position().ok(),
+                    identity: id.identity.to_string(),
+                },
+            );
+        }
+        samples
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Run the scrobbler for the lifetime of the daemon. It idles while no
+/// service is enabled and picks up config reloads itself. `paused` is the
+/// daemon's hidden/snoozed flag.
+pub fn spawn(config: Arc<ConfigManager>, paused: Arc<AtomicBool>) {
+    tokio::spawn(async move {
+        let mut changes = config.subscribe();
+        let mut scrobbler = Scrobbler::new(&config.scrobble_config());
+        let sampler = Arc::new(PlayerSampler::default());
+        let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if !scrobbler.is_active() {
+                        continue;
+                    }
+                    if paused.load(Ordering::Relaxed) {
+                        scrobbler.hide(Instant::now()).await;
+                        continue;
+                    }
+                    let known = scrobbler.known_tracks();
+                    let sampler_config = config.clone();
+                    let sampler = sampler.clone();
+                    let samples = match tokio::task::spawn_blocking(move || {
+                        sampler.sample(&sampler_config, &known)
+                    })
+                    .await
+                    {
+                        Ok(samples) => samples,
+                        Err(e) => {
+                            warn!("Scrobbler sampling task failed: {}", e);
+                            continue;
+                        }
+                    };
+                    scrobbler.observe(samples, Instant::now(), unix_now()).await;
+                },
+                Ok(change) = changes.recv() => {
+                    if matches!(change, ConfigChange::Reloaded) {
+                        scrobbler.reconfigure(&config.scrobble_config());
+                    }
+                },
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::{Config, FilterRule};
+    use crate::test_util::spawn_server;
+
+    #[derive(Default)]
+    struct Recorder {
+        now_playing: Mutex<Vec<String>>,
+        scrobbled: Mutex<Vec<Scrobble>>,
+        failures: Mutex<Vec<ScrobbleError>>,
+    }
+
+    struct MockService(Arc<Recorder>);
+
+    #[async_trait]
+    impl ScrobbleService for MockService {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn max_batch(&self) -> usize {
+            2
+        }
+
+        async fn now_playing(&self, track: &Scrobble) -> Result<(), ScrobbleError> {
+            self.0.now_playing.lock().push(track.track.clone());
+            Ok(())
+        }
+
+        async fn scrobble(&self, batch: &[Scrobble]) -> Result<(), ScrobbleError> {
+            if let Some(e) = self.0.failures.lock().pop() {
+                return Err(e);
+            }
+            self.0.scrobbled.lock().extend_from_slice(batch);
+            Ok(())
+        }
+    }
+
+    fn scrobbler(recorder: &Arc<Recorder>, queue_path: std::path::PathBuf) -> Scrobbler {
+        Scrobbler {
+            services: vec![ServiceQueue::new(
+                Box::new(MockService(recorder.clone())),
+                ScrobbleQueue::load(queue_path, 100),
+            )],
+            trackers: HashMap::new(),
+            recheck: false,
+        }
+    }
+
+    fn temp_queue() -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!(
+                "mprisence-scrobbler-{}-{}",
+                std::process::id(),
+                nanos
+            ))
+            .join("mock.json")
+    }
+
+    fn sample(title: &str, status: PlaybackStatus, with_metadata: bool) -> PlayerSample {
+        let metadata = MediaMetadata {
+            title: Some(title.to_string()),
+            artists: vec!["Artist".to_string()],
+            artist_display: Some("Artist".to_string()),
+            duration_secs: Some(60),
+            ..Default::default()
+        };
+        PlayerSample {
+            fingerprint: TrackFingerprint {
+                track_id: None,
+                url: None,
+                art_url: None,
+                title: Some(title.to_string()),
+                artists: vec!["Artist".to_string()],
+                length: Some(Duration::from_secs(60)),
+            },
+            metadata: with_metadata.then_some(metadata),
+            filtered: false,
+            status,
+            position: None,
+            identity: "Player".to_string(),
+        }
+    }
+
+    fn samples(sample: PlayerSample) -> HashMap<SmolStr, PlayerSample> {
+        HashMap::from([(SmolStr::new("player"), sample)])
+    }
+
+    #[test]
+    fn splits_multi_artist_mbids_from_tags() {
+        let metadata = MediaMetadata {
+            title: Some("Song".to_string()),
+            artist_display: Some("A, B".to_string()),
+            musicbrainz_artist_id: Some(
+                "056e4f3e-d505-4dad-8ec1-d04f521cbb56; 149f91ef-1287-46fb-9ee4-ebf5c8e2d0d8"
+                    .to_string(),
+            ),
+            isrc: Some("USQX91300108".to_string()),
+            ..Default::default()
+        };
+        let track = Scrobble::from_metadata(&metadata, "Elisa").unwrap();
+        assert_eq!(track.musicbrainz_artist_ids.len(), 2);
+        assert_eq!(track.isrc.as_deref(), Some("USQX91300108"));
+        assert_eq!(track.player, "Elisa");
+    }
+
+    #[tokio::test]
+    async fn announces_and_scrobbles_tracked_plays() {
+        let recorder = Arc::new(Recorder::default());
+        let path = temp_queue();
+        let mut scrobbler = scrobbler(&recorder, path.clone());
+        let start = Instant::now();
+
+        scrobbler
+            .observe(
+                samples(sample("One", PlaybackStatus::Playing, true)),
+                start,
+                100,
+            )
+            .await;
+        for secs in (5..=30).step_by(5) {
+            scrobbler
+                .observe(
+                    samples(sample("One", PlaybackStatus::Playing, false)),
+                    start + Duration::from_secs(secs),
+                    100 + secs,
+                )
+                .await;
+        }
+        assert_eq!(*recorder.now_playing.lock(), vec!["One".to_string()]);
+        let scrobbled = recorder.scrobbled.lock().clone();
+        assert_eq!(scrobbled.len(), 1);
+        assert_eq!(scrobbled[0].timestamp, 100);
+        assert_eq!(scrobbled[0].player, "Player");
+        assert!(scrobbler.services[0].queue.is_empty());
+
+        // A track without an artist is not tracked at all.
+        let mut untagged = sample("Two", PlaybackStatus::Playing, true);
+        untagged.metadata.as_mut().unwrap().artist_display = None;
+        scrobbler
+            .observe(samples(untagged), start + Duration::from_secs(40), 140)
+            .await;
+        assert!(scrobbler.trackers.is_empty());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    fn config_with_filter(rule: FilterRule) -> ConfigManager {
+        ConfigManager::new_with_config(Config {
+            filter: vec![rule],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn hidden_and_redacted_tracks_are_filtered() {
+        let metadata = sample("Secret", PlaybackStatus::Playing, true)
+            .metadata
+            .unwrap();
+        let rule = |action| FilterRule {
+            title: Some("Secret".into()),
+            action,
+            ..Default::default()
+        };
+
+        let hide = config_with_filter(rule(FilterAction::Hide));
+        assert!(is_filtered(&hide, &metadata, None));
+        let redact = config_with_filter(rule(FilterAction::Redact));
+        assert!(is_filtered(&redact, &metadata, None));
+        // A template rule only changes how the track is shown.
+        let template = config_with_filter(rule(FilterAction::Template));
+        assert!(!is_filtered(&template, &metadata, None));
+
+        let other = sample("Public", PlaybackStatus::Playing, true)
+            .metadata
+            .unwrap();
+        assert!(!is_filtered(&hide, &other, None));
+    }
+
+    #[tokio::test]
+    async fn filtered_tracks_are_never_announced_or_queued() {
+        let recorder = Arc::new(Recorder::default());
+        let path = temp_queue();
+        let mut scrobbler = scrobbler(&recorder, path.clone());
+        let start = Instant::now();
+
+        scrobbler
+            .observe(
+                samples(sample("One", PlaybackStatus::Playing, true)),
+                start,
+                100,
+            )
+            .await;
+        assert_eq!(scrobbler.trackers.len(), 1);
+
+        for secs in (5..=60).step_by(5) {
+            let mut filtered = sample("Secret", PlaybackStatus::Playing, true);
+            filtered.filtered = true;
+            scrobbler
+                .observe(
+                    samples(filtered),
+                    start + Duration::from_secs(secs),
+                    100 + secs,
+                )
+                .await;
+        }
+        assert!(scrobbler.trackers.is_empty());
+        assert_eq!(*recorder.now_playing.lock(), vec!["One".to_string()]);
+        assert!(recorder.scrobbled.lock().is_empty());
+        assert!(ScrobbleQueue::load(path.clone(), 100).is_empty());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn plays_heard_while_hidden_do_not_count() {
+        let recorder = Arc::new(Recorder::default());
+        let path = temp_queue();
+        let mut scrobbler = scrobbler(&recorder, path.clone());
+        let start = Instant::now();
+
+        scrobbler
+            .observe(
+                samples(sample("One", PlaybackStatus::Playing, true)),
+                start,
+                100,
+            )
+            .await;
+        for secs in (5..=20).step_by(5) {
+            scrobbler
+                .observe(
+                    samples(sample("One", PlaybackStatus::Playing, false)),
+                    start + Duration::from_secs(secs),
+                    100 + secs,
+                )
+                .await;
+        }
+
+        // 20s of a 60s track, then hidden: the play starts over once shown.
+        scrobbler.hide(start + Duration::from_secs(25)).await;
+        assert!(scrobbler.trackers.is_empty());
+        scrobbler
+            .observe(
+                samples(sample("One", PlaybackStatus::Playing, true)),
+                start + Duration::from_secs(30),
+                130,
+            )
+            .await;
+        for secs in [35, 40] {
+            scrobbler
+                .observe(
+                    samples(sample("One", PlaybackStatus::Playing, false)),
+                    start + Duration::from_secs(secs),
+                    100 + secs,
+                )
+                .await;
+        }
+        assert!(recorder.scrobbled.lock().is_empty());
+        assert!(scrobbler.services[0].queue.is_empty());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn reload_filters_stop_listenbrainz_submissions_in_progress() {
+        let (api, rx) = spawn_server(vec![(200, r#"{"status":"ok"}"#)]);
+        let path = temp_queue();
+        let mut scrobbler = Scrobbler {
+            services: vec![ServiceQueue::new(
+                Box::new(
+                    ListenbrainzClient::from_config(&crate::config::schema::ListenbrainzConfig {
+                        enabled: true,
+                        api_url: api,
+                        token: Some("user-token".to_string()),
+                    })
+                    .unwrap(),
+                ),
+                ScrobbleQueue::load(path.clone(), 100),
+            )],
+            trackers: HashMap::new(),
+            recheck: false,
+        };
+        let start = Instant::now();
+
+        scrobbler
+            .observe(
+                samples(sample("Secret", PlaybackStatus::Playing, true)),
+                start,
+                100,
+            )
+            .await;
+        assert!(rx.recv().unwrap().body_text().contains("playing_now"));
+        assert_eq!(scrobbler.known_tracks().len(), 1);
+
+        // A reload adds a rule hiding the track: the next sample resolves it
+        // again and the play is dropped before it is queued.
+        scrobbler.recheck = true;
+        assert!(scrobbler.known_tracks().is_empty());
+        assert_eq!(scrobbler.known_tracks().len(), 1);
+        for secs in (5..=60).step_by(5) {
+            let mut filtered = sample("Secret", PlaybackStatus::Playing, true);
+            filtered.filtered = true;
+            scrobbler
+                .observe(
+                    samples(filtered),
+                    start + Duration::from_secs(secs),
+                    100 + secs,
+                )
+                .await;
+        }
+        assert!(scrobbler.trackers.is_empty());
+        assert!(rx.try_recv().is_err());
+        assert!(!path.exists());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn keeps_failed_scrobbles_queued_until_retry() {
+        let recorder = Arc::new(Recorder::default());
+        let path = temp_queue();
+        let mut scrobbler = scrobbler(&recorder, path.clone());
+        let start = Instant::now();
+        for timestamp in 1..=3 {
+            scrobbler.services[0].queue.push(Scrobble {
+                timestamp,
+                ..Default::default()
+            });
+        }
+        scrobbler.services[0].save();
+
+        recorder
+            .failures
+            .lock()
+            .push(ScrobbleError::Network("offline".to_string()));
+        scrobbler.flush(start).await;
+        assert_eq!(scrobbler.services[0].queue.len(), 3);
+        assert_eq!(ScrobbleQueue::load(path.clone(), 100).len(), 3);
+
+        // Still backing off.
+        scrobbler.flush(start + Duration::from_secs(1)).await;
+        assert!(recorder.scrobbled.lock().is_empty());
+
+        // A rejected batch is dropped rather than retried forever.
+        recorder.failures.lock().push(ScrobbleError::Rejected {
+            service: "mock",
+            message: "bad".to_string(),
+        });
+        scrobbler.flush(start + INITIAL_RETRY_DELAY).await;
+        let scrobbled: Vec<u64> = recorder
+            .scrobbled
+            .lock()
+            .iter()
+            .map(|s| s.timestamp)
+            .collect();
+        assert_eq!(scrobbled, vec![3]);
+        assert!(ScrobbleQueue::load(path.clone(), 100).is_empty());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
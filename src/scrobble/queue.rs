@@ -0,0 +1,176 @@
+//! Scrobbles not yet accepted by a service, persisted as one JSON file per
+//! service so they survive restarts and offline stretches.
+
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, warn};
+
+use super::{error::ScrobbleError, Scrobble};
+
+pub struct ScrobbleQueue {
+    path: PathBuf,
+    entries: VecDeque<Scrobble>,
+    max_len: usize,
+}
+
+impl ScrobbleQueue {
+    /// `$XDG_DATA_HOME/mprisence/scrobble/<service>.json`
+    pub fn default_path(service: &str) -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| {
+            dir.join("mprisence")
+                .join("scrobble")
+                .join(format!("{service}.json"))
+        })
+    }
+
+    /// Load the queue at `path`. A missing file is an empty queue; an
+    /// unreadable one is moved aside rather than overwritten.
+    pub fn load(path: PathBuf, max_len: usize) -> Self {
+        let entries = match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<VecDeque<Scrobble>>(&bytes) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    let aside = path.with_extension("json.bad");
+                    warn!(
+                        "Scrobble queue {} is corrupt ({}), moving it to {}",
+                        path.display(),
+                        e,
+                        aside.display()
+                    );
+                    let _ = fs::rename(&path, aside);
+                    VecDeque::new()
+                }
+            },
+            Err(_) => VecDeque::new(),
+        };
+        if !entries.is_empty() {
+            debug!(
+                "Loaded {} queued scrobbles from {}",
+                entries.len(),
+                path.display()
+            );
+        }
+        let mut queue = Self {
+            path,
+            entries,
+            max_len: max_len.max(1),
+        };
+        queue.truncate();
+        queue
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn push(&mut self, scrobble: Scrobble) {
+        self.entries.push_back(scrobble);
+        self.truncate();
+    }
+
+    /// Oldest `n` entries, left in place until `remove_front` confirms them.
+    pub fn front(&self, n: usize) -> Vec<Scrobble> {
+        self.entries.iter().take(n).cloned().collect()
+    }
+
+    pub fn remove_front(&mut self, n: usize) {
+        let n = n.min(self.entries.len());
+        self.entries.drain(..n);
+    }
+
+    /// Write through a temporary file so a crash never leaves half a queue.
+    pub fn save(&self) -> Result<(), ScrobbleError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.entries)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.entries.len().saturating_sub(self.max_len);
+        if excess > 0 {
+            warn!(
+                "Scrobble queue {} is full, dropping the {} oldest",
+                self.path.display(),
+                excess
+            );
+            self.entries.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_path() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!(
+                "mprisence-scrobble-queue-{}-{}",
+                std::process::id(),
+                nanos
+            ))
+            .join("lastfm.json")
+    }
+
+    fn scrobble(timestamp: u64) -> Scrobble {
+        Scrobble {
+            artist: "Artist".to_string(),
+            track: "Song".to_string(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn persists_across_loads_and_drops_oldest_when_full() {
+        let path = temp_path();
+        let mut queue = ScrobbleQueue::load(path.clone(), 2);
+        assert!(queue.is_empty());
+        for timestamp in 1..=3 {
+            queue.push(scrobble(timestamp));
+        }
+        queue.save().unwrap();
+
+        let mut queue = ScrobbleQueue::load(path.clone(), 2);
+        assert_eq!(queue.front(5), vec![scrobble(2), scrobble(3)]);
+        queue.remove_front(1);
+        queue.save().unwrap();
+        assert_eq!(ScrobbleQueue::load(path.clone(), 2).len(), 1);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn moves_corrupt_queue_aside() {
+        let path = temp_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, b"{not json").unwrap();
+
+        let queue = ScrobbleQueue::load(path.clone(), 10);
+        assert!(queue.is_empty());
+        assert!(path.with_extension("json.bad").exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
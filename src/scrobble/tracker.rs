@@ -0,0 +1,255 @@
+//! Per-player play accounting. A play counts once the track, longer than
+//! 30 seconds, has been playing for half its length or four minutes,
+//! whichever comes first. Time only accrues while the player reports
+//! `Playing`, so pauses and stalls do not count towards the threshold.
+
+use std::time::{Duration, Instant};
+
+use log::{debug, trace};
+use mpris::PlaybackStatus;
+
+use super::Scrobble;
+use crate::player::health::TrackFingerprint;
+
+const MIN_TRACK_LENGTH: Duration = Duration::from_secs(30);
+const MAX_THRESHOLD: Duration = Duration::from_secs(240);
+
+/// Longest stretch credited between two samples. Longer gaps (suspend, a
+/// hung D-Bus call) only count this much.
+const MAX_SAMPLE_GAP: Duration = Duration::from_secs(15);
+
+/// A position this close to the start after the track already counted
+/// means it is playing again (repeat-one, or restarted by hand).
+const REPLAY_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayEvent {
+    NowPlaying(Scrobble),
+    Scrobble(Scrobble),
+}
+
+#[derive(Debug)]
+pub struct PlayTracker {
+    fingerprint: TrackFingerprint,
+    track: Scrobble,
+    played: Duration,
+    last_playing: Option<Instant>,
+    last_position: Option<Duration>,
+    started: bool,
+    scrobbled: bool,
+}
+
+impl PlayTracker {
+    pub fn new(fingerprint: TrackFingerprint, track: Scrobble) -> Self {
+        Self {
+            fingerprint,
+            track,
+            played: Duration::ZERO,
+            last_playing: None,
+            last_position: None,
+            started: false,
+            scrobbled: false,
+        }
+    }
+
+    pub fn fingerprint(&self) -> &TrackFingerprint {
+        &self.fingerprint
+    }
+
+    /// Play time needed before the track counts, or `None` when it is too
+    /// short to scrobble at all. Tracks of unknown length need four minutes.
+    fn threshold(&self) -> Option<Duration> {
+        match self.fingerprint.length {
+            Some(length) if length <= MIN_TRACK_LENGTH => None,
+            Some(length) => Some((length / 2).min(MAX_THRESHOLD)),
+            None => Some(MAX_THRESHOLD),
+        }
+    }
+
+    /// Feed one sample of the player. `now` drives play-time accounting and
+    /// `unix_time` stamps the start of the play.
+    pub fn observe(
+        &mut self,
+        status: PlaybackStatus,
+        position: Option<Duration>,
+        now: Instant,
+        unix_time: u64,
+    ) -> Vec<PlayEvent> {
+        let mut events = Vec::new();
+        let threshold = self.threshold();
+
+        if let (true, Some(threshold), Some(position), Some(last)) =
+            (self.scrobbled, threshold, position, self.last_position)
+        {
+            if position < REPLAY_WINDOW && last >= threshold {
+                debug!("'{}' started over, counting a new play", self.track.track);
+                self.played = Duration::ZERO;
+                self.started = false;
+                self.scrobbled = false;
+            }
+        }
+        if position.is_some() {
+            self.last_position = position;
+        }
+
+        if status != PlaybackStatus::Playing {
+            self.last_playing = None;
+            return events;
+        }
+
+        if let Some(last) = self.last_playing {
+            self.played += now.saturating_duration_since(last).min(MAX_SAMPLE_GAP);
+        }
+        self.last_playing = Some(now);
+
+        if !self.started {
+            self.started = true;
+            self.track.timestamp = unix_time;
+            events.push(PlayEvent::NowPlaying(self.track.clone()));
+        }
+
+        trace!(
+            "'{}' played {:?} of {:?} needed",
+            self.track.track,
+            self.played,
+            threshold
+        );
+        if let Some(threshold) = threshold {
+            if !self.scrobbled && self.played >= threshold {
+                self.scrobbled = true;
+                events.push(PlayEvent::Scrobble(self.track.clone()));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(length_secs: Option<u64>) -> PlayTracker {
+        let fingerprint = TrackFingerprint {
+            track_id: None,
+            url: None,
+            art_url: None,
+            title: Some("Song".to_string()),
+            artists: vec!["Artist".to_string()],
+            length: length_secs.map(Duration::from_secs),
+        };
+        let track = Scrobble {
+            artist: "Artist".to_string(),
+            track: "Song".to_string(),
+            duration_secs: length_secs,
+            ..Default::default()
+        };
+        PlayTracker::new(fingerprint, track)
+    }
+
+    /// Sample every 5 seconds for `secs`, returning every emitted event.
+    fn play(
+        tracker: &mut PlayTracker,
+        start: Instant,
+        from: u64,
+        secs: u64,
+        status: PlaybackStatus,
+    ) -> Vec<PlayEvent> {
+        (from..=from + secs)
+            .step_by(5)
+            .flat_map(|t| {
+                tracker.observe(
+                    status,
+                    Some(Duration::from_secs(t)),
+                    start + Duration::from_secs(t),
+                    1_700_000_000 + t,
+                )
+            })
+            .collect()
+    }
+
+    fn scrobbles(events: &[PlayEvent]) -> usize {
+        events
+            .iter()
+            .filter(|e| matches!(e, PlayEvent::Scrobble(_)))
+            .count()
+    }
+
+    #[test]
+    fn scrobbles_at_half_the_track_once() {
+        let start = Instant::now();
+        let mut t = tracker(Some(200));
+        let events = play(&mut t, start, 0, 95, PlaybackStatus::Playing);
+        assert_eq!(scrobbles(&events), 0);
+        match &events[0] {
+            PlayEvent::NowPlaying(track) => assert_eq!(track.timestamp, 1_700_000_000),
+            other => panic!("expected now playing, got {other:?}"),
+        }
+
+        let events = play(&mut t, start, 100, 100, PlaybackStatus::Playing);
+        assert_eq!(scrobbles(&events), 1);
+        match &events[0] {
+            PlayEvent::Scrobble(track) => assert_eq!(track.timestamp, 1_700_000_000),
+            other => panic!("expected scrobble, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn caps_threshold_at_four_minutes_and_skips_short_tracks() {
+        let start = Instant::now();
+        let mut long = tracker(Some(3600));
+        assert_eq!(
+            scrobbles(&play(&mut long, start, 0, 235, PlaybackStatus::Playing)),
+            0
+        );
+        assert_eq!(
+            scrobbles(&play(&mut long, start, 240, 5, PlaybackStatus::Playing)),
+            1
+        );
+
+        let mut short = tracker(Some(25));
+        assert_eq!(
+            scrobbles(&play(&mut short, start, 0, 25, PlaybackStatus::Playing)),
+            0
+        );
+    }
+
+    #[test]
+    fn paused_time_and_long_gaps_do_not_count() {
+        let start = Instant::now();
+        let mut t = tracker(Some(200));
+        play(&mut t, start, 0, 40, PlaybackStatus::Playing);
+        assert_eq!(
+            scrobbles(&play(&mut t, start, 45, 300, PlaybackStatus::Paused)),
+            0
+        );
+
+        // One sample an hour later adds at most MAX_SAMPLE_GAP.
+        let later = start + Duration::from_secs(3600);
+        assert!(t
+            .observe(PlaybackStatus::Playing, None, later, 0)
+            .is_empty());
+        assert!(t
+            .observe(
+                PlaybackStatus::Playing,
+                None,
+                later + Duration::from_secs(3600),
+                0
+            )
+            .is_empty());
+        assert_eq!(t.played, Duration::from_secs(40) + MAX_SAMPLE_GAP);
+    }
+
+    #[test]
+    fn counts_a_replay_of_the_same_track() {
+        let start = Instant::now();
+        let mut t = tracker(Some(60));
+        assert_eq!(
+            scrobbles(&play(&mut t, start, 0, 60, PlaybackStatus::Playing)),
+            1
+        );
+        let replay = start + Duration::from_secs(65);
+        let events = play(&mut t, replay, 0, 30, PlaybackStatus::Playing);
+        assert!(matches!(events[0], PlayEvent::NowPlaying(_)));
+        assert_eq!(scrobbles(&events), 1);
+    }
+}
@@ -0,0 +1,108 @@
+//! Discord Rich Presence over local IPC. Unlike the `[[sink]]` outputs, one
+//! connection is opened per player so each shows under its own app id.
+
+use discord_rich_presence::{
+    activity::{Activity, Assets, Button, Timestamps},
+    DiscordIpc, DiscordIpcClient,
+};
+use parking_lot::{Mutex, MutexGuard};
+
+use super::{error::SinkError, PresenceSink, SinkActivity, SinkPlayer};
+
+pub struct DiscordSink {
+    client: Mutex<DiscordIpcClient>,
+    app_id: String,
+}
+
+impl DiscordSink {
+    /// Create a client for `app_id`. Nothing is connected until `connect`.
+    pub fn new(app_id: &str) -> Self {
+        Self {
+            client: Mutex::new(DiscordIpcClient::new(app_id)),
+            app_id: app_id.to_string(),
+        }
+    }
+
+    pub fn app_id(&self) -> &str {
+        &self.app_id
+    }
+
+    /// The IPC client, for connection management.
+    pub fn client(&self) -> MutexGuard<'_, DiscordIpcClient> {
+        self.client.lock()
+    }
+}
+
+fn build_activity(activity: &SinkActivity) -> Activity<'_> {
+    let mut discord_activity = Activity::default()
+        .activity_type(activity.activity_type.into())
+        .status_display_type(activity.status_display_type.into());
+
+    if !activity.details.is_empty() {
+        discord_activity = discord_activity.details(&activity.details);
+    }
+    if !activity.state.is_empty() {
+        discord_activity = discord_activity.state(&activity.state);
+    }
+
+    if let Some(timestamps) = activity.timestamps {
+        discord_activity = discord_activity.timestamps({
+            let ts = Timestamps::default().start(timestamps.start as i64);
+            if let Some(end) = timestamps.end {
+                ts.end(end as i64)
+            } else {
+                ts
+            }
+        });
+    }
+
+    let mut assets = Assets::default();
+    if let Some(img_url) = activity.cover_url.as_deref() {
+        assets = assets.large_image(img_url);
+        if !activity.large_text.is_empty() {
+            assets = assets.large_text(&activity.large_text);
+        }
+        if activity.show_icon {
+            assets = assets.small_image(activity.icon.as_str());
+            if !activity.small_text.is_empty() {
+                assets = assets.small_text(&activity.small_text);
+            }
+        }
+    } else {
+        assets = assets.large_image(activity.icon.as_str());
+        if !activity.large_text.is_empty() {
+            assets = assets.large_text(&activity.large_text);
+        }
+    }
+    discord_activity = discord_activity.assets(assets);
+
+    if !activity.buttons.is_empty() {
+        discord_activity = discord_activity.buttons(
+            activity
+                .buttons
+                .iter()
+                .map(|button| Button::new(button.label.as_str(), button.url.as_str()))
+                .collect(),
+        );
+    }
+
+    discord_activity
+}
+
+impl PresenceSink for DiscordSink {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn publish(&self, activity: &SinkActivity) -> Result<(), SinkError> {
+        self.client()
+            .set_activity(build_activity(activity))
+            .map_err(|err| SinkError::Discord(err.to_string()))
+    }
+
+    fn clear(&self, _player: &SinkPlayer) -> Result<(), SinkError> {
+        self.client()
+            .clear_activity()
+            .map_err(|err| SinkError::Discord(err.to_string()))
+    }
+}
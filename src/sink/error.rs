@@ -0,0 +1,20 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SinkError {
+    #[error("Discord IPC error: {0}")]
+    Discord(String),
+
+    /// The sink's delivery queue is full; the event was dropped.
+    #[error("{0} is not keeping up, dropping event")]
+    Backlog(&'static str),
+
+    #[error("HTTP server error: {0}")]
+    Http(String),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+}
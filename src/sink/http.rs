@@ -0,0 +1,495 @@
+//! Local now-playing server for OBS browser sources and other overlays on
+//! this machine. Listens on 127.0.0.1 only, and answers only requests
+//! addressed to `127.0.0.1:<port>` or `localhost:<port>`, so a web page
+//! cannot reach it by rebinding its own hostname to 127.0.0.1:
+//!
+//! - `GET /now-playing`: the current activity as JSON, `null` when idle
+//! - `GET /now-playing/events`: the same payload as Server-Sent Events
+//! - `GET /now-playing/cover`: the local cover art bytes, if any
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    net::SocketAddr,
+    sync::{mpsc, Arc, OnceLock, Weak},
+    thread,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use log::{debug, trace, warn};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::{error::SinkError, PresenceSink, SinkActivity, SinkPlayer};
+use crate::cover::sources::ArtSource;
+
+const SINK: &str = "http";
+
+/// SSE comment sent on idle streams, so closed overlays are noticed.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+const IDLE_PAYLOAD: &str = "null";
+
+/// Running servers by configured port. A config reload builds the new
+/// sinks while the old ones are still alive, so an unchanged `[[sink]]`
+/// must share the server instead of binding its port a second time.
+static SERVERS: OnceLock<Mutex<HashMap<u16, Weak<Shared>>>> = OnceLock::new();
+
+pub struct HttpSink {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    server: Arc<Server>,
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    current: Option<Current>,
+    /// Origins whose pages may read responses (CORS). Empty by default, so
+    /// arbitrary sites open in the browser cannot read local data.
+    allowed_origins: Vec<String>,
+    /// Bumped whenever the local cover changes, so overlays can use the
+    /// cover URL as a cache key.
+    cover_version: u64,
+    /// Open event streams; dropped on the next send once they hang up.
+    subscribers: Vec<mpsc::Sender<String>>,
+}
+
+struct Current {
+    player: SinkPlayer,
+    payload: String,
+    local_cover: Option<Arc<ArtSource>>,
+}
+
+/// The `/now-playing` body: the activity plus where to get its local cover.
+#[derive(Serialize)]
+struct NowPlaying<'a> {
+    #[serde(flatten)]
+    activity: &'a SinkActivity,
+    local_cover_url: Option<String>,
+}
+
+impl HttpSink {
+    /// Serve on `127.0.0.1:port`, reusing the server already running there.
+    /// Port 0 picks a free port.
+    pub fn bind(port: u16, allowed_origins: &[String]) -> Result<Self, SinkError> {
+        let mut servers = SERVERS.get_or_init(Default::default).lock();
+        if port != 0 {
+            if let Some(shared) = servers.get(&port).and_then(Weak::upgrade) {
+                debug!("Reusing now-playing server on port {}", port);
+                shared.state.lock().allowed_origins = allowed_origins.to_vec();
+                return Ok(Self { shared });
+            }
+        }
+
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| SinkError::Http(format!("cannot listen on port {port}: {e}")))?;
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .expect("tcp listener has an ip address");
+        let state = State {
+            allowed_origins: allowed_origins.to_vec(),
+            ..Default::default()
+        };
+        let shared = Arc::new(Shared {
+            server: Arc::new(server),
+            addr,
+            state: Arc::new(Mutex::new(state)),
+        });
+
+        let server = Arc::clone(&shared.server);
+        let state = Arc::clone(&shared.state);
+        thread::Builder::new()
+            .name("now-playing-http".to_string())
+            .spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request, addr.port(), &state);
+                }
+                debug!("Now-playing server on {} stopped", addr);
+            })?;
+        debug!("Serving now-playing on http://{}", addr);
+
+        if port != 0 {
+            servers.insert(port, Arc::downgrade(&shared));
+        }
+        Ok(Self { shared })
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // Ends `incoming_requests`, which drops the server and its socket.
+        self.server.unblock();
+    }
+}
+
+impl State {
+    fn payload(&self) -> &str {
+        self.current
+            .as_ref()
+            .map_or(IDLE_PAYLOAD, |current| current.payload.as_str())
+    }
+
+    /// The request's `Origin` if it may read the response.
+    fn allowed_origin(&self, request: &Request) -> Option<String> {
+        let origin = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Origin"))?
+            .value
+            .as_str();
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == origin)
+            .then(|| origin.to_string())
+    }
+
+    fn broadcast(&mut self) {
+        let payload = self.payload().to_string();
+        self.subscribers
+            .retain(|subscriber| subscriber.send(payload.clone()).is_ok());
+    }
+}
+
+impl PresenceSink for HttpSink {
+    fn name(&self) -> &'static str {
+        SINK
+    }
+
+    fn publish(&self, activity: &SinkActivity) -> Result<(), SinkError> {
+        let mut state = self.shared.state.lock();
+        let previous_cover = state
+            .current
+            .as_ref()
+            .and_then(|current| current.local_cover.as_deref());
+        if activity.local_cover.is_some() && previous_cover != activity.local_cover.as_deref() {
+            state.cover_version += 1;
+        }
+        let local_cover_url = activity.local_cover.as_ref().map(|_| {
+            format!(
+                "http://{}/now-playing/cover?v={}",
+                self.shared.addr, state.cover_version
+            )
+        });
+        let payload = serde_json::to_string(&NowPlaying {
+            activity,
+            local_cover_url,
+        })?;
+        state.current = Some(Current {
+            player: activity.player.clone(),
+            payload,
+            local_cover: activity.local_cover.clone(),
+        });
+        state.broadcast();
+        Ok(())
+    }
+
+    fn clear(&self, player: &SinkPlayer) -> Result<(), SinkError> {
+        let mut state = self.shared.state.lock();
+        // Another player may have taken over since; its activity stays.
+        if state
+            .current
+            .as_ref()
+            .is_some_and(|current| current.player == *player)
+        {
+            state.current = None;
+            state.broadcast();
+        }
+        Ok(())
+    }
+
+    fn wants_local_cover(&self) -> bool {
+        true
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("static header")
+}
+
+/// Whether the request's `Host` names this server. Any other host means
+/// the browser resolved someone else's name to 127.0.0.1 (DNS rebinding).
+fn is_local_host(request: &Request, port: u16) -> bool {
+    let Some(host) = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Host"))
+    else {
+        return false;
+    };
+    let host = host.value.as_str();
+    let local = |name: &str| {
+        host.get(..name.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(name))
+            && host[name.len()..] == format!(":{port}")
+    };
+    local("127.0.0.1") || local("localhost")
+}
+
+fn handle(request: Request, port: u16, state: &Mutex<State>) {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    trace!("Now-playing request: {} {}", request.method(), path);
+    if !is_local_host(&request, port) {
+        debug!("Rejecting now-playing request for a foreign Host");
+        let _ = request.respond(Response::empty(403));
+        return;
+    }
+    if *request.method() != Method::Get {
+        let _ = request.respond(Response::empty(405));
+        return;
+    }
+
+    let allowed_origin = state.lock().allowed_origin(&request);
+    let (status, content_type, body) = match path.as_str() {
+        "/now-playing" => (200, "application/json", state.lock().payload().into()),
+        "/now-playing/cover" => {
+            let source = state
+                .lock()
+                .current
+                .as_ref()
+                .and_then(|current| current.local_cover.clone());
+            match source.as_deref().and_then(read_cover) {
+                Some(bytes) => (200, cover_content_type(&bytes), bytes),
+                None => (404, "text/plain", b"no local cover".to_vec()),
+            }
+        }
+        "/now-playing/events" => {
+            stream_events(request, state, allowed_origin);
+            return;
+        }
+        _ => (404, "text/plain", b"not found".to_vec()),
+    };
+    let mut response = Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", content_type))
+        .with_header(header("Cache-Control", "no-cache"))
+        .with_header(header("Vary", "Origin"));
+    if let Some(origin) = &allowed_origin {
+        response.add_header(header("Access-Control-Allow-Origin", origin));
+    }
+    let _ = request.respond(response);
+}
+
+/// Cover bytes from disk or memory. Remote URLs are not proxied; those are
+/// already in the payload as `cover_url`.
+fn read_cover(source: &ArtSource) -> Option<Vec<u8>> {
+    match source {
+        ArtSource::Bytes(bytes) => Some(bytes.clone()),
+        ArtSource::Base64(data) => STANDARD.decode(data.as_bytes()).ok(),
+        ArtSource::File(path) => match std::fs::read(path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                debug!("Failed to read cover {:?}: {}", path, e);
+                None
+            }
+        },
+        ArtSource::Url(_) => None,
+    }
+}
+
+fn cover_content_type(bytes: &[u8]) -> &'static str {
+    image::guess_format(bytes)
+        .map(|format| format.to_mime_type())
+        .unwrap_or("application/octet-stream")
+}
+
+/// Hand the connection to its own thread, which writes the current payload
+/// and then every change until the client goes away. The response has no
+/// length, so it is written raw: tiny_http would buffer a chunked body.
+fn stream_events(request: Request, state: &Mutex<State>, allowed_origin: Option<String>) {
+    let (tx, rx) = mpsc::channel();
+    {
+        let mut state = state.lock();
+        let _ = tx.send(state.payload().to_string());
+        state.subscribers.push(tx);
+    }
+    let mut writer = request.into_writer();
+    let spawned = thread::Builder::new()
+        .name("now-playing-sse".to_string())
+        .spawn(move || {
+            let cors = allowed_origin
+                .map(|origin| format!("Access-Control-Allow-Origin: {origin}\r\n"))
+                .unwrap_or_default();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/event-stream\r\n\
+                 Cache-Control: no-cache\r\n\
+                 Vary: Origin\r\n\
+                 {cors}Connection: close\r\n\r\n"
+            );
+            if writer.write_all(head.as_bytes()).is_err() {
+                return;
+            }
+            loop {
+                let chunk = match rx.recv_timeout(KEEPALIVE) {
+                    Ok(payload) => format!("data: {payload}\n\n"),
+                    Err(mpsc::RecvTimeoutError::Timeout) => ": keep-alive\n\n".to_string(),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                };
+                if writer
+                    .write_all(chunk.as_bytes())
+                    .and_then(|_| writer.flush())
+                    .is_err()
+                {
+                    trace!("Now-playing event stream closed");
+                    return;
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("Failed to start now-playing event stream: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::activity;
+    use serde_json::Value;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+
+    const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    fn send(addr: SocketAddr, path: &str, host: &str, origin: Option<&str>) -> TcpStream {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let origin = origin
+            .map(|origin| format!("Origin: {origin}\r\n"))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "GET {path} HTTP/1.0\r\nHost: {host}\r\n{origin}\r\n"
+        )
+        .unwrap();
+        stream
+    }
+
+    fn request(addr: SocketAddr, path: &str, origin: Option<&str>) -> TcpStream {
+        send(addr, path, &addr.to_string(), origin)
+    }
+
+    fn read_response(mut stream: TcpStream) -> (String, Vec<u8>) {
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let split = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        let head = String::from_utf8_lossy(&response[..split]).into_owned();
+        (head, response[split + 4..].to_vec())
+    }
+
+    fn get_from(addr: SocketAddr, path: &str, origin: Option<&str>) -> (String, Vec<u8>) {
+        read_response(request(addr, path, origin))
+    }
+
+    fn get(addr: SocketAddr, path: &str) -> (String, Vec<u8>) {
+        get_from(addr, path, None)
+    }
+
+    fn next_event(reader: &mut BufReader<TcpStream>) -> Value {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            if let Some(data) = line.strip_prefix("data: ") {
+                return serde_json::from_str(data.trim_end()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn serves_now_playing_and_local_cover() {
+        let sink = HttpSink::bind(0, &[]).unwrap();
+        let addr = sink.shared.addr;
+        let (_, body) = get(addr, "/now-playing");
+        assert_eq!(body, b"null");
+
+        let mut activity = activity();
+        activity.local_cover = Some(Arc::new(ArtSource::Bytes(PNG_MAGIC.to_vec())));
+        sink.publish(&activity).unwrap();
+
+        let (head, body) = get(addr, "/now-playing");
+        assert!(!head.contains("Access-Control-Allow-Origin"));
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["details"], "Get Lucky");
+        assert_eq!(body["context"]["title"], "Get Lucky");
+        assert_eq!(body["cover_url"], "https://example.com/cover.jpg");
+        let cover_url = body["local_cover_url"].as_str().unwrap();
+        assert!(cover_url.starts_with(&format!("http://{addr}/now-playing/cover?v=")));
+
+        let (head, body) = get(addr, "/now-playing/cover?v=1");
+        assert!(head.contains("image/png"));
+        assert_eq!(body, PNG_MAGIC);
+
+        assert!(get(addr, "/nope").0.starts_with("HTTP/1.0 404"));
+    }
+
+    #[test]
+    fn streams_changes_as_server_sent_events() {
+        let sink = HttpSink::bind(0, &[]).unwrap();
+        let mut events = BufReader::new(request(sink.shared.addr, "/now-playing/events", None));
+        assert_eq!(next_event(&mut events), Value::Null);
+
+        let activity = activity();
+        sink.publish(&activity).unwrap();
+        assert_eq!(next_event(&mut events)["state"], "Daft Punk");
+
+        // Clearing a player that is not shown leaves the activity alone.
+        sink.clear(&SinkPlayer {
+            identity: "Other".to_string(),
+            bus_name: "other".to_string(),
+        })
+        .unwrap();
+        sink.clear(&activity.player).unwrap();
+        assert_eq!(next_event(&mut events), Value::Null);
+    }
+
+    #[test]
+    fn only_allowed_origins_may_read_cross_origin() {
+        let sink = HttpSink::bind(0, &["http://localhost:3000".to_string()]).unwrap();
+        let addr = sink.shared.addr;
+
+        let (head, _) = get_from(addr, "/now-playing", Some("https://evil.example"));
+        assert!(!head.contains("Access-Control-Allow-Origin"));
+
+        let (head, _) = get_from(addr, "/now-playing", Some("http://localhost:3000"));
+        assert!(head.contains("Access-Control-Allow-Origin: http://localhost:3000"));
+
+        // DNS rebinding: a page on evil.example resolved to 127.0.0.1 is
+        // same-origin with itself and needs no CORS, but its Host gives it away.
+        for path in ["/now-playing", "/now-playing/cover", "/now-playing/events"] {
+            let (head, body) = read_response(send(addr, path, "evil.example", None));
+            assert!(head.starts_with("HTTP/1.0 403"), "{path}: {head}");
+            assert!(body.is_empty());
+        }
+        let localhost = format!("localhost:{}", addr.port());
+        let (head, _) = read_response(send(addr, "/now-playing", &localhost, None));
+        assert!(head.starts_with("HTTP/1.0 200"));
+
+        let mut events = BufReader::new(request(
+            addr,
+            "/now-playing/events",
+            Some("http://localhost:3000"),
+        ));
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            events.read_line(&mut head).unwrap();
+        }
+        assert!(head.contains("Access-Control-Allow-Origin: http://localhost:3000"));
+    }
+}
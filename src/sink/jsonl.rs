@@ -0,0 +1,105 @@
+//! JSON lines output: one [`SinkEvent`] per line, for status bars and
+//! scripts tailing a file or reading mprisence's stdout.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+};
+
+use parking_lot::Mutex;
+
+use super::{error::SinkError, PresenceSink, SinkActivity, SinkEvent, SinkPlayer};
+
+/// `path` value that selects stdout instead of a file.
+const STDOUT: &str = "-";
+
+pub struct JsonLinesSink {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLinesSink {
+    /// Append to `path`, creating it if needed, or write to stdout for `-`.
+    pub fn open(path: &str) -> Result<Self, SinkError> {
+        let out: Box<dyn Write + Send> = if path == STDOUT {
+            Box::new(io::stdout())
+        } else {
+            Box::new(OpenOptions::new().create(true).append(true).open(path)?)
+        };
+        Ok(Self::from_writer(out))
+    }
+
+    fn from_writer(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: Mutex::new(out),
+        }
+    }
+
+    fn write(&self, event: &SinkEvent<'_>) -> Result<(), SinkError> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        // One write per line so readers never see half an event.
+        let mut out = self.out.lock();
+        out.write_all(&line)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl PresenceSink for JsonLinesSink {
+    fn name(&self) -> &'static str {
+        "jsonl"
+    }
+
+    fn publish(&self, activity: &SinkActivity) -> Result<(), SinkError> {
+        self.write(&SinkEvent::Activity(activity))
+    }
+
+    fn clear(&self, player: &SinkPlayer) -> Result<(), SinkError> {
+        self.write(&SinkEvent::Clear { player })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::activity;
+    use serde_json::Value;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn appends_one_event_per_line() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "mprisence-sink-{}-{}.jsonl",
+            std::process::id(),
+            nanos
+        ));
+        let path_str = path.to_str().unwrap();
+
+        let activity = activity();
+        JsonLinesSink::open(path_str)
+            .unwrap()
+            .publish(&activity)
+            .unwrap();
+        // Reopening appends rather than truncating.
+        JsonLinesSink::open(path_str)
+            .unwrap()
+            .clear(&activity.player)
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let events: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "activity");
+        assert_eq!(events[0]["cover_url"], "https://example.com/cover.jpg");
+        assert_eq!(events[1]["event"], "clear");
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
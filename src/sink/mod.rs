@@ -0,0 +1,264 @@
+//! Outputs for the rendered presence. `Presence` renders an activity once
+//! and hands it to every [`PresenceSink`]: Discord, plus any `[[sink]]`
+//! entries (JSON lines, webhooks, the local HTTP server) feeding status
+//! bars, overlays and dashboards.
+
+pub mod discord;
+pub mod error;
+pub mod http;
+pub mod jsonl;
+pub mod webhook;
+
+use std::sync::Arc;
+
+use log::{debug, warn};
+use serde::Serialize;
+
+use crate::config::{
+    schema::{ActivityType, SinkConfig, StatusDisplayType},
+    ConfigManager,
+};
+use crate::cover::sources::ArtSource;
+use crate::template::{ActivityButton, RenderContext};
+
+use self::error::SinkError;
+use self::http::HttpSink;
+use self::jsonl::JsonLinesSink;
+use self::webhook::WebhookSink;
+
+/// The player an activity belongs to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SinkPlayer {
+    pub identity: String,
+    pub bus_name: String,
+}
+
+/// Unix seconds. `end` is absent for elapsed-time display or unknown length.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SinkTimestamps {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+/// A fully rendered activity, as pushed to Discord.
+#[derive(Debug, Clone, Serialize)]
+pub struct SinkActivity {
+    pub player: SinkPlayer,
+    pub activity_type: ActivityType,
+    pub status_display_type: StatusDisplayType,
+    pub details: String,
+    pub state: String,
+    pub large_text: String,
+    pub small_text: String,
+    pub buttons: Vec<ActivityButton>,
+    pub cover_url: Option<String>,
+    /// Player icon, shown in place of a missing cover or next to it when
+    /// `show_icon` is set.
+    pub icon: String,
+    pub show_icon: bool,
+    pub timestamps: Option<SinkTimestamps>,
+    /// The template variables the texts were rendered from.
+    pub context: RenderContext,
+    /// Cover art on this machine (file, embedded picture, data URL), for
+    /// sinks that serve it themselves. Only filled in when a sink asks for
+    /// it through [`PresenceSink::wants_local_cover`].
+    #[serde(skip)]
+    pub local_cover: Option<Arc<ArtSource>>,
+}
+
+/// What serialized sinks emit: one object per event, tagged by `event`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SinkEvent<'a> {
+    Activity(&'a SinkActivity),
+    Clear { player: &'a SinkPlayer },
+}
+
+pub trait PresenceSink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn publish(&self, activity: &SinkActivity) -> Result<(), SinkError>;
+
+    fn clear(&self, player: &SinkPlayer) -> Result<(), SinkError>;
+
+    /// Whether `publish` should get `local_cover`. Reading it can mean
+    /// copying an embedded picture, so it is skipped unless needed.
+    fn wants_local_cover(&self) -> bool {
+        false
+    }
+}
+
+/// The `[[sink]]` outputs. Discord is driven by `Presence` itself because
+/// its connection is per player; these are shared by every player.
+pub struct SinkManager {
+    sinks: Vec<Box<dyn PresenceSink>>,
+}
+
+impl SinkManager {
+    /// Build the configured sinks. One that cannot be opened is logged and
+    /// left out so it does not take presence down with it.
+    pub fn new(config: &ConfigManager) -> Self {
+        let sinks = config
+            .sink_configs()
+            .iter()
+            .filter_map(|sink_config| match Self::create_sink(sink_config) {
+                Ok(sink) => {
+                    debug!("Enabled presence sink: {}", sink.name());
+                    Some(sink)
+                }
+                Err(e) => {
+                    warn!("Skipping [[sink]] {:?}: {}", sink_config, e);
+                    None
+                }
+            })
+            .collect();
+        Self { sinks }
+    }
+
+    fn create_sink(config: &SinkConfig) -> Result<Box<dyn PresenceSink>, SinkError> {
+        Ok(match config {
+            SinkConfig::Jsonl { path } => Box::new(JsonLinesSink::open(path)?),
+            SinkConfig::Webhook { url, headers } => Box::new(WebhookSink::new(url, headers)),
+            SinkConfig::Http {
+                port,
+                allowed_origins,
+            } => Box::new(HttpSink::bind(*port, allowed_origins)?),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    pub fn wants_local_cover(&self) -> bool {
+        self.sinks.iter().any(|sink| sink.wants_local_cover())
+    }
+
+    pub fn publish(&self, activity: &SinkActivity) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.publish(activity) {
+                warn!("Presence sink {} failed to publish: {}", sink.name(), e);
+            }
+        }
+    }
+
+    pub fn clear(&self, player: &SinkPlayer) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.clear(player) {
+                warn!("Presence sink {} failed to clear: {}", sink.name(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::metadata::MediaMetadata;
+    use serde_json::Value;
+
+    pub fn activity() -> SinkActivity {
+        SinkActivity {
+            player: SinkPlayer {
+                identity: "Elisa".to_string(),
+                bus_name: "elisa".to_string(),
+            },
+            activity_type: ActivityType::Listening,
+            status_display_type: StatusDisplayType::State,
+            details: "Get Lucky".to_string(),
+            state: "Daft Punk".to_string(),
+            large_text: "Random Access Memories".to_string(),
+            small_text: String::new(),
+            buttons: Vec::new(),
+            cover_url: Some("https://example.com/cover.jpg".to_string()),
+            icon: "elisa".to_string(),
+            show_icon: false,
+            timestamps: Some(SinkTimestamps {
+                start: 1_700_000_000,
+                end: None,
+            }),
+            context: RenderContext {
+                player: "Elisa".to_string(),
+                player_bus_name: "elisa".to_string(),
+                status: Some("Playing".to_string()),
+                status_icon: Some("▶".to_string()),
+                volume: None,
+                position_secs: None,
+                position_display: None,
+                progress_percent: None,
+                lyric_line: None,
+                metadata: MediaMetadata {
+                    title: Some("Get Lucky".to_string()),
+                    ..Default::default()
+                },
+            },
+            local_cover: None,
+        }
+    }
+
+    #[test]
+    fn events_are_tagged_and_flattened() {
+        let activity = activity();
+        let value = serde_json::to_value(SinkEvent::Activity(&activity)).unwrap();
+        assert_eq!(value["event"], "activity");
+        assert_eq!(value["details"], "Get Lucky");
+        assert_eq!(value["activity_type"], "listening");
+        assert_eq!(value["player"]["identity"], "Elisa");
+        assert_eq!(value["timestamps"]["end"], Value::Null);
+        assert_eq!(value["context"]["title"], "Get Lucky");
+        assert!(value.get("local_cover").is_none());
+
+        let value = serde_json::to_value(SinkEvent::Clear {
+            player: &activity.player,
+        })
+        .unwrap();
+        assert_eq!(value["event"], "clear");
+        assert_eq!(value["player"]["bus_name"], "elisa");
+    }
+
+    #[test]
+    fn redacted_tracks_serialize_without_identifying_tags() {
+        use crate::config::schema::RedactField;
+
+        let mut activity = activity();
+        activity.context.metadata = MediaMetadata {
+            title: Some("Get Lucky".to_string()),
+            track_display: Some("8/13".to_string()),
+            isrc: Some("USQX91300108".to_string()),
+            barcode: Some("888837168612".to_string()),
+            catalog_number: Some("88883716861".to_string()),
+            musicbrainz_track_id: Some("0d0e4f7b".to_string()),
+            musicbrainz_album_id: Some("aa997ea0".to_string()),
+            musicbrainz_artist_id: Some("056e4f3e".to_string()),
+            musicbrainz_release_group_id: Some("4e8e2fdb".to_string()),
+            composer: Some("Pharrell Williams".to_string()),
+            remixer: Some("Someone".to_string()),
+            comment: Some("Grammy winner".to_string()),
+            ..Default::default()
+        };
+        activity
+            .context
+            .metadata
+            .redact(&[RedactField::Title], "Private");
+
+        let value = serde_json::to_value(SinkEvent::Activity(&activity)).unwrap();
+        let context = &value["context"];
+        assert_eq!(context["title"], "Private");
+        for key in [
+            "track_display",
+            "isrc",
+            "barcode",
+            "catalog_number",
+            "musicbrainz_track_id",
+            "musicbrainz_album_id",
+            "musicbrainz_artist_id",
+            "musicbrainz_release_group_id",
+            "composer",
+            "remixer",
+            "comment",
+            "lyric_line",
+        ] {
+            assert_eq!(context[key], Value::Null, "{key} leaked");
+        }
+    }
+}
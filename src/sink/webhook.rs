@@ -0,0 +1,112 @@
+//! Webhook output: every [`SinkEvent`] POSTed as JSON, for home dashboards
+//! and overlays that would rather be told than poll.
+
+use std::{collections::HashMap, time::Duration};
+
+use log::{debug, warn};
+use reqwest::{header, Client};
+use tokio::sync::mpsc;
+
+use super::{error::SinkError, PresenceSink, SinkActivity, SinkEvent, SinkPlayer};
+
+const SINK: &str = "webhook";
+
+/// Events waiting for delivery. Presence updates are bursty but small;
+/// a receiver this far behind is down, and newer events are dropped
+/// rather than piling up.
+const QUEUE_LEN: usize = 32;
+
+const USER_AGENT: &str = concat!("mprisence/", env!("CARGO_PKG_VERSION"));
+
+pub struct WebhookSink {
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl WebhookSink {
+    /// Start the delivery task for `url`. Requests are sent one at a time
+    /// in order. Once [`QUEUE_LEN`] are waiting, new events are dropped, so
+    /// a receiver that far behind misses the latest state until the next
+    /// update gets through.
+    pub fn new(url: &str, headers: &HashMap<String, String>) -> Self {
+        let (tx, mut rx) = mpsc::channel::<Vec<u8>>(QUEUE_LEN);
+        let client = create_client();
+        let url = url.to_string();
+        let headers = headers.clone();
+        tokio::spawn(async move {
+            while let Some(body) = rx.recv().await {
+                let mut request = client
+                    .post(&url)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(body);
+                for (name, value) in &headers {
+                    request = request.header(name.as_str(), value.as_str());
+                }
+                match request.send().await {
+                    Ok(response) if response.status().is_success() => {
+                        debug!("Delivered presence event to {}", url);
+                    }
+                    Ok(response) => {
+                        warn!("Webhook {} answered {}", url, response.status());
+                    }
+                    Err(e) => warn!("Failed to deliver presence event to {}: {}", url, e),
+                }
+            }
+            debug!("Webhook sink for {} stopped", url);
+        });
+        Self { tx }
+    }
+
+    fn send(&self, event: &SinkEvent<'_>) -> Result<(), SinkError> {
+        let body = serde_json::to_vec(event)?;
+        self.tx.try_send(body).map_err(|_| SinkError::Backlog(SINK))
+    }
+}
+
+fn create_client() -> Client {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default()
+}
+
+impl PresenceSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        SINK
+    }
+
+    fn publish(&self, activity: &SinkActivity) -> Result<(), SinkError> {
+        self.send(&SinkEvent::Activity(activity))
+    }
+
+    fn clear(&self, player: &SinkPlayer) -> Result<(), SinkError> {
+        self.send(&SinkEvent::Clear { player })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::activity;
+    use crate::test_util::spawn_server;
+    use serde_json::Value;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn posts_events_in_order() {
+        let (url, rx) = spawn_server(vec![(200, ""), (500, "")]);
+        let sink = WebhookSink::new(&format!("{url}/hook"), &HashMap::new());
+        let activity = activity();
+        sink.publish(&activity).unwrap();
+        sink.clear(&activity.player).unwrap();
+
+        let request = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(request.url, "/hook");
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["event"], "activity");
+        assert_eq!(body["state"], "Daft Punk");
+
+        let request = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["event"], "clear");
+    }
+}
@@ -1,16 +1,33 @@
-use log::{debug, error, info, trace};
-use std::sync::Arc;
+use log::{debug, error, info, trace, warn};
+use std::{collections::HashSet, sync::Arc};
 
-use handlebars::{handlebars_helper, no_escape, Handlebars};
+use handlebars::{
+    handlebars_helper, no_escape, Context, Handlebars, Helper, HelperDef, JsonValue, RenderError,
+    ScopedJson,
+};
 use handlebars_misc_helpers::regex_helpers;
-use mpris::{PlaybackStatus, Player};
-use serde::Serialize;
+use mpris::PlaybackStatus;
+use serde::{Deserialize, Serialize};
+use url::Url;
 
 use crate::{
-    config::ConfigManager, error::TemplateError, metadata::MediaMetadata,
-    player::canonical_player_bus_name, utils::format_playback_status_icon,
+    config::{
+        schema::{ButtonTemplate, PlayerConfig, ProgressConfig, TemplateConfigLayer},
+        ConfigManager,
+    },
+    error::TemplateError,
+    metadata::MediaMetadata,
+    player::{canonical_player_bus_name, MediaPlayer},
+    utils::{format_duration, format_playback_status_icon},
 };
 
+/// Discord shows at most two buttons on an activity.
+const MAX_ACTIVITY_BUTTONS: usize = 2;
+/// Discord rejects the whole activity when a button label exceeds this.
+const MAX_BUTTON_LABEL_CHARS: usize = 32;
+/// Discord rejects the whole activity when a button URL exceeds this.
+const MAX_BUTTON_URL_CHARS: usize = 512;
+
 /// A struct containing all variables available for template rendering,
 /// including player state and media metadata.
 #[derive(Debug, Clone, Serialize)]
@@ -20,6 +37,14 @@ pub struct RenderContext {
     pub status: Option<String>,
     pub status_icon: Option<String>,
     pub volume: Option<f64>,
+    /// Playback position when rendered, in seconds.
+    pub position_secs: Option<u64>,
+    /// Position as "MM:SS", like `duration_display`.
+    pub position_display: Option<String>,
+    /// Position as a percentage (0-100) of the duration, if known.
+    pub progress_percent: Option<u8>,
+    /// Synced lyric line at the player's position, when `[lyrics]` is on.
+    pub lyric_line: Option<String>,
 
     #[serde(flatten)]
     pub metadata: MediaMetadata,
@@ -27,13 +52,19 @@ pub struct RenderContext {
 
 impl RenderContext {
     pub fn new(
-        player: &Player,
+        player: &MediaPlayer,
         playback_status: PlaybackStatus,
         metadata: MediaMetadata,
         name_override: Option<&str>,
     ) -> Self {
         let status = Some(format!("{:?}", playback_status));
         let status_icon = Some(format_playback_status_icon(playback_status).to_string());
+        let position = player.get_position().ok();
+        let progress_percent = position
+            .zip(metadata.duration_secs.filter(|secs| *secs > 0))
+            .map(|(position, duration)| {
+                (position.as_secs_f64() * 100.0 / duration as f64).min(100.0) as u8
+            });
 
         Self {
             player: name_override
@@ -43,6 +74,10 @@ impl RenderContext {
             status,
             status_icon,
             volume: player.get_volume().ok(),
+            position_secs: position.map(|position| position.as_secs()),
+            position_display: position.map(|position| format_duration(position.as_secs())),
+            progress_percent,
+            lyric_line: None,
             metadata,
         }
     }
@@ -50,24 +85,98 @@ impl RenderContext {
 
 pub struct TemplateManager {
     handlebars: Handlebars<'static>,
+    /// Number of `template.buttons` entries registered as
+    /// `button_label_{i}` / `button_url_{i}`.
+    button_count: usize,
+    /// Every labelled template set compiled at load, by set name (e.g.
+    /// `player.vlc`). Each field is registered as `{set}.{field}`.
+    player_templates: HashSet<Box<str>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivityTexts {
     pub details: String,
     pub state: String,
     pub large_text: String,
     pub small_text: String,
+    pub buttons: Vec<ActivityButton>,
+}
+
+/// Rendered `[bar]` templates for `mprisence bar`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarTexts {
+    pub text: String,
+    pub tooltip: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityButton {
+    pub label: String,
+    pub url: String,
 }
 
 handlebars_helper!(eq: |x: str, y: str| x == y);
 handlebars_helper!(contains: |haystack: str, needle: str| haystack.contains(needle));
 handlebars_helper!(icontains: |haystack: str, needle: str| haystack.to_lowercase().contains(&needle.to_lowercase()));
+handlebars_helper!(urlencode: |s: str| urlencoding::encode(s).into_owned());
+
+/// `{{progress_bar}}`: `progress_percent` as a bar of `[progress]` glyphs.
+/// A number argument (0-100) replaces `progress_percent`; `width`, `filled`
+/// and `empty` hash arguments override the config.
+struct ProgressBarHelper {
+    width: usize,
+    filled: String,
+    empty: String,
+}
 
-fn register_template_helpers(handlebars: &mut Handlebars<'static>) {
+impl HelperDef for ProgressBarHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut handlebars::RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let percent = h
+            .param(0)
+            .map(|param| param.value())
+            .or_else(|| ctx.data().get("progress_percent"))
+            .and_then(JsonValue::as_f64);
+        let Some(percent) = percent else {
+            return Ok(ScopedJson::Derived(JsonValue::Null));
+        };
+        let width = h
+            .hash_get("width")
+            .and_then(|width| width.value().as_u64())
+            .map_or(self.width, |width| width as usize);
+        let glyph = |key: &str| h.hash_get(key).and_then(|glyph| glyph.value().as_str());
+        Ok(ScopedJson::Derived(JsonValue::String(progress_bar(
+            percent,
+            width,
+            glyph("filled").unwrap_or(&self.filled),
+            glyph("empty").unwrap_or(&self.empty),
+        ))))
+    }
+}
+
+fn progress_bar(percent: f64, width: usize, filled: &str, empty: &str) -> String {
+    let filled_cells = ((percent.clamp(0.0, 100.0) / 100.0) * width as f64).round() as usize;
+    filled.repeat(filled_cells) + &empty.repeat(width - filled_cells)
+}
+
+fn register_template_helpers(handlebars: &mut Handlebars<'static>, progress: &ProgressConfig) {
     handlebars.register_helper("eq", Box::new(eq));
     handlebars.register_helper("contains", Box::new(contains));
     handlebars.register_helper("icontains", Box::new(icontains));
+    handlebars.register_helper("urlencode", Box::new(urlencode));
+    handlebars.register_helper(
+        "progress_bar",
+        Box::new(ProgressBarHelper {
+            width: progress.bar_width,
+            filled: progress.bar_filled.clone(),
+            empty: progress.bar_empty.clone(),
+        }),
+    );
     regex_helpers::register(handlebars);
 }
 
@@ -79,7 +188,7 @@ impl TemplateManager {
         let template_config = config.template_config();
 
         trace!("Registering custom template helpers");
-        register_template_helpers(&mut handlebars);
+        register_template_helpers(&mut handlebars, &config.progress_config());
 
         handlebars
             .register_template_string("details", &template_config.details)
@@ -106,8 +215,56 @@ impl TemplateManager {
                 e
             })?;
 
+        let bar_config = config.bar_config();
+        for (name, source) in [
+            ("bar.text", &bar_config.text),
+            ("bar.tooltip", &bar_config.tooltip),
+        ] {
+            handlebars
+                .register_template_string(name, source)
+                .map_err(|e| {
+                    error!("Failed to register '{}' template: {}", name, e);
+                    e
+                })?;
+        }
+
+        for (index, button) in template_config.buttons.iter().enumerate() {
+            handlebars
+                .register_template_string(&format!("button_label_{index}"), &button.label)
+                .map_err(|e| {
+                    error!(
+                        "Failed to register label template for button {}: {}",
+                        index, e
+                    );
+                    e
+                })?;
+            handlebars
+                .register_template_string(&format!("button_url_{index}"), &button.url)
+                .map_err(|e| {
+                    error!(
+                        "Failed to register url template for button {}: {}",
+                        index, e
+                    );
+                    e
+                })?;
+        }
+
+        let mut player_templates = HashSet::new();
+        for (template, buttons) in config.template_sets() {
+            register_player_templates(
+                &mut handlebars,
+                &mut player_templates,
+                template.as_ref(),
+                buttons.as_deref(),
+            )?;
+        }
+
         debug!("Template manager initialization completed successfully");
-        Ok(Self { handlebars })
+        Ok(Self {
+            handlebars,
+            button_count: template_config.buttons.len(),
+            player_templates,
+        })
     }
 
     /// Create a TemplateManager from raw template strings (tests, config UI preview).
@@ -120,14 +277,18 @@ impl TemplateManager {
         let mut handlebars = Handlebars::new();
         handlebars.register_escape_fn(no_escape);
 
-        register_template_helpers(&mut handlebars);
+        register_template_helpers(&mut handlebars, &ProgressConfig::default());
 
         handlebars.register_template_string("details", details)?;
         handlebars.register_template_string("state", state)?;
         handlebars.register_template_string("large_text", large_text)?;
         handlebars.register_template_string("small_text", small_text)?;
 
-        Ok(Self { handlebars })
+        Ok(Self {
+            handlebars,
+            button_count: 0,
+            player_templates: HashSet::new(),
+        })
     }
 
     pub fn render(
@@ -142,23 +303,89 @@ impl TemplateManager {
         })
     }
 
+    /// Name a per-player template was compiled under at load, if its set
+    /// was registered.
+    fn player_template_name(&self, set: Option<&str>, name: &str) -> Option<String> {
+        let set = set.filter(|set| self.player_templates.contains(*set))?;
+        Some(format!("{set}.{name}"))
+    }
+
+    fn render_field(
+        &self,
+        name: &str,
+        layer: Option<&TemplateConfigLayer>,
+        context: &RenderContext,
+    ) -> Result<String, TemplateError> {
+        let Some(layer) = layer.filter(|layer| layer.entries().any(|(field, _)| field == name))
+        else {
+            return self.render(name, context);
+        };
+        match self.player_template_name(layer.set(name), name) {
+            Some(template_name) => self.render(&template_name, context),
+            None => {
+                warn!(
+                    "Per-player '{}' template was not compiled at load, using the global one",
+                    name
+                );
+                self.render(name, context)
+            }
+        }
+    }
+
+    /// Render activity buttons. `overrides` comes from the resolved player
+    /// config and replaces the globally registered `template.buttons`.
+    pub fn render_buttons(
+        &self,
+        overrides: Option<&[ButtonTemplate]>,
+        context: &RenderContext,
+    ) -> Result<Vec<ActivityButton>, TemplateError> {
+        let mut rendered = Vec::new();
+        match overrides {
+            Some(buttons) => {
+                for (index, button) in buttons.iter().enumerate() {
+                    let set = button.set.as_deref();
+                    let (Some(label), Some(url)) = (
+                        self.player_template_name(set, &format!("button_label_{index}")),
+                        self.player_template_name(set, &format!("button_url_{index}")),
+                    ) else {
+                        warn!(
+                            "Button '{}' was not compiled at load, skipping it",
+                            button.label
+                        );
+                        continue;
+                    };
+                    rendered.push((self.render(&label, context)?, self.render(&url, context)?));
+                }
+            }
+            None => {
+                for index in 0..self.button_count {
+                    rendered.push((
+                        self.render(&format!("button_label_{index}"), context)?,
+                        self.render(&format!("button_url_{index}"), context)?,
+                    ));
+                }
+            }
+        }
+        Ok(sanitize_buttons(rendered))
+    }
+
     pub fn render_activity_texts(
         &self,
-        player: &Player,
-        playback_status: PlaybackStatus,
-        metadata: MediaMetadata,
-        name_override: Option<&str>,
+        render_context: &RenderContext,
+        player_config: &PlayerConfig,
     ) -> Result<ActivityTexts, TemplateError> {
-        trace!("Creating activity texts for player: {}", player.identity());
-
-        debug!("Creating render context with player and metadata information");
-        let render_context = RenderContext::new(player, playback_status, metadata, name_override);
+        trace!(
+            "Creating activity texts for player: {}",
+            render_context.player
+        );
 
         trace!("Rendering all activity text templates");
-        let details = self.render("details", &render_context)?;
-        let state_text = self.render("state", &render_context)?;
-        let large_text = self.render("large_text", &render_context)?;
-        let small_text = self.render("small_text", &render_context)?;
+        let layer = player_config.template.as_ref();
+        let details = self.render_field("details", layer, render_context)?;
+        let state_text = self.render_field("state", layer, render_context)?;
+        let large_text = self.render_field("large_text", layer, render_context)?;
+        let small_text = self.render_field("small_text", layer, render_context)?;
+        let buttons = self.render_buttons(player_config.buttons.as_deref(), render_context)?;
 
         trace!("Activity text rendering completed successfully");
         Ok(ActivityTexts {
@@ -166,14 +393,118 @@ impl TemplateManager {
             state: state_text,
             large_text,
             small_text,
+            buttons,
+        })
+    }
+
+    /// Render the `[bar]` templates. The player's `name` override applies,
+    /// its `[template]` layer does not.
+    pub fn render_bar_texts(
+        &self,
+        player: &MediaPlayer,
+        playback_status: PlaybackStatus,
+        metadata: MediaMetadata,
+        player_config: &PlayerConfig,
+    ) -> Result<BarTexts, TemplateError> {
+        let render_context = RenderContext::new(
+            player,
+            playback_status,
+            metadata,
+            player_config.name.as_deref(),
+        );
+        Ok(BarTexts {
+            text: self.render("bar.text", &render_context)?,
+            tooltip: self.render("bar.tooltip", &render_context)?,
+        })
+    }
+}
+
+/// Compile one labelled template set under `{set}.{field}`, once per set.
+/// Fields and buttons without a label (see `Config::label_template_sets`)
+/// belong to no set and are skipped.
+fn register_player_templates(
+    handlebars: &mut Handlebars<'static>,
+    registered: &mut HashSet<Box<str>>,
+    template: Option<&TemplateConfigLayer>,
+    buttons: Option<&[ButtonTemplate]>,
+) -> Result<(), TemplateError> {
+    let mut sources: Vec<(&str, String, &str)> = template
+        .into_iter()
+        .flat_map(|layer| {
+            layer
+                .entries()
+                .filter_map(|(field, source)| Some((layer.set(field)?, field.to_string(), source)))
         })
+        .collect();
+    for (index, button) in buttons.unwrap_or_default().iter().enumerate() {
+        if let Some(set) = button.set.as_deref() {
+            sources.push((set, format!("button_label_{index}"), &button.label));
+            sources.push((set, format!("button_url_{index}"), &button.url));
+        }
+    }
+
+    for (set, field, source) in sources {
+        let name = format!("{set}.{field}");
+        handlebars
+            .register_template_string(&name, source)
+            .map_err(|e| {
+                error!("Failed to register '{}' template: {}", name, e);
+                e
+            })?;
+        if !registered.contains(set) {
+            trace!("Registered template set {}", set);
+            registered.insert(set.into());
+        }
     }
+    Ok(())
+}
+
+/// Drop buttons Discord would reject: empty labels and URLs that do not
+/// parse, are not http(s) or run past `MAX_BUTTON_URL_CHARS`. Empty URLs
+/// are the common case, e.g. a `{{url}}` template on a local file. Kept
+/// URLs are re-serialized, which percent-encodes stray spaces and other
+/// characters Discord refuses; the length limit applies to that form.
+fn sanitize_buttons(rendered: Vec<(String, String)>) -> Vec<ActivityButton> {
+    rendered
+        .into_iter()
+        .filter_map(|(label, url)| {
+            let label = label.trim();
+            let url = url.trim();
+            let parsed = Url::parse(url)
+                .ok()
+                .filter(|parsed| matches!(parsed.scheme(), "http" | "https"))
+                .filter(|parsed| parsed.as_str().chars().count() <= MAX_BUTTON_URL_CHARS);
+            let Some(parsed) = parsed.filter(|_| !label.is_empty()) else {
+                trace!("Dropping activity button '{}' -> '{}'", label, url);
+                return None;
+            };
+            Some(ActivityButton {
+                label: label.chars().take(MAX_BUTTON_LABEL_CHARS).collect(),
+                url: parsed.into(),
+            })
+        })
+        .take(MAX_ACTIVITY_BUTTONS)
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{RenderContext, TemplateManager};
-    use crate::{error::TemplateError, metadata::MediaMetadata};
+    use std::{collections::HashSet, sync::Arc};
+
+    use super::{
+        register_player_templates, sanitize_buttons, ActivityButton, RenderContext, TemplateManager,
+    };
+    use crate::{
+        config::{
+            schema::{
+                ButtonTemplate, Config, PlayerConfigLayer, TemplateConfigLayer,
+                WebPlayerConfigLayer,
+            },
+            ConfigManager,
+        },
+        error::TemplateError,
+        metadata::MediaMetadata,
+    };
 
     fn test_context() -> RenderContext {
         RenderContext {
@@ -182,6 +513,10 @@ mod tests {
             status: Some("Playing".into()),
             status_icon: Some(">".into()),
             volume: Some(0.5),
+            position_secs: None,
+            position_display: None,
+            progress_percent: None,
+            lyric_line: None,
             metadata: MediaMetadata {
                 title: Some("Song Title".into()),
                 artist_display: Some("Artist Name".into()),
@@ -190,6 +525,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn renders_default_bar_templates() {
+        let config = std::sync::Arc::new(crate::config::ConfigManager::new_with_config(
+            crate::config::Config::default(),
+        ));
+        let manager = TemplateManager::new(&config).expect("default templates compile");
+        let context = test_context();
+
+        assert_eq!(
+            manager.render("bar.text", &context).unwrap(),
+            "> Artist Name - Song Title"
+        );
+        assert_eq!(
+            manager.render("bar.tooltip", &context).unwrap(),
+            "Song Title\nArtist Name\nSpotify Desktop"
+        );
+    }
+
     #[test]
     fn renders_contains_helper() {
         let manager = TemplateManager::new_raw(
@@ -224,6 +577,33 @@ mod tests {
         assert_eq!(rendered, "match");
     }
 
+    #[test]
+    fn renders_progress_bar_helper() {
+        let manager = TemplateManager::new_raw(
+            "{{position_display}} {{progress_bar}} {{duration_display}}",
+            "{{progress_bar width=4 filled=\"#\" empty=\"-\"}}",
+            "{{progress_bar 100 width=3}}",
+            "[{{progress_bar}}]",
+        )
+        .expect("template manager should initialize");
+        let mut context = test_context();
+        context.position_secs = Some(83);
+        context.position_display = Some("01:23".into());
+        context.progress_percent = Some(38);
+        context.metadata.duration_display = Some("03:35".into());
+
+        assert_eq!(
+            manager.render("details", &context).unwrap(),
+            "01:23 ████░░░░░░ 03:35"
+        );
+        assert_eq!(manager.render("state", &context).unwrap(), "##--");
+        assert_eq!(manager.render("large_text", &context).unwrap(), "███");
+
+        // No duration (e.g. a stream): no bar.
+        context.progress_percent = None;
+        assert_eq!(manager.render("small_text", &context).unwrap(), "[]");
+    }
+
     #[test]
     fn renders_regex_is_match_helper() {
         let manager = TemplateManager::new_raw(
@@ -275,4 +655,260 @@ mod tests {
             other => panic!("unexpected template error: {other:?}"),
         }
     }
+
+    #[test]
+    fn renders_button_overrides_from_context() {
+        let mut manager = TemplateManager::new_raw("", "", "", "").expect("template manager");
+        let set: Arc<str> = "player.bandcamp".into();
+        let buttons = [
+            ButtonTemplate {
+                label: "Search {{title}}".into(),
+                url: "https://bandcamp.com/search?q={{title}}".into(),
+                set: Some(set.clone()),
+            },
+            ButtonTemplate {
+                label: "Lyrics".into(),
+                url:
+                    "https://genius.com/search?q={{urlencode artist_display}}%20{{urlencode title}}"
+                        .into(),
+                set: Some(set),
+            },
+        ];
+        register_player_templates(
+            &mut manager.handlebars,
+            &mut manager.player_templates,
+            None,
+            Some(&buttons),
+        )
+        .expect("buttons should register");
+
+        let rendered = manager
+            .render_buttons(Some(&buttons), &test_context())
+            .expect("buttons should render");
+
+        assert_eq!(
+            rendered,
+            vec![
+                ActivityButton {
+                    label: "Search Song Title".into(),
+                    url: "https://bandcamp.com/search?q=Song%20Title".into(),
+                },
+                ActivityButton {
+                    label: "Lyrics".into(),
+                    url: "https://genius.com/search?q=Artist%20Name%20Song%20Title".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn urlencode_helper_escapes_query_values() {
+        let manager = TemplateManager::new_raw("?q={{urlencode title}}", "", "", "")
+            .expect("template manager");
+        let mut context = test_context();
+        context.metadata.title = Some("Rock & Roll/Live?".into());
+
+        let rendered = manager.render("details", &context).expect("render");
+        assert_eq!(rendered, "?q=Rock%20%26%20Roll%2FLive%3F");
+    }
+
+    #[test]
+    fn drops_buttons_without_http_url_or_label() {
+        let buttons = sanitize_buttons(vec![
+            ("Empty".into(), "".into()),
+            ("Local".into(), "file:///music/song.flac".into()),
+            ("".into(), "https://example.com".into()),
+            ("Open".into(), " https://example.com/a ".into()),
+            ("Broken".into(), "https://exa mple.com/".into()),
+            ("Second".into(), "http://example.com/b".into()),
+            ("Third".into(), "https://example.com/c".into()),
+        ]);
+
+        let urls: Vec<&str> = buttons.iter().map(|b| b.url.as_str()).collect();
+        assert_eq!(urls, ["https://example.com/a", "http://example.com/b"]);
+    }
+
+    #[test]
+    fn drops_buttons_with_urls_discord_rejects_as_too_long() {
+        let at_limit = format!("https://example.com/{}", "a".repeat(512 - 20));
+        let past_limit = format!("{at_limit}b");
+        let buttons = sanitize_buttons(vec![
+            ("Long".into(), past_limit),
+            ("Fits".into(), at_limit.clone()),
+        ]);
+
+        let urls: Vec<&str> = buttons.iter().map(|b| b.url.as_str()).collect();
+        assert_eq!(urls, [at_limit.as_str()]);
+    }
+
+    #[test]
+    fn truncates_long_button_labels() {
+        let buttons = sanitize_buttons(vec![("x".repeat(40), "https://example.com".into())]);
+        assert_eq!(buttons[0].label.chars().count(), 32);
+    }
+
+    #[test]
+    fn player_template_layer_overrides_only_set_fields() {
+        let mut manager =
+            TemplateManager::new_raw("global {{title}}", "global state", "", "").unwrap();
+        let mut layer = TemplateConfigLayer {
+            details: Some("Episode: {{title}}".into()),
+            ..Default::default()
+        };
+        layer.label(&"player.podcasts".into());
+        register_player_templates(
+            &mut manager.handlebars,
+            &mut manager.player_templates,
+            Some(&layer),
+            None,
+        )
+        .expect("player templates should register");
+
+        let context = test_context();
+        assert_eq!(
+            manager
+                .render_field("details", Some(&layer), &context)
+                .unwrap(),
+            "Episode: Song Title"
+        );
+        assert_eq!(
+            manager
+                .render_field("state", Some(&layer), &context)
+                .unwrap(),
+            "global state"
+        );
+        assert!(manager.player_templates.contains("player.podcasts"));
+        assert!(manager.handlebars.has_template("player.podcasts.details"));
+    }
+
+    #[test]
+    fn player_templates_compile_once_per_config_key() {
+        let layer = |template: TemplateConfigLayer| PlayerConfigLayer {
+            template: Some(template),
+            ..Default::default()
+        };
+        let mut config = Config::default();
+        config.user_player.insert(
+            "default".to_string(),
+            layer(TemplateConfigLayer {
+                details: Some("Now: {{title}}".into()),
+                state: Some("by {{artist_display}}".into()),
+                ..Default::default()
+            }),
+        );
+        // Same source as `[player.default]`, registered under its own key.
+        config.user_player.insert(
+            "vlc".to_string(),
+            layer(TemplateConfigLayer {
+                details: Some("Now: {{title}}".into()),
+                ..Default::default()
+            }),
+        );
+        let config = Arc::new(ConfigManager::new_with_config(config));
+        let manager = TemplateManager::new(&config).expect("template manager");
+
+        assert!(manager.player_templates.contains("player.default"));
+        assert!(manager.player_templates.contains("player.vlc"));
+        assert!(manager.handlebars.has_template("player.default.details"));
+        assert!(manager.handlebars.has_template("player.vlc.details"));
+
+        let resolved = config.get_player_config("VLC media player", "vlc");
+        let template = resolved.template.as_ref().unwrap();
+        assert_eq!(template.set("details"), Some("player.vlc"));
+        assert_eq!(template.set("state"), Some("player.default"));
+
+        let texts = manager
+            .render_activity_texts(&test_context(), &resolved)
+            .unwrap();
+        assert_eq!(texts.details, "Now: Song Title");
+        assert_eq!(texts.state, "by Artist Name");
+    }
+
+    /// A config with `[web_player.default]` set to `default` and a
+    /// `[web_player.ytmusic]` entry that matches YouTube Music.
+    fn web_player_config(default: WebPlayerConfigLayer) -> Arc<ConfigManager> {
+        let mut config = Config::default();
+        config
+            .user_web_player
+            .insert("default".to_string(), default);
+        config.user_web_player.insert(
+            "ytmusic".to_string(),
+            WebPlayerConfigLayer {
+                match_pattern: Some("music.youtube.com".into()),
+                ..Default::default()
+            },
+        );
+        config.rebuild_merged_web_player();
+        Arc::new(ConfigManager::new_with_config(config))
+    }
+
+    #[test]
+    fn default_web_player_templates_render_from_their_compiled_set() {
+        let config = web_player_config(WebPlayerConfigLayer {
+            template: Some(TemplateConfigLayer {
+                details: Some("On the web: {{title}}".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let manager = TemplateManager::new(&config).expect("template manager");
+
+        let (resolved, _) = config.get_player_config_with_title_fallback(
+            "Firefox",
+            "firefox",
+            Some("https://music.youtube.com/watch?v=abc"),
+            None,
+        );
+        let template = resolved.template.as_ref().unwrap();
+        assert_eq!(template.set("details"), Some("web_player.default"));
+
+        let texts = manager
+            .render_activity_texts(&test_context(), &resolved)
+            .unwrap();
+        assert_eq!(texts.details, "On the web: Song Title");
+    }
+
+    #[test]
+    fn default_web_player_buttons_are_shown() {
+        let config = web_player_config(WebPlayerConfigLayer {
+            buttons: Some(vec![ButtonTemplate {
+                label: "Listen along".into(),
+                url: "{{{url}}}".into(),
+                set: None,
+            }]),
+            ..Default::default()
+        });
+        let manager = TemplateManager::new(&config).expect("template manager");
+
+        let url = "https://music.youtube.com/watch?v=abc";
+        let (resolved, _) =
+            config.get_player_config_with_title_fallback("Firefox", "firefox", Some(url), None);
+        let mut context = test_context();
+        context.metadata.url = Some(url.into());
+
+        let texts = manager.render_activity_texts(&context, &resolved).unwrap();
+        assert_eq!(
+            texts.buttons,
+            vec![ActivityButton {
+                label: "Listen along".into(),
+                url: url.into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_player_template_fails_registration() {
+        let mut manager = TemplateManager::new_raw("", "", "", "").unwrap();
+        let mut registered = HashSet::new();
+        let mut layer = TemplateConfigLayer {
+            state: Some("{{#if}}".into()),
+            ..Default::default()
+        };
+        layer.label(&"player.broken".into());
+
+        let result =
+            register_player_templates(&mut manager.handlebars, &mut registered, Some(&layer), None);
+
+        assert!(matches!(result, Err(TemplateError::HandlebarsTemplate(_))));
+    }
 }
@@ -0,0 +1,77 @@
+//! Helpers shared by unit tests across modules.
+
+use std::sync::mpsc;
+use std::thread;
+
+/// A request received by [`spawn_server`].
+pub struct Captured {
+    pub method: String,
+    /// Path and query string.
+    pub url: String,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Captured {
+    /// Value of header `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// A local stand-in for an HTTP API: answers requests in order with the
+/// scripted `(status, body)` replies, sending each request back. Returns
+/// the base URL, without a trailing slash.
+pub fn spawn_server(replies: Vec<(u16, &'static str)>) -> (String, mpsc::Receiver<Captured>) {
+    let server = tiny_http::Server::http("127.0.0.1:0").expect("bind test server");
+    let addr = server.server_addr().to_ip().expect("ip listener");
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for (status, reply) in replies {
+            let Ok(mut request) = server.recv() else {
+                return;
+            };
+            let headers = request
+                .headers()
+                .iter()
+                .map(|h| (h.field.to_string(), h.value.to_string()))
+                .collect();
+            let mut body = Vec::new();
+            let _ = request.as_reader().read_to_end(&mut body);
+            let _ = tx.send(Captured {
+                method: request.method().as_str().to_string(),
+                url: request.url().to_string(),
+                headers,
+                body,
+            });
+            let _ =
+                request.respond(tiny_http::Response::from_string(reply).with_status_code(status));
+        }
+    });
+    (format!("http://{addr}"), rx)
+}
+
+/// A stand-in for MPD on one connection: greets, then answers each
+/// expected command line with its canned response, in order.
+pub async fn fake_mpd<S>(stream: S, script: Vec<(&'static str, &'static str)>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut stream = BufReader::new(stream);
+    stream.write_all(b"OK MPD 0.23.5\n").await.unwrap();
+    for (expected, response) in script {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        assert_eq!(line.trim_end(), expected);
+        stream.write_all(response.as_bytes()).await.unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 use mime_guess::Mime;
 use mpris::PlaybackStatus;
 use semver::Version;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 use url::Url;
 
@@ -32,6 +33,20 @@ pub fn normalize_art_url(url: &str) -> String {
     format!("{}://{}{}", scheme, host, path)
 }
 
+/// Expand a leading `~/` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Replace the leading `from` of `path` with `to`. Matches whole
+/// components, so `/music` does not match `/musical`.
+pub fn replace_path_prefix(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    path.strip_prefix(from).ok().map(|rest| to.join(rest))
+}
+
 pub fn format_duration(seconds: u64) -> String {
     let hours = seconds / 3600;
     let minutes = (seconds % 3600) / 60;
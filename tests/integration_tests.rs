@@ -106,6 +106,10 @@ fn test_template_manager_with_config() {
         status: Some("Playing".to_string()),
         status_icon: Some("▶".to_string()),
         volume: Some(1.0),
+        position_secs: None,
+        position_display: None,
+        progress_percent: None,
+        lyric_line: None,
         metadata: metadata.clone(),
     };
 
@@ -133,6 +137,10 @@ fn test_template_manager_with_config() {
         status: None,
         status_icon: None,
         volume: Some(1.0),
+        position_secs: None,
+        position_display: None,
+        progress_percent: None,
+        lyric_line: None,
         metadata: metadata.clone(),
     };
 